@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// An earlier message of a conversation, sent along with a moderation
+/// request as context.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextMessage {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub text: String,
+}
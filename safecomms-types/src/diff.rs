@@ -0,0 +1,247 @@
+use crate::ModerationResponse;
+use std::ops::Range;
+
+/// The most cells the longest common subsequence table may have, about 16
+/// MB; larger diffs are matched more coarsely.
+const MAX_TABLE_CELLS: usize = 4_000_000;
+
+/// A piece of the original content that `safe_content` replaced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    /// Byte range in the original content.
+    pub range: Range<usize>,
+    pub original: String,
+    /// What `safe_content` has instead; empty if the text was removed.
+    pub replacement: String,
+}
+
+impl ModerationResponse {
+    /// What `safe_content` changed in `original`, the text that was
+    /// moderated, in order. Empty when the response has no safe content.
+    pub fn diff(&self, original: &str) -> Vec<Replacement> {
+        match &self.safe_content {
+            Some(safe_content) => diff(original, safe_content),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Splits text into alternating runs of whitespace and non-whitespace, as
+/// byte ranges.
+fn tokens(text: &str) -> Vec<Range<usize>> {
+    let mut tokens: Vec<Range<usize>> = Vec::new();
+    let mut previous = None;
+
+    for (offset, c) in text.char_indices() {
+        let whitespace = c.is_whitespace();
+        match tokens.last_mut() {
+            Some(token) if previous == Some(whitespace) => token.end = offset + c.len_utf8(),
+            _ => tokens.push(offset..offset + c.len_utf8()),
+        }
+        previous = Some(whitespace);
+    }
+    tokens
+}
+
+/// Diffs word by word, then trims each changed run down to the characters
+/// that actually differ, so `"fucking"` masked as `"****ing"` reports just
+/// `"fuck"`.
+fn diff(original: &str, safe: &str) -> Vec<Replacement> {
+    let old = tokens(original);
+    let new = tokens(safe);
+    let same = |i: usize, j: usize| original[old[i].clone()] == safe[new[j].clone()];
+
+    // Unchanged tokens at either end are common and need no table.
+    let prefix = (0..old.len().min(new.len())).take_while(|&i| same(i, i)).count();
+    let suffix = (0..old.len().min(new.len()) - prefix)
+        .take_while(|&k| same(old.len() - 1 - k, new.len() - 1 - k))
+        .count();
+    let (n, m) = (old.len() - prefix - suffix, new.len() - prefix - suffix);
+    let offset = |tokens: &[Range<usize>], index: usize, len: usize| {
+        tokens.get(prefix + index).map_or(len, |token| token.start)
+    };
+
+    // Every changed run lies between two consecutive matches; the end of
+    // both sides closes the last one.
+    let mut replacements = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let matches = matches(n, m, |i, j| same(prefix + i, prefix + j));
+    for (next_i, next_j) in matches.into_iter().chain([(n, m)]) {
+        if next_i > i || next_j > j {
+            replacements.extend(trimmed(
+                original,
+                offset(&old, i, original.len())..offset(&old, next_i, original.len()),
+                safe,
+                offset(&new, j, safe.len())..offset(&new, next_j, safe.len()),
+            ));
+        }
+        (i, j) = (next_i + 1, next_j + 1);
+    }
+
+    replacements
+}
+
+/// The pairs of tokens, in order, that are kept unchanged between `n` old
+/// and `m` new tokens.
+///
+/// This is their longest common subsequence while its table stays within
+/// [`MAX_TABLE_CELLS`]. Past that, masking, which keeps every token in
+/// place, is matched position by position, and anything else is treated as
+/// one change.
+fn matches(n: usize, m: usize, same: impl Fn(usize, usize) -> bool) -> Vec<(usize, usize)> {
+    if (n + 1).saturating_mul(m + 1) > MAX_TABLE_CELLS {
+        return if n == m {
+            (0..n).filter(|&i| same(i, i)).map(|i| (i, i)).collect()
+        } else {
+            Vec::new()
+        };
+    }
+
+    // lengths[i * (m + 1) + j] is the longest common subsequence of the
+    // tokens from i and j onwards.
+    let width = m + 1;
+    let mut lengths = vec![0u32; (n + 1) * width];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i * width + j] = if same(i, j) {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if same(i, j) {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Narrows a changed run to where the two sides differ.
+fn trimmed(
+    original: &str,
+    mut old: Range<usize>,
+    safe: &str,
+    mut new: Range<usize>,
+) -> Option<Replacement> {
+    let common_prefix: usize = original[old.clone()]
+        .chars()
+        .zip(safe[new.clone()].chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum();
+    old.start += common_prefix;
+    new.start += common_prefix;
+
+    let common_suffix: usize = original[old.clone()]
+        .chars()
+        .rev()
+        .zip(safe[new.clone()].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum();
+    old.end -= common_suffix;
+    new.end -= common_suffix;
+
+    (!old.is_empty() || !new.is_empty()).then(|| Replacement {
+        original: original[old.clone()].to_string(),
+        replacement: safe[new].to_string(),
+        range: old,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replaced(original: &str, range: Range<usize>, replacement: &str) -> Replacement {
+        Replacement {
+            original: original[range.clone()].to_string(),
+            replacement: replacement.to_string(),
+            range,
+        }
+    }
+
+    #[test]
+    fn masked_word_in_multibyte_text() {
+        let original = "ty kurwa mać";
+        assert_eq!(
+            diff(original, "ty ***** mać"),
+            vec![replaced(original, 3..8, "*****")]
+        );
+    }
+
+    #[test]
+    fn partly_masked_word_reports_only_the_masked_characters() {
+        assert_eq!(diff("fucking", "****ing"), vec![replaced("fucking", 0..4, "****")]);
+
+        let original = "żółć";
+        let start = "żół".len();
+        assert_eq!(
+            diff(original, "żółw"),
+            vec![replaced(original, start..original.len(), "w")]
+        );
+    }
+
+    #[test]
+    fn changes_at_the_start_and_end() {
+        let original = "kurwa, cześć kurwa";
+        let end = original.len();
+        assert_eq!(
+            diff(original, "*****, cześć *****"),
+            vec![
+                replaced(original, 0..5, "*****"),
+                replaced(original, end - 5..end, "*****"),
+            ]
+        );
+    }
+
+    #[test]
+    fn replacement_across_several_words() {
+        let original = "you stupid idiot now";
+        assert_eq!(
+            diff(original, "you [removed] now"),
+            vec![replaced(original, 4..16, "[removed]")]
+        );
+    }
+
+    #[test]
+    fn removed_words() {
+        let original = "hello bad world";
+        assert_eq!(diff(original, "hello world"), vec![replaced(original, 6..10, "")]);
+    }
+
+    #[test]
+    fn masking_past_the_table_limit_is_matched_by_position() {
+        // Changed words at both ends leave no common prefix or suffix to
+        // trim, so the table would have far more than MAX_TABLE_CELLS.
+        let words = "słowo ".repeat(2_100);
+        let original = format!("kurwa {words}kurwa");
+        let end = original.len();
+        assert_eq!(
+            diff(&original, &format!("***** {words}*****")),
+            vec![
+                replaced(&original, 0..5, "*****"),
+                replaced(&original, end - 5..end, "*****"),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_and_identical_texts() {
+        assert_eq!(diff("", ""), vec![]);
+        assert_eq!(diff("zażółć gęślą", "zażółć gęślą"), vec![]);
+        assert_eq!(diff("", "słowo"), vec![replaced("", 0..0, "słowo")]);
+        assert_eq!(diff("słowo", ""), vec![replaced("słowo", 0.."słowo".len(), "")]);
+    }
+}
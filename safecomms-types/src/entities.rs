@@ -0,0 +1,81 @@
+use crate::ModerationResponse;
+use serde::{Deserialize, Serialize};
+
+/// The kind of a [`PiiEntity`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum PiiEntityType {
+    Email,
+    Phone,
+    Address,
+    Ssn,
+    /// A type this version of the SDK does not know, as reported.
+    Other(String),
+}
+
+impl PiiEntityType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PiiEntityType::Email => "email",
+            PiiEntityType::Phone => "phone",
+            PiiEntityType::Address => "address",
+            PiiEntityType::Ssn => "ssn",
+            PiiEntityType::Other(name) => name,
+        }
+    }
+}
+
+impl From<String> for PiiEntityType {
+    fn from(name: String) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "email" => PiiEntityType::Email,
+            "phone" | "phone_number" => PiiEntityType::Phone,
+            "address" => PiiEntityType::Address,
+            "ssn" => PiiEntityType::Ssn,
+            _ => PiiEntityType::Other(name),
+        }
+    }
+}
+
+impl From<PiiEntityType> for String {
+    fn from(kind: PiiEntityType) -> Self {
+        kind.as_str().to_string()
+    }
+}
+
+/// Personal data the API found in the content, reported when PII detection
+/// is requested.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PiiEntity {
+    #[serde(rename = "type")]
+    pub kind: PiiEntityType,
+    /// Offset of the first character, counted in characters.
+    pub start: usize,
+    /// Offset just past the last character, counted in characters.
+    pub end: usize,
+    pub confidence: f64,
+    /// The value with most of it masked, e.g. `j***@example.com`.
+    #[serde(rename = "maskedValue")]
+    pub masked_value: Option<String>,
+}
+
+impl PiiEntity {
+    /// The entity's text in `content`, which must be the content that was
+    /// moderated.
+    pub fn text<'a>(&self, content: &'a str) -> Option<&'a str> {
+        let mut offsets = content
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain(std::iter::once(content.len()));
+        let start = offsets.nth(self.start)?;
+        let end = offsets.nth(self.end.checked_sub(self.start + 1)?)?;
+        content.get(start..end)
+    }
+}
+
+impl ModerationResponse {
+    /// The personal data found, empty unless PII detection was requested.
+    pub fn pii_entities(&self) -> &[PiiEntity] {
+        self.pii_entities.as_deref().unwrap_or_default()
+    }
+}
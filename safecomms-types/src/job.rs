@@ -0,0 +1,18 @@
+use crate::{JobId, ModerationResponse, timestamp};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+/// An asynchronous moderation job. `result` is set once the job has
+/// completed.
+#[derive(Deserialize, Debug)]
+pub struct Job {
+    #[serde(rename = "jobId")]
+    pub job_id: JobId,
+    pub status: String,
+    pub result: Option<ModerationResponse>,
+    #[serde(rename = "createdAt", default, deserialize_with = "timestamp::option::deserialize")]
+    pub created_at: Option<DateTime<Utc>>,
+    /// When the job completed or failed.
+    #[serde(rename = "completedAt", default, deserialize_with = "timestamp::option::deserialize")]
+    pub completed_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+/// The language of the content, sent to the API as an ISO 639-1 code.
+///
+/// Strings convert with `into()`, so `Some("en".into())` still works. Known
+/// codes become their variant, ignoring case; anything else is sent as given.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Language {
+    Arabic,
+    Chinese,
+    Dutch,
+    English,
+    French,
+    German,
+    Hindi,
+    Italian,
+    Japanese,
+    Korean,
+    Polish,
+    Portuguese,
+    Russian,
+    Spanish,
+    Turkish,
+    /// A code this version of the SDK does not know, sent as given.
+    Custom(String),
+}
+
+impl Language {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Language::Arabic => "ar",
+            Language::Chinese => "zh",
+            Language::Dutch => "nl",
+            Language::English => "en",
+            Language::French => "fr",
+            Language::German => "de",
+            Language::Hindi => "hi",
+            Language::Italian => "it",
+            Language::Japanese => "ja",
+            Language::Korean => "ko",
+            Language::Polish => "pl",
+            Language::Portuguese => "pt",
+            Language::Russian => "ru",
+            Language::Spanish => "es",
+            Language::Turkish => "tr",
+            Language::Custom(code) => code,
+        }
+    }
+}
+
+impl From<&str> for Language {
+    fn from(code: &str) -> Self {
+        match code.to_ascii_lowercase().as_str() {
+            "ar" => Language::Arabic,
+            "zh" => Language::Chinese,
+            "nl" => Language::Dutch,
+            "en" => Language::English,
+            "fr" => Language::French,
+            "de" => Language::German,
+            "hi" => Language::Hindi,
+            "it" => Language::Italian,
+            "ja" => Language::Japanese,
+            "ko" => Language::Korean,
+            "pl" => Language::Polish,
+            "pt" => Language::Portuguese,
+            "ru" => Language::Russian,
+            "es" => Language::Spanish,
+            "tr" => Language::Turkish,
+            _ => Language::Custom(code.to_string()),
+        }
+    }
+}
+
+impl From<String> for Language {
+    fn from(code: String) -> Self {
+        match Language::from(code.as_str()) {
+            Language::Custom(_) => Language::Custom(code),
+            language => language,
+        }
+    }
+}
+
+impl From<Language> for String {
+    fn from(language: Language) -> Self {
+        language.as_str().to_string()
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Deserializer};
+use serde_json::Number;
+use std::collections::HashMap;
+
+/// A value the API has sent both as a string and as a number or boolean.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum Scalar {
+    Text(String),
+    Number(Number),
+    Bool(bool),
+}
+
+impl From<Scalar> for String {
+    fn from(scalar: Scalar) -> Self {
+        match scalar {
+            Scalar::Text(text) => text,
+            Scalar::Number(number) => number.to_string(),
+            Scalar::Bool(flag) => flag.to_string(),
+        }
+    }
+}
+
+/// Reads a string that may have been sent as a number.
+pub(crate) fn string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<Scalar>::deserialize(deserializer)?.map(String::from))
+}
+
+/// Reads category scores sent as strings, numbers or a mix, leaving out
+/// categories without a score.
+pub(crate) fn scores<'de, D>(deserializer: D) -> Result<Option<HashMap<String, String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let scores = Option::<HashMap<String, Option<Scalar>>>::deserialize(deserializer)?;
+    Ok(scores.map(|scores| {
+        scores
+            .into_iter()
+            .filter_map(|(category, score)| Some((category, score?.into())))
+            .collect()
+    }))
+}
@@ -0,0 +1,205 @@
+//! The request and response types of the SafeComms API, with their serde
+//! representations and no HTTP client, for services that store or forward
+//! moderation results. The `safecomms` client re-exports all of them.
+
+mod chat;
+mod diff;
+mod entities;
+mod ids;
+mod job;
+mod language;
+mod lenient;
+mod metadata;
+pub mod normalize;
+mod prompt_injection;
+mod replacement;
+#[cfg(feature = "schemars")]
+mod schema;
+mod severity;
+pub mod spans;
+pub mod timestamp;
+
+pub use chat::ContextMessage;
+pub use diff::Replacement;
+pub use entities::{PiiEntity, PiiEntityType};
+pub use ids::{JobId, ProfileId, RequestId};
+pub use job::Job;
+pub use language::Language;
+pub use metadata::Metadata;
+pub use prompt_injection::PromptInjectionResponse;
+pub use replacement::ReplacementStyle;
+pub use severity::{ReplaceSeverity, Severity};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize)]
+pub struct TextModerationRequest<'a> {
+    pub content: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<Language>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replace: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pii: Option<bool>,
+    #[serde(rename = "replaceSeverity", skip_serializing_if = "Option::is_none")]
+    pub replace_severity: Option<ReplaceSeverity>,
+    #[serde(rename = "moderationProfileId", skip_serializing_if = "Option::is_none")]
+    pub moderation_profile_id: Option<&'a ProfileId>,
+    /// How `replace` masks unsafe content and PII.
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub replacement_style: Option<&'a ReplacementStyle>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<&'a Metadata>,
+    /// Earlier messages of the conversation, oldest first, for the API to
+    /// judge the content in context.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<&'a [ContextMessage]>,
+}
+
+/// An owned text moderation request, for APIs that cannot borrow such as
+/// `tower::Service`.
+#[derive(Debug, Clone, Default)]
+pub struct ModerationRequest {
+    pub content: String,
+    pub language: Option<Language>,
+    pub replace: Option<bool>,
+    pub pii: Option<bool>,
+    pub replace_severity: Option<ReplaceSeverity>,
+    pub moderation_profile_id: Option<ProfileId>,
+    pub metadata: Metadata,
+    pub context: Vec<ContextMessage>,
+}
+
+impl ModerationRequest {
+    /// A request for `content` with every option left to the API's default.
+    pub fn new(content: impl Into<String>) -> Self {
+        Self { content: content.into(), ..Self::default() }
+    }
+
+    /// The language of the content, as a [`Language`] or a code such as `"en"`.
+    pub fn language(mut self, language: impl Into<Language>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    pub fn replace(mut self, replace: bool) -> Self {
+        self.replace = Some(replace);
+        self
+    }
+
+    pub fn pii(mut self, pii: bool) -> Self {
+        self.pii = Some(pii);
+        self
+    }
+
+    /// The lowest severity `replace` masks, as a [`ReplaceSeverity`], a
+    /// [`Severity`] or a level such as `"high"`.
+    pub fn replace_severity(mut self, replace_severity: impl Into<ReplaceSeverity>) -> Self {
+        self.replace_severity = Some(replace_severity.into());
+        self
+    }
+
+    pub fn moderation_profile_id(mut self, moderation_profile_id: impl Into<ProfileId>) -> Self {
+        self.moderation_profile_id = Some(moderation_profile_id.into());
+        self
+    }
+
+    pub fn metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Earlier messages of the conversation, oldest first.
+    pub fn context(mut self, context: Vec<ContextMessage>) -> Self {
+        self.context = context;
+        self
+    }
+}
+
+#[derive(Serialize, Default)]
+pub struct ImageModerationRequest<'a> {
+    pub image: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<Language>,
+    #[serde(rename = "moderationProfileId", skip_serializing_if = "Option::is_none")]
+    pub moderation_profile_id: Option<&'a ProfileId>,
+    #[serde(rename = "enableOcr", skip_serializing_if = "Option::is_none")]
+    pub enable_ocr: Option<bool>,
+    #[serde(rename = "enhancedOcr", skip_serializing_if = "Option::is_none")]
+    pub enhanced_ocr: Option<bool>,
+    #[serde(rename = "extractMetadata", skip_serializing_if = "Option::is_none")]
+    pub extract_metadata: Option<bool>,
+    /// Where to push the result of an asynchronous job. Only used by
+    /// asynchronous image moderation.
+    #[serde(rename = "callbackUrl", skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<&'a str>,
+    /// Secret used to sign the callback, so that the receiver can verify it.
+    #[serde(rename = "callbackSecret", skip_serializing_if = "Option::is_none")]
+    pub callback_secret: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<&'a Metadata>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModerationResponse {
+    /// Read as false if the API leaves it out.
+    #[serde(rename = "isClean", default)]
+    pub is_clean: bool,
+    /// The severity as reported, whether or not it is one of the known
+    /// levels; see [`severity_level`](Self::severity_level).
+    #[serde(default, deserialize_with = "lenient::string")]
+    pub severity: Option<String>,
+    /// Scores as reported, which the API sends as strings or as numbers.
+    #[serde(rename = "categoryScores", default, deserialize_with = "lenient::scores")]
+    pub category_scores: Option<HashMap<String, String>>,
+    pub issues: Option<Vec<ModerationIssue>>,
+    pub reason: Option<String>,
+    #[serde(rename = "isBypassAttempt", default)]
+    pub is_bypass_attempt: bool,
+    #[serde(rename = "safeContent")]
+    pub safe_content: Option<String>,
+    pub addons: Option<AddonUsage>,
+    /// Personal data found in the content; see
+    /// [`pii_entities`](Self::pii_entities).
+    #[serde(rename = "piiEntities")]
+    pub pii_entities: Option<Vec<PiiEntity>>,
+    /// The metadata the request was tagged with, and its correlation ID.
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    pub metadata: Metadata,
+    /// What was tolerated while parsing the response leniently, field by
+    /// field.
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModerationIssue {
+    pub term: Option<String>,
+    pub context: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AddonUsage {
+    #[serde(rename = "replacedUnsafe")]
+    pub replaced_unsafe: bool,
+    #[serde(rename = "replacedPii")]
+    pub replaced_pii: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UsageResponse {
+    pub tier: String,
+    #[serde(rename = "rateLimit")]
+    pub rate_limit: i32,
+    #[serde(rename = "tokenLimit")]
+    pub token_limit: Option<i32>,
+    #[serde(rename = "tokensUsed")]
+    pub tokens_used: i32,
+    #[serde(rename = "remainingTokens")]
+    pub remaining_tokens: i32,
+    /// When the token allowance is next reset.
+    #[serde(rename = "resetsAt", default, with = "timestamp::option")]
+    pub resets_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,78 @@
+use crate::ModerationResponse;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const CORRELATION_ID: &str = "correlation_id";
+
+/// Free-form tags for a moderation request, such as who wrote the content
+/// and where, for analytics, cost attribution and auditing.
+///
+/// Metadata is sent to the API with the request and comes back on the
+/// [`ModerationResponse`], whether or not the API echoes it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Metadata(BTreeMap<String, String>);
+
+impl Metadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets `key` to `value`, replacing any earlier value.
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.0.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn user_id(self, user_id: impl Into<String>) -> Self {
+        self.with("user_id", user_id)
+    }
+
+    pub fn channel_id(self, channel_id: impl Into<String>) -> Self {
+        self.with("channel_id", channel_id)
+    }
+
+    pub fn tenant(self, tenant: impl Into<String>) -> Self {
+        self.with("tenant", tenant)
+    }
+
+    /// The ID that joins the moderation call with the caller's traces.
+    pub fn correlation_id(self, correlation_id: impl Into<String>) -> Self {
+        self.with(CORRELATION_ID, correlation_id)
+    }
+
+    /// The part of the product the content came from, e.g. `chat`.
+    pub fn feature(self, feature: impl Into<String>) -> Self {
+        self.with("feature", feature)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Metadata {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self(iter.into_iter().map(|(key, value)| (key.into(), value.into())).collect())
+    }
+}
+
+impl ModerationResponse {
+    /// The `user_id` the request was tagged with.
+    pub fn user_id(&self) -> Option<&str> {
+        self.metadata.get("user_id")
+    }
+
+    /// The `X-Correlation-Id` the request was sent with.
+    pub fn correlation_id(&self) -> Option<&str> {
+        self.metadata.get(CORRELATION_ID)
+    }
+}
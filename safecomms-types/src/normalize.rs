@@ -0,0 +1,154 @@
+//! Maps common obfuscations to canonical text, so that local word lists and
+//! logs see roughly what the API sees.
+
+use std::ops::Range;
+
+/// Characters that render as nothing and are used to split up words.
+const INVISIBLE: &[char] = &[
+    '\u{00AD}', // soft hyphen
+    '\u{034F}', // combining grapheme joiner
+    '\u{180E}', // Mongolian vowel separator
+    '\u{200B}', // zero width space
+    '\u{200C}', // zero width non-joiner
+    '\u{200D}', // zero width joiner
+    '\u{2060}', // word joiner
+    '\u{FEFF}', // zero width no-break space
+];
+
+/// Whether `c` renders as nothing.
+pub fn is_invisible(c: char) -> bool {
+    INVISIBLE.contains(&c)
+}
+
+/// The Latin letter that `c` is commonly used to imitate, for Cyrillic and
+/// Greek look-alikes and fullwidth forms.
+pub fn homoglyph(c: char) -> Option<char> {
+    let latin = match c {
+        'а' | 'α' => 'a',
+        'в' | 'β' => 'b',
+        'с' | 'ϲ' => 'c',
+        'ԁ' => 'd',
+        'е' | 'ε' => 'e',
+        'һ' => 'h',
+        'і' | 'ι' => 'i',
+        'ј' => 'j',
+        'к' | 'κ' => 'k',
+        'м' => 'm',
+        'п' | 'η' => 'n',
+        'о' | 'ο' => 'o',
+        'р' | 'ρ' => 'p',
+        'ԛ' => 'q',
+        'ѕ' => 's',
+        'т' | 'τ' => 't',
+        'υ' => 'u',
+        'ν' => 'v',
+        'ѡ' | 'ω' => 'w',
+        'х' | 'χ' => 'x',
+        'у' | 'γ' => 'y',
+        'ᴢ' => 'z',
+        'А' | 'Α' => 'A',
+        'В' | 'Β' => 'B',
+        'С' => 'C',
+        'Е' | 'Ε' => 'E',
+        'Н' | 'Η' => 'H',
+        'І' | 'Ι' => 'I',
+        'Ј' => 'J',
+        'К' | 'Κ' => 'K',
+        'М' | 'Μ' => 'M',
+        'О' | 'Ο' => 'O',
+        'Р' | 'Ρ' => 'P',
+        'Ѕ' => 'S',
+        'Т' | 'Τ' => 'T',
+        'Х' | 'Χ' => 'X',
+        'У' | 'Υ' => 'Y',
+        'Ζ' => 'Z',
+        '\u{FF21}'..='\u{FF3A}' => char::from_u32(c as u32 - 0xFF21 + 'A' as u32)?,
+        '\u{FF41}'..='\u{FF5A}' => char::from_u32(c as u32 - 0xFF41 + 'a' as u32)?,
+        _ => return None,
+    };
+    Some(latin)
+}
+
+/// The letter that a digit or symbol stands for in leetspeak.
+pub fn leet(c: char) -> Option<char> {
+    let letter = match c {
+        '4' | '@' => 'a',
+        '8' => 'b',
+        '3' => 'e',
+        '6' | '9' => 'g',
+        '1' | '!' | '|' => 'i',
+        '0' => 'o',
+        '5' | '$' => 's',
+        '7' | '+' => 't',
+        '2' => 'z',
+        _ => return None,
+    };
+    Some(letter)
+}
+
+/// Lowercases `text`, drops invisible characters and maps look-alike and
+/// leetspeak characters to the letters they imitate, so `"Н3ll0"` becomes
+/// `"hello"`.
+///
+/// Leetspeak is only undone inside words that also contain a letter, so
+/// numbers such as `"2024"` are left alone, and symbols are only read as
+/// letters in the middle of a word.
+pub fn canonicalize(text: &str) -> String {
+    canonicalize_with_offsets(text).0
+}
+
+/// Like [`canonicalize`], but also returns, for every byte of the canonical
+/// text, the byte range of the original character it came from.
+pub(crate) fn canonicalize_with_offsets(text: &str) -> (String, Vec<Range<usize>>) {
+    let mut canonical = Canonical::default();
+    let mut word = Vec::new();
+
+    for (offset, c) in text.char_indices().filter(|&(_, c)| !is_invisible(c)) {
+        let source = offset..offset + c.len_utf8();
+        let c = homoglyph(c).unwrap_or(c);
+        if c.is_alphanumeric() || leet(c).is_some() {
+            word.push((c, source));
+        } else {
+            canonical.push_word(&word);
+            word.clear();
+            canonical.push(c, source);
+        }
+    }
+    canonical.push_word(&word);
+
+    (canonical.text, canonical.sources)
+}
+
+#[derive(Default)]
+struct Canonical {
+    text: String,
+    sources: Vec<Range<usize>>,
+}
+
+impl Canonical {
+    fn push(&mut self, c: char, source: Range<usize>) {
+        for lower in c.to_lowercase() {
+            self.text.push(lower);
+            self.sources
+                .extend(std::iter::repeat_n(source.clone(), lower.len_utf8()));
+        }
+    }
+
+    fn push_word(&mut self, word: &[(char, Range<usize>)]) {
+        let has_letter = word.iter().any(|(c, _)| c.is_alphabetic());
+        let first = word.iter().position(|(c, _)| c.is_alphanumeric());
+        let last = word.iter().rposition(|(c, _)| c.is_alphanumeric());
+
+        for (i, (c, source)) in word.iter().enumerate() {
+            // Symbols only stand for letters between other characters of the
+            // word, so "hello!" keeps its exclamation mark.
+            let interior =
+                first.is_some_and(|first| i > first) && last.is_some_and(|last| i < last);
+            let c = match leet(*c) {
+                Some(letter) if has_letter && (c.is_alphanumeric() || interior) => letter,
+                _ => *c,
+            };
+            self.push(c, source.clone());
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// The verdict on text that is about to be given to an LLM.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PromptInjectionResponse {
+    #[serde(rename = "isInjection")]
+    pub is_injection: bool,
+    /// Confidence between 0 and 1 that the text is an injection attempt.
+    pub score: f64,
+    /// The kind of attack detected, e.g. `"jailbreak"` or
+    /// `"instruction_override"`.
+    pub category: Option<String>,
+    pub reason: Option<String>,
+}
+
+impl PromptInjectionResponse {
+    /// Whether the score reaches `threshold`, for apps that want to gate
+    /// sensitive tool use more strictly than the API's own verdict.
+    pub fn exceeds(&self, threshold: f64) -> bool {
+        self.score >= threshold
+    }
+}
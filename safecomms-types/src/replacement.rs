@@ -0,0 +1,76 @@
+use serde::{Serialize, Serializer};
+
+/// How replaced or redacted text is masked.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ReplacementStyle {
+    /// Every character becomes `*`.
+    #[default]
+    Asterisks,
+    /// Enough is kept to recognise the value: the first character and domain
+    /// of an email address (`j***@example.com`), or the last four characters
+    /// of anything else (`**** **** **** 1111`).
+    Partial,
+    /// A tag naming what was removed, e.g. `[EMAIL]`.
+    Tag,
+    /// A fixed token.
+    Custom(String),
+}
+
+/// The `replacementStyle`/`replacementToken` request fields.
+#[derive(Serialize)]
+struct ReplacementOptions<'a> {
+    #[serde(rename = "replacementStyle")]
+    style: &'static str,
+    #[serde(rename = "replacementToken", skip_serializing_if = "Option::is_none")]
+    token: Option<&'a str>,
+}
+
+impl ReplacementStyle {
+    fn options(&self) -> ReplacementOptions<'_> {
+        let (style, token) = match self {
+            ReplacementStyle::Asterisks => ("asterisks", None),
+            ReplacementStyle::Partial => ("partial", None),
+            ReplacementStyle::Tag => ("tag", None),
+            ReplacementStyle::Custom(token) => ("custom", Some(token.as_str())),
+        };
+        ReplacementOptions { style, token }
+    }
+
+    /// Masks `original`, using `tag` (e.g. `"EMAIL"`) for the tag style.
+    pub fn mask(&self, original: &str, tag: &str) -> String {
+        match self {
+            ReplacementStyle::Asterisks => "*".repeat(original.chars().count()),
+            ReplacementStyle::Partial => mask_partially(original),
+            ReplacementStyle::Tag => format!("[{}]", tag),
+            ReplacementStyle::Custom(token) => token.clone(),
+        }
+    }
+}
+
+/// Serializes as the `replacementStyle` and `replacementToken` request fields.
+impl Serialize for ReplacementStyle {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.options().serialize(serializer)
+    }
+}
+
+fn mask_partially(original: &str) -> String {
+    if let Some((local, domain)) = original.split_once('@') {
+        let first: String = local.chars().take(1).collect();
+        return format!("{}***@{}", first, domain);
+    }
+
+    let to_mask = original.chars().filter(|c| c.is_alphanumeric()).count().saturating_sub(4);
+    let mut masked = 0;
+    original
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() && masked < to_mask {
+                masked += 1;
+                '*'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
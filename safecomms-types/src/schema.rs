@@ -0,0 +1,261 @@
+//! JSON Schemas for the types, as they are serialized, for OpenAPI documents
+//! and for validating stored results.
+
+use crate::{
+    AddonUsage, ContextMessage, ImageModerationRequest, Job, JobId, Language, Metadata,
+    ModerationIssue, ModerationResponse, PiiEntity, PiiEntityType, ProfileId,
+    PromptInjectionResponse, ReplaceSeverity, ReplacementStyle, RequestId, Severity,
+    TextModerationRequest, UsageResponse,
+};
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+const SEVERITIES: [&str; 4] = ["low", "medium", "high", "critical"];
+const REPLACEMENT_STYLES: [&str; 4] = ["asterisks", "partial", "tag", "custom"];
+
+/// Timestamps, which are written as RFC 3339.
+struct Timestamp;
+
+/// Implements `JsonSchema` as an object with the given properties, named as
+/// they are serialized. Borrowed fields are described by their owned types.
+macro_rules! object_schema {
+    ($type:ty, $name:literal, required: [$($required:literal),*], {
+        $($field:literal: $field_type:ty),* $(,)?
+    }) => {
+        impl JsonSchema for $type {
+            fn schema_name() -> Cow<'static, str> {
+                $name.into()
+            }
+
+            fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+                json_schema!({
+                    "type": "object",
+                    "properties": {
+                        $($field: generator.subschema_for::<$field_type>()),*
+                    },
+                    "required": [$($required),*],
+                })
+            }
+        }
+    };
+}
+
+/// Implements `JsonSchema` as a string. Enums with a fallback variant accept
+/// any string, so their known values are only examples.
+macro_rules! string_schema {
+    ($type:ty, $name:literal, $description:literal, $examples:expr) => {
+        impl JsonSchema for $type {
+            fn schema_name() -> Cow<'static, str> {
+                $name.into()
+            }
+
+            fn json_schema(_: &mut SchemaGenerator) -> Schema {
+                json_schema!({
+                    "type": "string",
+                    "description": $description,
+                    "examples": $examples,
+                })
+            }
+        }
+    };
+}
+
+/// Implements `JsonSchema` for an ID, which is serialized as a string.
+macro_rules! id_schema {
+    ($($type:ident),*) => {
+        $(impl JsonSchema for $type {
+            fn schema_name() -> Cow<'static, str> {
+                stringify!($type).into()
+            }
+
+            fn json_schema(_: &mut SchemaGenerator) -> Schema {
+                json_schema!({ "type": "string" })
+            }
+        })*
+    };
+}
+
+id_schema!(ProfileId, JobId, RequestId);
+
+impl JsonSchema for Severity {
+    fn schema_name() -> Cow<'static, str> {
+        "Severity".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "enum": SEVERITIES,
+        })
+    }
+}
+
+string_schema!(ReplaceSeverity, "ReplaceSeverity", "The lowest severity to mask.", SEVERITIES);
+string_schema!(
+    Language,
+    "Language",
+    "An ISO 639-1 language code.",
+    ["en", "es", "de", "fr"]
+);
+string_schema!(
+    PiiEntityType,
+    "PiiEntityType",
+    "The kind of personal data.",
+    ["email", "phone", "address", "ssn"]
+);
+
+impl JsonSchema for Timestamp {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> Cow<'static, str> {
+        "Timestamp".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "format": "date-time",
+        })
+    }
+}
+
+impl JsonSchema for Metadata {
+    fn schema_name() -> Cow<'static, str> {
+        "Metadata".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "additionalProperties": { "type": "string" },
+        })
+    }
+}
+
+impl JsonSchema for ReplacementStyle {
+    fn schema_name() -> Cow<'static, str> {
+        "ReplacementStyle".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "properties": {
+                "replacementStyle": { "type": "string", "enum": REPLACEMENT_STYLES },
+                "replacementToken": { "type": "string" },
+            },
+            "required": ["replacementStyle"],
+        })
+    }
+}
+
+impl JsonSchema for TextModerationRequest<'_> {
+    fn schema_name() -> Cow<'static, str> {
+        "TextModerationRequest".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        // The replacement style is flattened into the request.
+        json_schema!({
+            "type": "object",
+            "properties": {
+                "content": generator.subschema_for::<String>(),
+                "language": generator.subschema_for::<Language>(),
+                "replace": generator.subschema_for::<bool>(),
+                "pii": generator.subschema_for::<bool>(),
+                "replaceSeverity": generator.subschema_for::<ReplaceSeverity>(),
+                "moderationProfileId": generator.subschema_for::<ProfileId>(),
+                "replacementStyle": { "type": "string", "enum": REPLACEMENT_STYLES },
+                "replacementToken": { "type": "string" },
+                "metadata": generator.subschema_for::<Metadata>(),
+                "context": generator.subschema_for::<Vec<ContextMessage>>(),
+            },
+            "required": ["content"],
+        })
+    }
+}
+
+object_schema!(ImageModerationRequest<'_>, "ImageModerationRequest", required: ["image"], {
+    "image": String,
+    "language": Language,
+    "moderationProfileId": ProfileId,
+    "enableOcr": bool,
+    "enhancedOcr": bool,
+    "extractMetadata": bool,
+    "callbackUrl": String,
+    "callbackSecret": String,
+    "metadata": Metadata,
+});
+
+object_schema!(ContextMessage, "ContextMessage", required: ["userId", "text"], {
+    "userId": String,
+    "text": String,
+});
+
+object_schema!(ModerationResponse, "ModerationResponse", required: [], {
+    "isClean": bool,
+    "severity": Option<String>,
+    "categoryScores": Option<HashMap<String, String>>,
+    "issues": Option<Vec<ModerationIssue>>,
+    "reason": Option<String>,
+    "isBypassAttempt": bool,
+    "safeContent": Option<String>,
+    "addons": Option<AddonUsage>,
+    "piiEntities": Option<Vec<PiiEntity>>,
+    "metadata": Metadata,
+});
+
+object_schema!(ModerationIssue, "ModerationIssue", required: [], {
+    "term": Option<String>,
+    "context": Option<String>,
+});
+
+object_schema!(AddonUsage, "AddonUsage", required: ["replacedUnsafe", "replacedPii"], {
+    "replacedUnsafe": bool,
+    "replacedPii": bool,
+});
+
+object_schema!(PiiEntity, "PiiEntity", required: ["type", "start", "end", "confidence"], {
+    "type": PiiEntityType,
+    "start": usize,
+    "end": usize,
+    "confidence": f64,
+    "maskedValue": Option<String>,
+});
+
+object_schema!(
+    UsageResponse,
+    "UsageResponse",
+    required: ["tier", "rateLimit", "tokensUsed", "remainingTokens"],
+    {
+        "tier": String,
+        "rateLimit": i32,
+        "tokenLimit": Option<i32>,
+        "tokensUsed": i32,
+        "remainingTokens": i32,
+        "resetsAt": Option<Timestamp>,
+    }
+);
+
+object_schema!(
+    PromptInjectionResponse,
+    "PromptInjectionResponse",
+    required: ["isInjection", "score"],
+    {
+        "isInjection": bool,
+        "score": f64,
+        "category": Option<String>,
+        "reason": Option<String>,
+    }
+);
+
+object_schema!(Job, "Job", required: ["jobId", "status"], {
+    "jobId": JobId,
+    "status": String,
+    "result": Option<ModerationResponse>,
+    "createdAt": Option<Timestamp>,
+    "completedAt": Option<Timestamp>,
+});
@@ -0,0 +1,122 @@
+use crate::ModerationResponse;
+use serde::{Deserialize, Serialize};
+
+/// The severity levels the API reports, from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    /// Parses a severity as reported by the API, ignoring case.
+    pub fn parse(severity: &str) -> Option<Self> {
+        match severity.to_ascii_lowercase().as_str() {
+            "low" => Some(Severity::Low),
+            "medium" => Some(Severity::Medium),
+            "high" => Some(Severity::High),
+            "critical" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+}
+
+/// The lowest severity `replace` masks. Strings convert with `into()`; known
+/// levels become their variant, ignoring case, and anything else is sent as
+/// given.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum ReplaceSeverity {
+    Low,
+    Medium,
+    High,
+    Critical,
+    /// A level this version of the SDK does not know, sent as given.
+    Custom(String),
+}
+
+impl ReplaceSeverity {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ReplaceSeverity::Low => "low",
+            ReplaceSeverity::Medium => "medium",
+            ReplaceSeverity::High => "high",
+            ReplaceSeverity::Critical => "critical",
+            ReplaceSeverity::Custom(level) => level,
+        }
+    }
+}
+
+impl From<Severity> for ReplaceSeverity {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Low => ReplaceSeverity::Low,
+            Severity::Medium => ReplaceSeverity::Medium,
+            Severity::High => ReplaceSeverity::High,
+            Severity::Critical => ReplaceSeverity::Critical,
+        }
+    }
+}
+
+impl From<&str> for ReplaceSeverity {
+    fn from(level: &str) -> Self {
+        match Severity::parse(level) {
+            Some(severity) => severity.into(),
+            None => ReplaceSeverity::Custom(level.to_string()),
+        }
+    }
+}
+
+impl From<String> for ReplaceSeverity {
+    fn from(level: String) -> Self {
+        match Severity::parse(&level) {
+            Some(severity) => severity.into(),
+            None => ReplaceSeverity::Custom(level),
+        }
+    }
+}
+
+impl From<ReplaceSeverity> for String {
+    fn from(level: ReplaceSeverity) -> Self {
+        level.as_str().to_string()
+    }
+}
+
+impl ModerationResponse {
+    /// The reported severity, if it is one of the known levels.
+    pub fn severity_level(&self) -> Option<Severity> {
+        self.severity.as_deref().and_then(Severity::parse)
+    }
+
+    /// The score for `category`, ignoring case.
+    pub fn category_score(&self, category: &str) -> Option<f64> {
+        self.scores()
+            .find(|(name, _)| name.eq_ignore_ascii_case(category))
+            .map(|(_, score)| score)
+    }
+
+    /// Categories scoring at least `threshold`, highest first.
+    pub fn flagged_categories(&self, threshold: f64) -> Vec<(&str, f64)> {
+        let mut categories: Vec<(&str, f64)> =
+            self.scores().filter(|(_, score)| *score >= threshold).collect();
+        categories.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        categories
+    }
+
+    /// The highest-scoring category.
+    pub fn worst_category(&self) -> Option<(&str, f64)> {
+        self.scores()
+            .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+    }
+
+    /// Category scores that parse as numbers.
+    fn scores(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.category_scores
+            .iter()
+            .flatten()
+            .filter_map(|(category, score)| Some((category.as_str(), score.parse().ok()?)))
+    }
+}
@@ -0,0 +1,152 @@
+//! Locates flagged terms in the original content, so that a UI can highlight
+//! exactly what was flagged.
+
+use crate::normalize::canonicalize_with_offsets;
+use crate::ModerationResponse;
+use std::ops::Range;
+
+/// Where a flagged term occurs in the original content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermSpan {
+    /// The term as reported by the API.
+    pub term: String,
+    /// Byte offsets into the content, for slicing in Rust.
+    pub bytes: Range<usize>,
+    /// Character offsets into the content, for UIs that count characters.
+    pub chars: Range<usize>,
+    /// Whether the content spells the term differently, e.g. in other case,
+    /// with look-alike characters or split up by invisible characters.
+    pub obfuscated: bool,
+}
+
+/// Every occurrence of `terms` in `content`, in order. Matching is on the
+/// [canonicalized](crate::normalize::canonicalize) text, so `"asshole"` is
+/// also found as `"А$$h0le"` or with zero width spaces between its letters.
+/// Where occurrences overlap, the one starting first wins.
+pub fn find_terms<I>(content: &str, terms: I) -> Vec<TermSpan>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let (canonical, sources) = canonicalize_with_offsets(content);
+    let mut spans: Vec<TermSpan> = Vec::new();
+
+    for term in terms {
+        let term = term.as_ref();
+        let needle = canonicalize_with_offsets(term).0;
+        if needle.trim().is_empty() {
+            continue;
+        }
+
+        for (start, found) in canonical.match_indices(needle.as_str()) {
+            let bytes = sources[start].start..sources[start + found.len() - 1].end;
+            spans.push(TermSpan {
+                term: term.to_string(),
+                chars: char_offset(content, bytes.start)..char_offset(content, bytes.end),
+                obfuscated: content[bytes.clone()] != *term,
+                bytes,
+            });
+        }
+    }
+
+    spans.sort_by_key(|span| (span.bytes.start, std::cmp::Reverse(span.bytes.end)));
+    let mut end = 0;
+    spans.retain(|span| {
+        let keep = span.bytes.start >= end;
+        if keep {
+            end = span.bytes.end;
+        }
+        keep
+    });
+    spans
+}
+
+fn char_offset(content: &str, byte: usize) -> usize {
+    content[..byte].chars().count()
+}
+
+impl ModerationResponse {
+    /// Where the terms of this response's issues occur in `content`, which
+    /// should be the text that was moderated. See [`find_terms`].
+    pub fn term_spans(&self, content: &str) -> Vec<TermSpan> {
+        let terms = self
+            .issues
+            .iter()
+            .flatten()
+            .filter_map(|issue| issue.term.as_deref());
+        find_terms(content, terms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(term: &str, bytes: Range<usize>, chars: Range<usize>, obfuscated: bool) -> TermSpan {
+        TermSpan {
+            term: term.to_string(),
+            bytes,
+            chars,
+            obfuscated,
+        }
+    }
+
+    #[test]
+    fn offsets_in_multibyte_text() {
+        let content = "zażółć idiot";
+        let start = "zażółć ".len();
+        assert_eq!(
+            find_terms(content, ["idiot"]),
+            vec![span("idiot", start..start + 5, 7..12, false)]
+        );
+    }
+
+    #[test]
+    fn terms_at_the_start_and_end() {
+        assert_eq!(
+            find_terms("idiot i idiot", ["idiot"]),
+            vec![
+                span("idiot", 0..5, 0..5, false),
+                span("idiot", 8..13, 8..13, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn obfuscated_terms_cover_the_original_characters() {
+        let content = "ty А$$h0le!";
+        let start = "ty ".len();
+        assert_eq!(
+            find_terms(content, ["asshole"]),
+            vec![span("asshole", start..content.len() - 1, 3..10, true)]
+        );
+
+        let content = "ass\u{200B}hole";
+        assert_eq!(
+            find_terms(content, ["asshole"]),
+            vec![span("asshole", 0..content.len(), 0..8, true)]
+        );
+
+        assert_eq!(find_terms("IDIOT", ["idiot"]), vec![span("idiot", 0..5, 0..5, true)]);
+    }
+
+    #[test]
+    fn overlapping_terms_keep_the_one_starting_first() {
+        assert_eq!(
+            find_terms("bad word salad", ["word salad", "bad word"]),
+            vec![span("bad word", 0..8, 0..8, false)]
+        );
+        assert_eq!(
+            find_terms("bad word", ["bad", "bad word"]),
+            vec![span("bad word", 0..8, 0..8, false)]
+        );
+    }
+
+    #[test]
+    fn empty_and_identical_inputs() {
+        assert_eq!(find_terms("", ["idiot"]), vec![]);
+        assert_eq!(find_terms("idiot", ["", "  "]), vec![]);
+        assert_eq!(find_terms("idiot", Vec::<&str>::new()), vec![]);
+        assert_eq!(find_terms("żółw", ["żółw"]), vec![span("żółw", 0.."żółw".len(), 0..4, false)]);
+    }
+}
@@ -0,0 +1,84 @@
+//! Timestamps as the API sends them, for use with `#[serde(with = ...)]`.
+//!
+//! The API sends RFC 3339 strings, but older endpoints leave out the offset,
+//! separate the date and time with a space, or send Unix times in seconds or
+//! milliseconds, as numbers or strings. All of these are read as UTC, and
+//! timestamps are written back as RFC 3339.
+
+use crate::lenient::Scalar;
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// Unix times from this on are taken to be in milliseconds; in seconds it is
+/// the year 5138.
+const MILLISECONDS_FROM: i64 = 100_000_000_000;
+const WITHOUT_OFFSET: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+
+/// Parses a timestamp in any of the API's formats.
+pub fn parse(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    if let Ok(unix) = text.parse::<i64>() {
+        return if unix.abs() >= MILLISECONDS_FROM {
+            DateTime::from_timestamp_millis(unix)
+        } else {
+            DateTime::from_timestamp(unix, 0)
+        };
+    }
+    if let Ok(time) = text.parse::<DateTime<Utc>>() {
+        return Some(time);
+    }
+    WITHOUT_OFFSET
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .map(|time| time.and_utc())
+}
+
+pub fn serialize<S: Serializer>(time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&time.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    let text = String::from(Scalar::deserialize(deserializer)?);
+    parse(&text).ok_or_else(|| D::Error::custom(format!("invalid timestamp `{}`", text)))
+}
+
+/// The same, for optional timestamps.
+pub mod option {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        time: &Option<DateTime<Utc>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(time) => super::serialize(time, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Utc>>, D::Error> {
+        #[derive(Deserialize)]
+        struct Timestamp(#[serde(with = "super")] DateTime<Utc>);
+
+        Ok(Option::<Timestamp>::deserialize(deserializer)?.map(|Timestamp(time)| time))
+    }
+}
+
+/// A timestamp as a `time::OffsetDateTime` in UTC, for code built on `time`.
+/// None if it is outside the years `time` supports.
+#[cfg(feature = "time")]
+pub fn to_offset_date_time(time: DateTime<Utc>) -> Option<time::OffsetDateTime> {
+    time::OffsetDateTime::from_unix_timestamp(time.timestamp())
+        .ok()?
+        .replace_nanosecond(time.timestamp_subsec_nanos())
+        .ok()
+}
+
+#[cfg(feature = "time")]
+pub fn from_offset_date_time(time: time::OffsetDateTime) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(time.unix_timestamp(), time.nanosecond())
+}
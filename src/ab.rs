@@ -0,0 +1,139 @@
+use crate::{
+    Action, Language, ModerationResponse, Policy, ProfileId, SafeCommsClient, SafeCommsError,
+};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// One side of an [`AbTest`]: a moderation profile and the policy applied to
+/// its results.
+#[derive(Debug, Clone, Default)]
+pub struct Variant {
+    pub profile_id: Option<ProfileId>,
+    pub policy: Policy,
+}
+
+impl Variant {
+    /// The profile `profile_id`, under the default policy.
+    pub fn profile(profile_id: impl Into<ProfileId>) -> Self {
+        Self {
+            profile_id: Some(profile_id.into()),
+            policy: Policy::default(),
+        }
+    }
+
+    /// The account's default profile, under `policy`.
+    pub fn policy(policy: Policy) -> Self {
+        Self {
+            profile_id: None,
+            policy,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+/// Both verdicts for one piece of content.
+#[derive(Debug)]
+pub struct AbOutcome {
+    pub a: ModerationResponse,
+    pub b: ModerationResponse,
+    pub action_a: Action,
+    pub action_b: Action,
+}
+
+/// How often the two variants agreed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AbReport {
+    pub compared: u64,
+    /// Comparisons where both variants took the same action.
+    pub agreed: u64,
+    /// Comparisons where the API's clean verdict differed.
+    pub verdicts_differed: u64,
+    /// How often each pair of actions, `(a, b)`, was taken.
+    pub confusion: BTreeMap<(Action, Action), u64>,
+}
+
+impl AbReport {
+    /// The share of comparisons where both variants agreed, if any were made.
+    pub fn agreement_rate(&self) -> Option<f64> {
+        (self.compared > 0).then(|| self.agreed as f64 / self.compared as f64)
+    }
+}
+
+/// Moderates the same content under two variants and reports how their
+/// decisions compare, to evaluate a profile or policy change before
+/// switching to it.
+///
+/// When both variants use the same profile, content is only moderated once
+/// and the two policies are applied to the one response.
+#[derive(Debug)]
+pub struct AbTest {
+    a: Variant,
+    b: Variant,
+    report: Mutex<AbReport>,
+}
+
+impl AbTest {
+    pub fn new(a: Variant, b: Variant) -> Self {
+        Self {
+            a,
+            b,
+            report: Mutex::default(),
+        }
+    }
+
+    pub async fn moderate_text<'a>(
+        &'a self,
+        client: &'a SafeCommsClient,
+        content: &'a str,
+        language: Option<&'a str>,
+    ) -> Result<AbOutcome, SafeCommsError> {
+        let moderate = |profile_id: Option<&'a ProfileId>| {
+            client.moderate_text(content, language.map(Language::from), None, None, None, profile_id)
+        };
+
+        let (a, b) = if self.a.profile_id == self.b.profile_id {
+            let a = moderate(self.a.profile_id.as_ref()).await?;
+            (a.clone(), a)
+        } else {
+            let (a, b) = futures_util::join!(
+                moderate(self.a.profile_id.as_ref()),
+                moderate(self.b.profile_id.as_ref())
+            );
+            (a?, b?)
+        };
+
+        Ok(self.compare(a, b))
+    }
+
+    /// Records a pair of responses obtained some other way, e.g. from image
+    /// moderation.
+    pub fn compare(&self, a: ModerationResponse, b: ModerationResponse) -> AbOutcome {
+        let action_a = self.a.policy.evaluate(&a);
+        let action_b = self.b.policy.evaluate(&b);
+
+        let mut report = self.report.lock().unwrap_or_else(|e| e.into_inner());
+        report.compared += 1;
+        if action_a == action_b {
+            report.agreed += 1;
+        }
+        if a.is_clean != b.is_clean {
+            report.verdicts_differed += 1;
+        }
+        *report.confusion.entry((action_a, action_b)).or_default() += 1;
+
+        AbOutcome {
+            a,
+            b,
+            action_a,
+            action_b,
+        }
+    }
+
+    pub fn report(&self) -> AbReport {
+        self.report.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
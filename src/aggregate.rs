@@ -0,0 +1,170 @@
+//! Verdict counts by user, channel, tenant or any other dimension, rolled up
+//! into fixed time windows for dashboards.
+
+use crate::ModerationResponse;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+const DEFAULT_CATEGORY_THRESHOLD: f64 = 0.5;
+
+/// Counts of moderation results.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Tally {
+    pub total: u64,
+    pub clean: u64,
+    /// Results the API found unsafe.
+    pub flagged: u64,
+    pub bypass_attempts: u64,
+    /// Unsafe results by reported severity, lowercased.
+    pub severities: BTreeMap<String, u64>,
+    /// How often each category scored above the aggregator's threshold.
+    pub categories: BTreeMap<String, u64>,
+}
+
+impl Tally {
+    pub(crate) fn add(&mut self, response: &ModerationResponse, category_threshold: f64) {
+        self.total += 1;
+        if response.is_clean {
+            self.clean += 1;
+        } else {
+            self.flagged += 1;
+            let severity = response.severity.as_deref().unwrap_or("unknown");
+            *self.severities.entry(severity.to_lowercase()).or_default() += 1;
+        }
+        if response.is_bypass_attempt {
+            self.bypass_attempts += 1;
+        }
+
+        for (category, score) in response.category_scores.iter().flatten() {
+            if score.parse::<f64>().is_ok_and(|score| score >= category_threshold) {
+                *self.categories.entry(category.to_lowercase()).or_default() += 1;
+            }
+        }
+    }
+
+    /// Adds `other`'s counts to this tally.
+    pub fn merge(&mut self, other: &Tally) {
+        self.total += other.total;
+        self.clean += other.clean;
+        self.flagged += other.flagged;
+        self.bypass_attempts += other.bypass_attempts;
+        for (severity, count) in &other.severities {
+            *self.severities.entry(severity.clone()).or_default() += count;
+        }
+        for (category, count) in &other.categories {
+            *self.categories.entry(category.clone()).or_default() += count;
+        }
+    }
+}
+
+/// The results recorded during one time window.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Window {
+    /// Start of the window, in seconds since the Unix epoch.
+    pub start: u64,
+    pub overall: Tally,
+    /// Tallies by dimension name, then by dimension value.
+    pub dimensions: BTreeMap<String, BTreeMap<String, Tally>>,
+}
+
+/// The retained windows, oldest first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Snapshot {
+    pub window_secs: u64,
+    pub windows: Vec<Window>,
+}
+
+/// Accumulates moderation results into time windows, overall and under each
+/// dimension they are recorded with. Only the most recent windows are kept.
+#[derive(Debug)]
+pub struct Aggregator {
+    window_secs: u64,
+    retention: usize,
+    category_threshold: f64,
+    windows: Mutex<VecDeque<Window>>,
+}
+
+impl Aggregator {
+    /// Rolls results up into windows of `window` (at least a second),
+    /// keeping the last `retention` of them.
+    pub fn new(window: Duration, retention: usize) -> Self {
+        Self {
+            window_secs: window.as_secs().max(1),
+            retention: retention.max(1),
+            category_threshold: DEFAULT_CATEGORY_THRESHOLD,
+            windows: Mutex::default(),
+        }
+    }
+
+    /// The score at which a category counts as present. Defaults to 0.5.
+    pub fn category_threshold(mut self, threshold: f64) -> Self {
+        self.category_threshold = threshold;
+        self
+    }
+
+    /// Records `response` in the current window, overall and under each
+    /// `(dimension, value)` pair, e.g. `[("user", "42"), ("channel", "general")]`.
+    pub fn record(&self, dimensions: &[(&str, &str)], response: &ModerationResponse) {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let start = now - now % self.window_secs;
+
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        if windows.back().is_none_or(|window| window.start != start) {
+            windows.push_back(Window {
+                start,
+                ..Window::default()
+            });
+            while windows.len() > self.retention {
+                windows.pop_front();
+            }
+        }
+        let window = windows.back_mut().expect("a window was just pushed");
+
+        window.overall.add(response, self.category_threshold);
+        for (dimension, value) in dimensions {
+            window
+                .dimensions
+                .entry(dimension.to_string())
+                .or_default()
+                .entry(value.to_string())
+                .or_default()
+                .add(response, self.category_threshold);
+        }
+    }
+
+    /// Everything recorded in the retained windows.
+    pub fn overall(&self) -> Tally {
+        self.sum(|window| Some(&window.overall))
+    }
+
+    /// Everything recorded under `dimension` = `value` in the retained
+    /// windows.
+    pub fn total(&self, dimension: &str, value: &str) -> Tally {
+        self.sum(|window| window.dimensions.get(dimension)?.get(value))
+    }
+
+    fn sum(&self, tally: impl Fn(&Window) -> Option<&Tally>) -> Tally {
+        let windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        windows.iter().filter_map(tally).fold(Tally::default(), |mut sum, tally| {
+            sum.merge(tally);
+            sum
+        })
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        Snapshot {
+            window_secs: self.window_secs,
+            windows: windows.iter().cloned().collect(),
+        }
+    }
+
+    /// The snapshot as JSON, for dashboards.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self.snapshot()).expect("snapshots serialize to JSON")
+    }
+}
@@ -0,0 +1,338 @@
+//! Command-line access to the SafeComms API.
+//!
+//! Exits with 0 when everything checked was clean, 1 when anything was
+//! flagged and 2 on errors.
+
+use clap::{Parser, Subcommand};
+use futures_util::{StreamExt, stream};
+use safecomms::{
+    FolderWatcher, ImageModerationRequest, Language, ModerationResponse, ProfileId, SafeCommsClient,
+    SafeCommsError,
+};
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(name = "safecomms", version, about = "Moderate content with the SafeComms API")]
+struct Cli {
+    #[arg(long, env = "SAFECOMMS_API_KEY", hide_env_values = true)]
+    api_key: String,
+    #[arg(long, env = "SAFECOMMS_BASE_URL")]
+    base_url: Option<String>,
+    /// Print raw JSON instead of a summary.
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Moderate text given as an argument, or read from stdin.
+    Text {
+        content: Option<String>,
+        #[arg(long)]
+        language: Option<String>,
+        #[arg(long)]
+        profile: Option<ProfileId>,
+    },
+    /// Moderate an image file or URL.
+    Image {
+        image: String,
+        #[arg(long)]
+        profile: Option<ProfileId>,
+        #[arg(long)]
+        ocr: bool,
+    },
+    /// Moderate each line of a file, or of stdin if the file is `-`.
+    Batch {
+        file: String,
+        #[arg(long)]
+        language: Option<String>,
+        #[arg(long)]
+        profile: Option<ProfileId>,
+        /// How many lines to moderate at once.
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+    },
+    /// Show the account's tier and token usage.
+    Usage,
+    /// Moderate files as they appear in a directory, moving them to
+    /// accepted or rejected directories, or to a failed directory if they
+    /// can never be moderated.
+    Watch {
+        dir: PathBuf,
+        #[arg(long)]
+        accepted: Option<PathBuf>,
+        #[arg(long)]
+        rejected: Option<PathBuf>,
+        #[arg(long)]
+        failed: Option<PathBuf>,
+        /// Append a JSON line per file to this log.
+        #[arg(long)]
+        log: Option<PathBuf>,
+        /// Seconds between scans.
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let client = SafeCommsClient::new(cli.api_key, cli.base_url);
+
+    let result = match cli.command {
+        Command::Text {
+            content,
+            language,
+            profile,
+        } => text(&client, content, language, profile, cli.json).await,
+        Command::Image {
+            image,
+            profile,
+            ocr,
+        } => self::image(&client, &image, profile, ocr, cli.json).await,
+        Command::Batch {
+            file,
+            language,
+            profile,
+            concurrency,
+        } => batch(&client, &file, language, profile, concurrency, cli.json).await,
+        Command::Usage => usage(&client, cli.json).await,
+        Command::Watch {
+            dir,
+            accepted,
+            rejected,
+            failed,
+            log,
+            interval,
+        } => {
+            let interval = Duration::from_secs(interval);
+            let mut watcher = FolderWatcher::new(client, dir);
+            if let Some(accepted) = accepted {
+                watcher = watcher.accepted_dir(accepted);
+            }
+            if let Some(rejected) = rejected {
+                watcher = watcher.rejected_dir(rejected);
+            }
+            if let Some(failed) = failed {
+                watcher = watcher.failed_dir(failed);
+            }
+            if let Some(log) = log {
+                watcher = watcher.results_log(log);
+            }
+            watch(&watcher, interval, cli.json).await
+        }
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+async fn text(
+    client: &SafeCommsClient,
+    content: Option<String>,
+    language: Option<String>,
+    profile: Option<ProfileId>,
+    json: bool,
+) -> Result<bool, SafeCommsError> {
+    let content = match content {
+        Some(content) => content,
+        None => {
+            let mut content = String::new();
+            std::io::stdin()
+                .read_to_string(&mut content)
+                .map_err(|e| SafeCommsError::ApiError(format!("Failed to read stdin: {}", e)))?;
+            content
+        }
+    };
+
+    let response = client
+        .moderate_text(
+            &content,
+            language.map(Language::from),
+            None,
+            None,
+            None,
+            profile.as_ref(),
+        )
+        .await?;
+    print_response(None, &response, json)?;
+    Ok(response.is_clean)
+}
+
+async fn image(
+    client: &SafeCommsClient,
+    image: &str,
+    profile: Option<ProfileId>,
+    ocr: bool,
+    json: bool,
+) -> Result<bool, SafeCommsError> {
+    let ocr = ocr.then_some(true);
+    let response = if image.starts_with("http://") || image.starts_with("https://") {
+        client
+            .moderate_image(ImageModerationRequest {
+                image,
+                language: None,
+                moderation_profile_id: profile.as_ref(),
+                enable_ocr: ocr,
+                enhanced_ocr: None,
+                extract_metadata: None,
+                callback_url: None,
+                callback_secret: None,
+                metadata: None,
+            })
+            .await?
+    } else {
+        client
+            .moderate_image_file(image, None, profile.as_ref(), ocr, None, None)
+            .await?
+    };
+    print_response(None, &response, json)?;
+    Ok(response.is_clean)
+}
+
+async fn batch(
+    client: &SafeCommsClient,
+    file: &str,
+    language: Option<String>,
+    profile: Option<ProfileId>,
+    concurrency: usize,
+    json: bool,
+) -> Result<bool, SafeCommsError> {
+    let reader: Box<dyn BufRead> = if file == "-" {
+        Box::new(BufReader::new(std::io::stdin()))
+    } else {
+        let file = std::fs::File::open(file)
+            .map_err(|e| SafeCommsError::ApiError(format!("Failed to read file: {}", e)))?;
+        Box::new(BufReader::new(file))
+    };
+    let lines: Vec<String> = reader
+        .lines()
+        .collect::<Result<_, _>>()
+        .map_err(|e| SafeCommsError::ApiError(format!("Failed to read input: {}", e)))?;
+
+    let (language, profile) = (language.map(Language::from), profile.as_ref());
+    let language = &language;
+    let mut results = stream::iter(lines.iter().enumerate().filter(|(_, line)| !line.is_empty()))
+        .map(|(number, line)| async move {
+            let response = client
+                .moderate_text(line, language.clone(), None, None, None, profile)
+                .await;
+            (number + 1, response)
+        })
+        .buffered(concurrency.max(1));
+
+    // A line that fails is reported and skipped, so that one bad line does
+    // not hide the results of the rest.
+    let (mut all_clean, mut failed) = (true, 0);
+    while let Some((line, response)) = results.next().await {
+        match response {
+            Ok(response) => {
+                all_clean &= response.is_clean;
+                print_response(Some(line), &response, json)?;
+            }
+            Err(e) if json => {
+                failed += 1;
+                let output = serde_json::json!({ "line": line, "error": e.to_string() });
+                println!("{}", output);
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{}: error - {}", line, e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(SafeCommsError::ApiError(format!(
+            "{} of {} lines could not be moderated",
+            failed,
+            lines.iter().filter(|line| !line.is_empty()).count()
+        )));
+    }
+    Ok(all_clean)
+}
+
+async fn usage(client: &SafeCommsClient, json: bool) -> Result<bool, SafeCommsError> {
+    let usage = client.get_usage().await?;
+    if json {
+        println!("{}", serde_json::to_string(&usage)?);
+    } else {
+        println!("tier: {}", usage.tier);
+        println!("rate limit: {}", usage.rate_limit);
+        println!("tokens used: {}", usage.tokens_used);
+        println!("tokens remaining: {}", usage.remaining_tokens);
+        if let Some(limit) = usage.token_limit {
+            println!("token limit: {}", limit);
+        }
+    }
+    Ok(true)
+}
+
+async fn watch(
+    watcher: &FolderWatcher,
+    interval: Duration,
+    json: bool,
+) -> Result<bool, SafeCommsError> {
+    loop {
+        for result in watcher.scan_once().await? {
+            if json {
+                println!("{}", serde_json::to_string(&result)?);
+            } else if let Some(error) = &result.error {
+                println!("{}: error - {}", result.file.display(), error);
+            } else {
+                let verdict = if result.accepted { "accepted" } else { "rejected" };
+                println!("{}: {}", result.file.display(), verdict);
+            }
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+fn print_response(
+    line: Option<usize>,
+    response: &ModerationResponse,
+    json: bool,
+) -> Result<(), SafeCommsError> {
+    if json {
+        let output = match line {
+            Some(line) => serde_json::json!({ "line": line, "result": response }),
+            None => serde_json::to_value(response)?,
+        };
+        println!("{}", output);
+        return Ok(());
+    }
+
+    let prefix = line.map(|line| format!("{}: ", line)).unwrap_or_default();
+    if response.is_clean {
+        println!("{}clean", prefix);
+        return Ok(());
+    }
+
+    let severity = response.severity.as_deref().unwrap_or("unknown severity");
+    let terms: Vec<&str> = response
+        .issues
+        .iter()
+        .flatten()
+        .filter_map(|issue| issue.term.as_deref())
+        .collect();
+    print!("{}flagged ({})", prefix, severity);
+    if !terms.is_empty() {
+        print!(": {}", terms.join(", "));
+    }
+    if let Some(reason) = &response.reason {
+        print!(" - {}", reason);
+    }
+    println!();
+    Ok(())
+}
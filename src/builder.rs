@@ -0,0 +1,469 @@
+#[cfg(feature = "compression")]
+use crate::compression::RequestCompression;
+use crate::connection::ConnectionConfig;
+use crate::endpoints::Endpoints;
+use crate::keys::ApiKeys;
+use crate::retry::RetryConfig;
+use crate::timeouts::Timeouts;
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+use crate::tls::TlsConfig;
+use crate::validation::InputLimits;
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+use crate::ClientIdentity;
+#[cfg(feature = "compression")]
+use crate::Compression;
+#[cfg(feature = "persistence")]
+use crate::ResultStore;
+#[cfg(feature = "async")]
+use crate::UrlExpander;
+use crate::{
+    DEFAULT_ERROR_BODY_LIMIT, DryRun, EndpointClass, Environment, HttpTransport, Middleware, Proxy,
+    RedactionPolicy, Region, ReplacementStyle, RequestSigner, RetryBudget, SafeCommsClient,
+    SafeCommsError, WireFormat,
+};
+use reqwest::Client as HttpClient;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub struct SafeCommsClientBuilder {
+    api_key: String,
+    extra_api_keys: Vec<String>,
+    base_urls: Vec<String>,
+    environment: Environment,
+    dry_run: Option<DryRun>,
+    debug_logging: Option<RedactionPolicy>,
+    replacement_style: Option<ReplacementStyle>,
+    describe_emoji: bool,
+    lenient_parsing: bool,
+    error_body_limit: usize,
+    retry: RetryConfig,
+    timeouts: Timeouts,
+    wire_format: WireFormat,
+    limits: InputLimits,
+    middleware: Vec<Box<dyn Middleware>>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    #[cfg(feature = "async")]
+    url_expander: Option<UrlExpander>,
+    transport: Option<Arc<dyn HttpTransport>>,
+    #[cfg(feature = "compression")]
+    compression: Option<RequestCompression>,
+    proxies: Vec<Proxy>,
+    system_proxy: bool,
+    connection: ConnectionConfig,
+    #[cfg(any(feature = "rustls", feature = "native-tls"))]
+    tls: TlsConfig,
+    #[cfg(feature = "langdetect")]
+    auto_language: bool,
+    #[cfg(feature = "persistence")]
+    result_store: Option<Arc<dyn ResultStore>>,
+}
+
+impl SafeCommsClientBuilder {
+    pub(crate) fn new(api_key: String) -> Self {
+        Self {
+            api_key,
+            extra_api_keys: Vec::new(),
+            base_urls: Vec::new(),
+            environment: Environment::default(),
+            dry_run: None,
+            debug_logging: None,
+            replacement_style: None,
+            describe_emoji: false,
+            lenient_parsing: false,
+            error_body_limit: DEFAULT_ERROR_BODY_LIMIT,
+            retry: RetryConfig::default(),
+            timeouts: Timeouts::default(),
+            wire_format: WireFormat::default(),
+            limits: InputLimits::default(),
+            middleware: Vec::new(),
+            signer: None,
+            #[cfg(feature = "async")]
+            url_expander: None,
+            transport: None,
+            #[cfg(feature = "compression")]
+            compression: None,
+            proxies: Vec::new(),
+            system_proxy: true,
+            connection: ConnectionConfig::default(),
+            #[cfg(any(feature = "rustls", feature = "native-tls"))]
+            tls: TlsConfig::default(),
+            #[cfg(feature = "langdetect")]
+            auto_language: false,
+            #[cfg(feature = "persistence")]
+            result_store: None,
+        }
+    }
+
+    pub fn base_url(mut self, base_url: String) -> Self {
+        self.base_urls = vec![base_url];
+        self
+    }
+
+    /// More keys to rotate between along with the builder's key, one per
+    /// request, e.g. to spread rate limits. A key that is rate limited is
+    /// left out until the API says it may retry, or for 30 seconds, and a
+    /// key rejected as unauthorized is left out until no other key works.
+    pub fn api_keys(mut self, keys: Vec<String>) -> Self {
+        self.extra_api_keys.extend(keys);
+        self
+    }
+
+    /// Base URLs to fail over between, in priority order.
+    pub fn endpoints(mut self, base_urls: Vec<String>) -> Self {
+        self.base_urls = base_urls;
+        self
+    }
+
+    pub fn region(self, region: Region) -> Self {
+        self.base_url(region.base_url().to_string())
+    }
+
+    /// Selects the target environment. Unless a base URL has been set
+    /// explicitly, this also picks the environment's API host.
+    pub fn environment(mut self, environment: Environment) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// Never calls the network; every request is validated and logged, and
+    /// answered with the synthetic responses from `dry_run`.
+    pub fn dry_run(mut self, dry_run: DryRun) -> Self {
+        self.dry_run = Some(dry_run);
+        self
+    }
+
+    /// Logs a summary of every request and response at debug level, with
+    /// user content redacted according to `policy`.
+    pub fn debug_logging(mut self, policy: RedactionPolicy) -> Self {
+        self.debug_logging = Some(policy);
+        self
+    }
+
+    /// How text moderation requests mask content when `replace` or PII
+    /// replacement is enabled.
+    pub fn replacement_style(mut self, style: ReplacementStyle) -> Self {
+        self.replacement_style = Some(style);
+        self
+    }
+
+    /// Spells out emoji and emoticons as words before text is moderated; see
+    /// [`emoji::describe`](crate::emoji::describe).
+    ///
+    /// The API then only sees the described text, so when any emoji were
+    /// described the response's `safe_content` and `pii_entities`, which
+    /// would refer to that text rather than the original, are left out.
+    pub fn describe_emoji(mut self) -> Self {
+        self.describe_emoji = true;
+        self
+    }
+
+    /// Parses moderation responses field by field, so a field in a shape
+    /// this version of the SDK does not expect is left out and described in
+    /// [`ModerationResponse::warnings`] instead of failing the response.
+    pub fn lenient_parsing(mut self) -> Self {
+        self.lenient_parsing = true;
+        self
+    }
+
+    /// Retries requests that get a 429 or 5xx, or that cannot connect to any
+    /// endpoint, up to `max_retries` times. Off by default.
+    ///
+    /// Retries wait for a backoff shared by all requests of the client, so
+    /// during an outage they back off together. POST requests are sent with
+    /// an idempotency key so retries are not charged twice.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.retry.max_retries = max_retries;
+        self
+    }
+
+    /// Limits the retries, including failovers to other endpoints, that may
+    /// be made per window; see [`RetryBudget`]. Unlimited by default.
+    pub fn retry_budget(mut self, budget: RetryBudget) -> Self {
+        self.retry.budget = Some(budget);
+        self
+    }
+
+    /// The shared backoff starts at `base` and doubles with each failure up
+    /// to `max`, unless the API asks for longer with `Retry-After`. Defaults
+    /// to 200ms and 10s.
+    pub fn retry_backoff(mut self, base: Duration, max: Duration) -> Self {
+        self.retry.backoff_base = base;
+        self.retry.backoff_max = max;
+        self
+    }
+
+    /// How many bytes of an error response's body are kept in
+    /// [`ResponseError::body`](crate::ResponseError::body). Defaults to 4 KiB.
+    pub fn error_body_limit(mut self, limit: usize) -> Self {
+        self.error_body_limit = limit;
+        self
+    }
+
+    /// Encodes request bodies in `format` and asks for responses in it,
+    /// falling back to JSON for responses. Binary formats need the `msgpack`
+    /// or `cbor` feature and an API that accepts them.
+    pub fn wire_format(mut self, format: WireFormat) -> Self {
+        self.wire_format = format;
+        self
+    }
+
+    /// The most characters of text sent in one request; longer text fails
+    /// with [`SafeCommsError::InvalidInput`] before it is sent. Defaults to
+    /// 100,000.
+    pub fn max_text_chars(mut self, max: usize) -> Self {
+        self.limits.max_text_chars = max;
+        self
+    }
+
+    /// The largest image sent, uploaded or base64-decoded; larger images
+    /// fail with [`SafeCommsError::InvalidInput`] before they are sent.
+    /// Defaults to 20 MiB.
+    pub fn max_image_bytes(mut self, max: usize) -> Self {
+        self.limits.max_image_bytes = max;
+        self
+    }
+
+    /// Adds a hook that runs around every request, after any added before
+    /// it.
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Signs every request with `signer`, after middleware has run.
+    pub fn request_signer(mut self, signer: impl RequestSigner + 'static) -> Self {
+        self.signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Replaces shortened links in text with their destinations before it is
+    /// moderated. Offsets in the response, such as PII entities, then refer
+    /// to the expanded text.
+    #[cfg(feature = "async")]
+    pub fn expand_short_urls(mut self, expander: UrlExpander) -> Self {
+        self.url_expander = Some(expander);
+        self
+    }
+
+    /// Compresses request bodies of at least `min_size` bytes, e.g. large
+    /// base64 images. Multipart uploads are not compressed. Requests are
+    /// compressed before they are signed.
+    #[cfg(feature = "compression")]
+    pub fn compress_requests(mut self, algorithm: Compression, min_size: usize) -> Self {
+        self.compression = Some(RequestCompression {
+            algorithm,
+            min_size,
+        });
+        self
+    }
+
+    /// Sends requests with `transport` instead of the built-in `reqwest`
+    /// client, e.g. a `hyper` client or a test double. Proxy, TLS and
+    /// connection options only configure the built-in client.
+    pub fn transport(mut self, transport: impl HttpTransport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Sends requests through `proxy`. May be called more than once, e.g. to
+    /// proxy HTTP and HTTPS differently; the first matching proxy is used.
+    /// Configuring a proxy disables the proxy environment variables.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Whether to honor the `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and
+    /// `NO_PROXY` environment variables when no proxy is configured. On by
+    /// default.
+    pub fn system_proxy(mut self, enabled: bool) -> Self {
+        self.system_proxy = enabled;
+        self
+    }
+
+    /// How long an idle pooled connection is kept open; `None` keeps it
+    /// until the server closes it. Defaults to 90 seconds.
+    pub fn pool_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connection.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// The most idle connections kept open per host. Unlimited by default.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.connection.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Sends HTTP/2 pings at this interval to keep connections alive. Off by
+    /// default.
+    pub fn http2_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.connection.http2_keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// How long to wait for a ping to be acknowledged before closing the
+    /// connection.
+    pub fn http2_keep_alive_timeout(mut self, timeout: Duration) -> Self {
+        self.connection.http2_keep_alive_timeout = Some(timeout);
+        self
+    }
+
+    /// Whether to ping connections that have no requests in flight, so pooled
+    /// connections survive idle periods.
+    pub fn http2_keep_alive_while_idle(mut self, enabled: bool) -> Self {
+        self.connection.http2_keep_alive_while_idle = Some(enabled);
+        self
+    }
+
+    /// Whether to disable Nagle's algorithm. On by default.
+    pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+        self.connection.tcp_nodelay = Some(enabled);
+        self
+    }
+
+    /// Sends TCP keepalive probes at this interval.
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.connection.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// How long to wait for a connection to be established.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connection.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// How long to wait for each read from a connection. Applies to every
+    /// endpoint class, as the HTTP client only supports it client-wide.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.connection.read_timeout = Some(timeout);
+        self
+    }
+
+    /// How long one attempt at a request may take in total, from sending it
+    /// to reading the whole response, unless its endpoint class has its own
+    /// timeout. Failovers and retries each get the full timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.default = Some(timeout);
+        self
+    }
+
+    /// The total timeout for requests of `class`, e.g. a long one for image
+    /// uploads and a short one for text.
+    pub fn timeout_for(mut self, class: EndpointClass, timeout: Duration) -> Self {
+        self.timeouts.classes.insert(class, timeout);
+        self
+    }
+
+    /// Resolves `host` to `addrs` instead of looking it up in DNS, e.g. to pin
+    /// the API to specific IPs under split-horizon DNS. A port of 0 uses the
+    /// URL's port. May be called once per host; TLS still verifies `host`.
+    pub fn resolve(mut self, host: &str, addrs: &[SocketAddr]) -> Self {
+        self.connection.resolve.push((host.to_string(), addrs.to_vec()));
+        self
+    }
+
+    /// Connects to every endpoint through the Unix socket at `path`, e.g. a
+    /// local sidecar that proxies to SafeComms. The base URL still sets the
+    /// scheme and `Host` header; proxy and TCP options are ignored. For
+    /// other connectors, see [`transport`](Self::transport).
+    #[cfg(unix)]
+    pub fn unix_socket(mut self, path: impl Into<PathBuf>) -> Self {
+        self.connection.unix_socket = Some(path.into());
+        self
+    }
+
+    /// Presents `identity` as a client certificate, for gateways that require
+    /// mutual TLS.
+    #[cfg(any(feature = "rustls", feature = "native-tls"))]
+    pub fn client_identity(mut self, identity: ClientIdentity) -> Self {
+        self.tls.identity = Some(identity);
+        self
+    }
+
+    /// Trusts the certificates in a PEM bundle in addition to the built-in
+    /// roots, e.g. a private gateway's CA. May be called more than once.
+    #[cfg(any(feature = "rustls", feature = "native-tls"))]
+    pub fn ca_bundle(mut self, pem: &[u8]) -> Self {
+        self.tls.ca_bundles.push(pem.to_vec());
+        self
+    }
+
+    /// Whether to trust the built-in root certificates. Turn off to trust
+    /// only the bundles passed to [`ca_bundle`](Self::ca_bundle).
+    #[cfg(any(feature = "rustls", feature = "native-tls"))]
+    pub fn built_in_roots(mut self, enabled: bool) -> Self {
+        self.tls.built_in_roots = enabled;
+        self
+    }
+
+    /// Detects the language of text locally whenever `moderate_text` is
+    /// called without one.
+    #[cfg(feature = "langdetect")]
+    pub fn auto_language(mut self) -> Self {
+        self.auto_language = true;
+        self
+    }
+
+    /// Records every moderation call made through the client in `store`.
+    #[cfg(feature = "persistence")]
+    pub fn result_store(mut self, store: impl ResultStore + 'static) -> Self {
+        self.result_store = Some(Arc::new(store));
+        self
+    }
+
+    pub fn build(self) -> Result<SafeCommsClient, SafeCommsError> {
+        let base_urls = if self.base_urls.is_empty() {
+            vec![self.environment.base_url().to_string()]
+        } else {
+            self.base_urls
+        };
+
+        let mut http = self.connection.apply(HttpClient::builder());
+        for proxy in &self.proxies {
+            http = http.proxy(proxy.to_reqwest()?);
+        }
+        if !self.system_proxy {
+            http = http.no_proxy();
+        }
+        #[cfg(any(feature = "rustls", feature = "native-tls"))]
+        let http = self.tls.apply(http)?;
+
+        let client = http.build()?;
+        Ok(SafeCommsClient {
+            transport: self.transport.unwrap_or_else(|| Arc::new(client.clone())),
+            client,
+            endpoints: Arc::new(Endpoints::new(base_urls).with_retry(self.retry)),
+            keys: Arc::new(ApiKeys::new(
+                std::iter::once(self.api_key).chain(self.extra_api_keys).collect(),
+            )),
+            environment: self.environment,
+            dry_run: self.dry_run.map(Arc::new),
+            idempotency_key: None,
+            correlation_id: None,
+            debug_logging: self.debug_logging,
+            replacement_style: self.replacement_style,
+            describe_emoji: self.describe_emoji,
+            lenient_parsing: self.lenient_parsing,
+            error_body_limit: self.error_body_limit,
+            timeouts: self.timeouts,
+            wire_format: self.wire_format,
+            limits: self.limits,
+            stats: Arc::default(),
+            middleware: Arc::new(self.middleware),
+            signer: self.signer,
+            tenant: None,
+            #[cfg(feature = "async")]
+            url_expander: self.url_expander.map(Arc::new),
+            #[cfg(feature = "compression")]
+            compression: self.compression,
+            #[cfg(feature = "langdetect")]
+            auto_language: self.auto_language,
+            #[cfg(feature = "persistence")]
+            result_store: self.result_store,
+        })
+    }
+}
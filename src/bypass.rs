@@ -0,0 +1,100 @@
+use crate::normalize::is_invisible;
+
+/// More combining marks than this on one character is treated as zalgo.
+pub(crate) const ZALGO_MARKS: usize = 2;
+/// Text with more combining marks than this fraction of its letters has
+/// excessive diacritics.
+const DIACRITIC_RATIO: f64 = 0.5;
+
+/// A sign that text has been written to slip past moderation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BypassSignal {
+    /// A word mixes Latin with Cyrillic or Greek letters, as in "pаypal" with
+    /// a Cyrillic "а".
+    MixedScripts { word: String },
+    /// Combining marks are stacked on a single character; `marks` is the
+    /// tallest stack.
+    Zalgo { marks: usize },
+    /// Far more combining marks than normal accented text would have.
+    ExcessiveDiacritics { marks: usize, letters: usize },
+    /// Characters that render as nothing, e.g. zero width spaces.
+    InvisibleCharacters { count: usize },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+}
+
+fn script(c: char) -> Option<Script> {
+    match c {
+        'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' => Some(Script::Latin),
+        '\u{0370}'..='\u{03FF}' => Some(Script::Greek),
+        '\u{0400}'..='\u{052F}' => Some(Script::Cyrillic),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_combining_mark(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+    )
+}
+
+/// Looks for common evasion techniques in `text`, so that suspicious
+/// messages can be sent with stricter options. Returns no signals for
+/// ordinary text.
+pub fn detect_bypass_signals(text: &str) -> Vec<BypassSignal> {
+    let mut signals = Vec::new();
+
+    for word in text.split(|c: char| c.is_whitespace() || c.is_ascii_punctuation()) {
+        let mut scripts = word.chars().filter_map(script);
+        if let Some(first) = scripts.next()
+            && scripts.any(|script| script != first)
+        {
+            signals.push(BypassSignal::MixedScripts {
+                word: word.chars().filter(|&c| !is_invisible(c)).collect(),
+            });
+        }
+    }
+
+    let mut marks = 0;
+    let mut stack = 0;
+    let mut tallest = 0;
+    let mut letters = 0;
+    let mut invisible = 0;
+
+    for c in text.chars() {
+        if is_combining_mark(c) {
+            marks += 1;
+            stack += 1;
+            tallest = tallest.max(stack);
+        } else {
+            stack = 0;
+            if c.is_alphabetic() {
+                letters += 1;
+            } else if is_invisible(c) {
+                invisible += 1;
+            }
+        }
+    }
+
+    if tallest > ZALGO_MARKS {
+        signals.push(BypassSignal::Zalgo { marks: tallest });
+    }
+    if marks > 0 && marks as f64 > letters as f64 * DIACRITIC_RATIO {
+        signals.push(BypassSignal::ExcessiveDiacritics { marks, letters });
+    }
+    if invisible > 0 {
+        signals.push(BypassSignal::InvisibleCharacters { count: invisible });
+    }
+
+    signals
+}
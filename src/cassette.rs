@@ -0,0 +1,293 @@
+use crate::{HttpTransport, SafeCommsError, TransportError};
+use futures_util::future::BoxFuture;
+use reqwest::header::AUTHORIZATION;
+use reqwest::{Request, Response};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const REDACTED: &str = "[REDACTED]";
+/// Response headers not worth keeping, or not safe to.
+const SKIPPED_HEADERS: [&str; 3] = ["set-cookie", "date", "transfer-encoding"];
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+struct RecordedRequest {
+    method: String,
+    /// Path and query.
+    path: String,
+    #[serde(flatten)]
+    body: RecordedBody,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct RecordedResponse {
+    status: u16,
+    headers: BTreeMap<String, String>,
+    #[serde(flatten)]
+    body: RecordedBody,
+}
+
+/// A body as text if it is UTF-8, such as JSON, and as hex otherwise, such
+/// as compressed or MessagePack bodies. Streamed bodies, i.e. multipart
+/// uploads, are not recorded and match any body on replay.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+struct RecordedBody {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    #[serde(rename = "bodyHex", skip_serializing_if = "Option::is_none")]
+    body_hex: Option<String>,
+}
+
+impl RecordedBody {
+    fn new(bytes: Option<&[u8]>, api_key: Option<&str>) -> Self {
+        match bytes.map(std::str::from_utf8) {
+            Some(Ok(text)) => Self {
+                body: Some(scrub(text, api_key)),
+                body_hex: None,
+            },
+            Some(Err(_)) => Self {
+                body: None,
+                body_hex: bytes.map(hex::encode),
+            },
+            None => Self::default(),
+        }
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        match (&self.body, &self.body_hex) {
+            (Some(text), _) => text.as_bytes().to_vec(),
+            (None, Some(encoded)) => hex::decode(encoded).unwrap_or_default(),
+            (None, None) => Vec::new(),
+        }
+    }
+
+    fn matches(&self, other: &RecordedBody) -> bool {
+        *self == RecordedBody::default() || *other == RecordedBody::default() || self == other
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+enum Mode {
+    Record(Arc<dyn HttpTransport>),
+    Replay,
+}
+
+/// Records API calls to a file and plays them back, so that integration
+/// tests run without network access or API costs; set it with
+/// [`SafeCommsClientBuilder::transport`](crate::SafeCommsClientBuilder::transport).
+///
+/// In record mode every request is sent and the request and response are
+/// written to the cassette, a JSON file meant to be checked in. The API key
+/// is never written: request headers are left out and any occurrence of the
+/// key is replaced with `[REDACTED]`. In replay mode requests are answered
+/// from the cassette by method, path and body, in the order they were
+/// recorded, and a request that was not recorded fails.
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+    interactions: Mutex<Vec<Interaction>>,
+    played: Mutex<Vec<bool>>,
+}
+
+impl Cassette {
+    /// Sends requests with a default `reqwest` client and records them to
+    /// `path`, replacing what it held.
+    pub fn record(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            mode: Mode::Record(Arc::new(reqwest::Client::new())),
+            interactions: Mutex::default(),
+            played: Mutex::default(),
+        }
+    }
+
+    /// Answers requests from the cassette at `path`.
+    pub fn replay(path: impl AsRef<Path>) -> Result<Self, SafeCommsError> {
+        let path = path.as_ref().to_path_buf();
+        let contents = std::fs::read(&path).map_err(|e| {
+            SafeCommsError::ApiError(format!("Failed to read cassette {}: {}", path.display(), e))
+        })?;
+        let file: CassetteFile = serde_json::from_slice(&contents)?;
+        let played = vec![false; file.interactions.len()];
+
+        Ok(Self {
+            path,
+            mode: Mode::Replay,
+            interactions: Mutex::new(file.interactions),
+            played: Mutex::new(played),
+        })
+    }
+
+    /// Replays the cassette at `path` if there is one, and records it
+    /// otherwise. Delete the file to record it again.
+    pub fn auto(path: impl AsRef<Path>) -> Result<Self, SafeCommsError> {
+        if path.as_ref().exists() {
+            Self::replay(path)
+        } else {
+            Ok(Self::record(path))
+        }
+    }
+
+    /// Sends recorded requests with `transport` instead of a default
+    /// `reqwest` client. Has no effect when replaying.
+    pub fn transport(mut self, transport: impl HttpTransport + 'static) -> Self {
+        if let Mode::Record(_) = self.mode {
+            self.mode = Mode::Record(Arc::new(transport));
+        }
+        self
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.mode, Mode::Replay)
+    }
+
+    async fn record_request(
+        &self,
+        transport: &dyn HttpTransport,
+        request: Request,
+    ) -> Result<Response, TransportError> {
+        let api_key = api_key(&request);
+        let recorded = recorded_request(&request, api_key.as_deref());
+
+        let response = transport.execute(request).await?;
+        let status = response.status().as_u16();
+        let headers: BTreeMap<String, String> = response
+            .headers()
+            .iter()
+            .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.as_str()))
+            .filter_map(|(name, value)| {
+                let value = value.to_str().ok()?;
+                Some((name.to_string(), scrub(value, api_key.as_deref())))
+            })
+            .collect();
+        let body = response.bytes().await?;
+
+        let interaction = Interaction {
+            request: recorded,
+            response: RecordedResponse {
+                status,
+                headers,
+                body: RecordedBody::new(Some(&body), api_key.as_deref()),
+            },
+        };
+        let replayed = to_response(&interaction.response, body.to_vec());
+        self.save(interaction)?;
+        replayed
+    }
+
+    fn replay_request(&self, request: &Request) -> Result<Response, TransportError> {
+        let recorded = recorded_request(request, api_key(request).as_deref());
+        let interactions = self.interactions.lock().unwrap_or_else(|e| e.into_inner());
+        let mut played = self.played.lock().unwrap_or_else(|e| e.into_inner());
+
+        let matching: Vec<usize> = interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| {
+                interaction.request.method == recorded.method
+                    && interaction.request.path == recorded.path
+                    && interaction.request.body.matches(&recorded.body)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        // Once every matching interaction has been played, the last one is
+        // repeated, e.g. for polling.
+        let index = matching
+            .iter()
+            .copied()
+            .find(|&index| !played[index])
+            .or(matching.last().copied())
+            .ok_or_else(|| {
+                TransportError::Other(
+                    format!(
+                        "No interaction for {} {} in cassette {}",
+                        recorded.method,
+                        recorded.path,
+                        self.path.display()
+                    )
+                    .into(),
+                )
+            })?;
+
+        played[index] = true;
+        let response = &interactions[index].response;
+        to_response(response, response.body.bytes())
+    }
+
+    fn save(&self, interaction: Interaction) -> Result<(), TransportError> {
+        let mut interactions = self.interactions.lock().unwrap_or_else(|e| e.into_inner());
+        interactions.push(interaction);
+        let file = CassetteFile {
+            interactions: interactions.clone(),
+        };
+
+        let write = || -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            if let Some(parent) = self.path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&self.path, serde_json::to_vec_pretty(&file)?)?;
+            Ok(())
+        };
+        write().map_err(TransportError::Other)
+    }
+}
+
+impl HttpTransport for Cassette {
+    fn execute(&self, request: Request) -> BoxFuture<'_, Result<Response, TransportError>> {
+        Box::pin(async move {
+            match &self.mode {
+                Mode::Record(transport) => self.record_request(transport.as_ref(), request).await,
+                Mode::Replay => self.replay_request(&request),
+            }
+        })
+    }
+}
+
+fn api_key(request: &Request) -> Option<String> {
+    let value = request.headers().get(AUTHORIZATION)?.to_str().ok()?;
+    let key = value.strip_prefix("Bearer ").unwrap_or(value).trim();
+    (!key.is_empty()).then(|| key.to_string())
+}
+
+fn recorded_request(request: &Request, api_key: Option<&str>) -> RecordedRequest {
+    let url = request.url();
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+
+    RecordedRequest {
+        method: request.method().to_string(),
+        path: scrub(&path, api_key),
+        body: RecordedBody::new(request.body().and_then(|body| body.as_bytes()), api_key),
+    }
+}
+
+fn scrub(text: &str, api_key: Option<&str>) -> String {
+    match api_key {
+        Some(key) => text.replace(key, REDACTED),
+        None => text.to_string(),
+    }
+}
+
+fn to_response(recorded: &RecordedResponse, body: Vec<u8>) -> Result<Response, TransportError> {
+    let mut response = http::Response::builder().status(recorded.status);
+    for (name, value) in &recorded.headers {
+        response = response.header(name, value);
+    }
+    let response = response
+        .body(body)
+        .map_err(|e| TransportError::Other(Box::new(e)))?;
+    Ok(Response::from(response))
+}
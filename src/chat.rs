@@ -0,0 +1,102 @@
+use crate::reputation::{Reputation, Standing};
+use crate::{
+    Action, ContextMessage, ContextWindow, Metadata, ModerationRequest, ModerationResponse, Policy,
+    SafeCommsClient, SafeCommsError,
+};
+
+const DEFAULT_HISTORY: usize = 10;
+
+/// The outcome of [`ChatModerator::moderate_chat_message`].
+#[derive(Debug, Clone)]
+pub struct ChatVerdict {
+    pub action: Action,
+    pub response: ModerationResponse,
+    /// The author's standing after the message, if a [`Reputation`] is
+    /// kept.
+    pub standing: Option<Standing>,
+}
+
+/// Moderates chat messages in the context of their channel.
+///
+/// Each channel's most recent messages are kept in a [`ContextWindow`] and
+/// sent along with the next message, so that replies are judged in context.
+/// Only messages the policy lets through are kept, as the API masked them if
+/// it did.
+pub struct ChatModerator {
+    client: SafeCommsClient,
+    policy: Policy,
+    reputation: Option<Reputation>,
+    context: ContextWindow,
+}
+
+impl ChatModerator {
+    pub fn new(client: SafeCommsClient) -> Self {
+        Self {
+            client,
+            policy: Policy::default(),
+            reputation: None,
+            context: ContextWindow::new(DEFAULT_HISTORY),
+        }
+    }
+
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Records every message's strikes against its author.
+    pub fn reputation(mut self, reputation: Reputation) -> Self {
+        self.reputation = Some(reputation);
+        self
+    }
+
+    /// Which recent messages of a channel are sent as context. Defaults to
+    /// the last 10.
+    pub fn context_window(mut self, window: ContextWindow) -> Self {
+        self.context = window;
+        self
+    }
+
+    /// Moderates `text`, posted by `user_id` in `channel_id`, with the
+    /// channel's recent messages as context. Updates the author's standing
+    /// and returns the action the policy takes.
+    pub async fn moderate_chat_message(
+        &self,
+        user_id: &str,
+        channel_id: &str,
+        text: &str,
+    ) -> Result<ChatVerdict, SafeCommsError> {
+        let request = ModerationRequest {
+            content: text.to_string(),
+            metadata: Metadata::new().user_id(user_id).channel_id(channel_id),
+            context: self.context.messages(channel_id),
+            ..ModerationRequest::default()
+        };
+        let response = self.client.moderate_request(&request).await?;
+
+        let standing = match &self.reputation {
+            Some(reputation) => Some(reputation.record(user_id, &response).await?),
+            None => None,
+        };
+        let action = self.policy.evaluate(&response);
+        if action <= Action::Flag {
+            let delivered = response.safe_content.as_deref().unwrap_or(text);
+            let message = ContextMessage {
+                user_id: user_id.to_string(),
+                text: delivered.to_string(),
+            };
+            self.context.push(channel_id, message);
+        }
+
+        Ok(ChatVerdict {
+            action,
+            response,
+            standing,
+        })
+    }
+
+    /// Forgets a channel's recent messages, e.g. when it is deleted.
+    pub fn clear(&self, channel_id: &str) {
+        self.context.clear(channel_id);
+    }
+}
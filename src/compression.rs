@@ -0,0 +1,68 @@
+use flate2::write::{GzEncoder, ZlibEncoder};
+use reqwest::Request;
+use reqwest::header::{CONTENT_ENCODING, HeaderValue};
+use std::io::Write;
+
+/// How request bodies are compressed; see
+/// [`SafeCommsClientBuilder::compress_requests`](crate::SafeCommsClientBuilder::compress_requests).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    /// Zlib-wrapped deflate, as HTTP's `deflate` encoding specifies.
+    Deflate,
+}
+
+impl Compression {
+    fn content_encoding(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Deflate => "deflate",
+        }
+    }
+
+    fn compress(self, body: &[u8]) -> std::io::Result<Vec<u8>> {
+        let level = flate2::Compression::default();
+        match self {
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), level);
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+            Compression::Deflate => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), level);
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RequestCompression {
+    pub(crate) algorithm: Compression,
+    pub(crate) min_size: usize,
+}
+
+impl RequestCompression {
+    /// Compresses the body of `request` if it is at least `min_size` bytes.
+    /// Streamed bodies, i.e. multipart uploads, are left alone.
+    pub(crate) fn apply(self, request: &mut Request) {
+        let Some(body) = request.body().and_then(|body| body.as_bytes()) else {
+            return;
+        };
+        if body.len() < self.min_size {
+            return;
+        }
+
+        match self.algorithm.compress(body) {
+            Ok(compressed) => {
+                *request.body_mut() = Some(compressed.into());
+                request.headers_mut().insert(
+                    CONTENT_ENCODING,
+                    HeaderValue::from_static(self.algorithm.content_encoding()),
+                );
+            }
+            Err(e) => log::warn!("Failed to compress request body: {}", e),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use reqwest::ClientBuilder;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Connection settings collected by the builder. Unset settings keep
+/// reqwest's defaults.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConnectionConfig {
+    pub(crate) pool_idle_timeout: Option<Option<Duration>>,
+    pub(crate) pool_max_idle_per_host: Option<usize>,
+    pub(crate) http2_keep_alive_interval: Option<Duration>,
+    pub(crate) http2_keep_alive_timeout: Option<Duration>,
+    pub(crate) http2_keep_alive_while_idle: Option<bool>,
+    pub(crate) tcp_nodelay: Option<bool>,
+    pub(crate) tcp_keepalive: Option<Duration>,
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) read_timeout: Option<Duration>,
+    pub(crate) resolve: Vec<(String, Vec<SocketAddr>)>,
+    #[cfg(unix)]
+    pub(crate) unix_socket: Option<PathBuf>,
+}
+
+impl ConnectionConfig {
+    pub(crate) fn apply(&self, mut http: ClientBuilder) -> ClientBuilder {
+        if let Some(timeout) = self.pool_idle_timeout {
+            http = http.pool_idle_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            http = http.pool_max_idle_per_host(max);
+        }
+        if let Some(interval) = self.http2_keep_alive_interval {
+            http = http.http2_keep_alive_interval(interval);
+        }
+        if let Some(timeout) = self.http2_keep_alive_timeout {
+            http = http.http2_keep_alive_timeout(timeout);
+        }
+        if let Some(enabled) = self.http2_keep_alive_while_idle {
+            http = http.http2_keep_alive_while_idle(enabled);
+        }
+        if let Some(enabled) = self.tcp_nodelay {
+            http = http.tcp_nodelay(enabled);
+        }
+        if let Some(interval) = self.tcp_keepalive {
+            http = http.tcp_keepalive(interval);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            http = http.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.read_timeout {
+            http = http.read_timeout(timeout);
+        }
+        for (host, addrs) in &self.resolve {
+            http = http.resolve_to_addrs(host, addrs);
+        }
+        #[cfg(unix)]
+        if let Some(path) = &self.unix_socket {
+            http = http.unix_socket(path.clone());
+        }
+        http
+    }
+}
@@ -0,0 +1,151 @@
+use crate::ContextMessage;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const DEFAULT_MAX_BYTES: usize = 8 * 1024;
+
+#[derive(Default)]
+struct Conversation {
+    messages: VecDeque<ContextMessage>,
+    bytes: usize,
+    used_at: Option<Instant>,
+}
+
+impl Conversation {
+    fn push(&mut self, message: ContextMessage, max_messages: usize, max_bytes: usize) {
+        self.bytes += size(&message);
+        self.messages.push_back(message);
+        while self.messages.len() > max_messages
+            || (self.bytes > max_bytes && self.messages.len() > 1)
+        {
+            if let Some(oldest) = self.messages.pop_front() {
+                self.bytes -= size(&oldest);
+            }
+        }
+
+        // A single message over the budget keeps its end, which is what the
+        // next message most likely responds to.
+        if self.bytes > max_bytes
+            && let Some(message) = self.messages.front_mut()
+        {
+            let excess = self.bytes - max_bytes;
+            let cut = message.text.ceil_char_boundary(excess.min(message.text.len()));
+            message.text.drain(..cut);
+            self.bytes -= cut;
+        }
+    }
+}
+
+fn size(message: &ContextMessage) -> usize {
+    message.user_id.len() + message.text.len()
+}
+
+/// The most recent messages of each conversation, to send as context when
+/// moderating the next one.
+///
+/// A conversation keeps at most `max_messages` messages and, by default,
+/// 8 KiB of user IDs and text; the oldest messages are dropped first.
+/// Conversations can also be dropped once idle, or when there are too many,
+/// least recently used first.
+pub struct ContextWindow {
+    max_messages: usize,
+    max_bytes: usize,
+    max_conversations: Option<usize>,
+    idle_timeout: Option<Duration>,
+    conversations: Mutex<HashMap<String, Conversation>>,
+}
+
+impl ContextWindow {
+    pub fn new(max_messages: usize) -> Self {
+        Self {
+            max_messages,
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_conversations: None,
+            idle_timeout: None,
+            conversations: Mutex::default(),
+        }
+    }
+
+    /// How many bytes of user IDs and text a conversation may keep.
+    pub fn max_bytes(mut self, bytes: usize) -> Self {
+        self.max_bytes = bytes;
+        self
+    }
+
+    /// How many conversations are kept before the least recently used is
+    /// dropped.
+    pub fn max_conversations(mut self, conversations: usize) -> Self {
+        self.max_conversations = Some(conversations);
+        self
+    }
+
+    /// Drops conversations that have seen no message for `timeout`.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Adds a message to the end of a conversation.
+    pub fn push(&self, conversation_id: &str, message: ContextMessage) {
+        if self.max_messages == 0 || self.max_bytes == 0 {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut conversations = self.conversations.lock().unwrap_or_else(|e| e.into_inner());
+        self.evict_idle(&mut conversations, now);
+
+        let conversation = conversations.entry(conversation_id.to_string()).or_default();
+        conversation.push(message, self.max_messages, self.max_bytes);
+        conversation.used_at = Some(now);
+
+        if let Some(max) = self.max_conversations {
+            while conversations.len() > max {
+                let least_recent = conversations
+                    .iter()
+                    .min_by_key(|(_, conversation)| conversation.used_at)
+                    .map(|(id, _)| id.clone());
+                match least_recent {
+                    Some(id) => conversations.remove(&id),
+                    None => break,
+                };
+            }
+        }
+    }
+
+    /// A conversation's messages, oldest first.
+    pub fn messages(&self, conversation_id: &str) -> Vec<ContextMessage> {
+        let mut conversations = self.conversations.lock().unwrap_or_else(|e| e.into_inner());
+        self.evict_idle(&mut conversations, Instant::now());
+        conversations
+            .get(conversation_id)
+            .map(|conversation| conversation.messages.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Forgets a conversation, e.g. when its channel is deleted.
+    pub fn clear(&self, conversation_id: &str) {
+        self.conversations
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(conversation_id);
+    }
+
+    /// How many conversations are kept.
+    pub fn len(&self) -> usize {
+        self.conversations.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn evict_idle(&self, conversations: &mut HashMap<String, Conversation>, now: Instant) {
+        if let Some(timeout) = self.idle_timeout {
+            conversations.retain(|_, conversation| {
+                conversation.used_at.is_some_and(|used_at| now.duration_since(used_at) < timeout)
+            });
+        }
+    }
+}
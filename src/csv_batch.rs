@@ -0,0 +1,150 @@
+use crate::{Language, ModerationResponse, SafeCommsClient, SafeCommsError};
+use futures_util::{StreamExt, stream};
+use std::io::{Read, Write};
+
+const DEFAULT_CONCURRENCY: usize = 4;
+const DEFAULT_CATEGORY_THRESHOLD: f64 = 0.5;
+const APPENDED_COLUMNS: [&str; 5] = ["verdict", "severity", "categories", "safe_content", "error"];
+
+/// Which column of a CSV file holds the text to moderate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvColumn {
+    /// The column with this header.
+    Name(String),
+    /// The column at this zero-based position.
+    Index(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub column: CsvColumn,
+    /// Whether the first row is a header. The appended columns get headers
+    /// only if it is.
+    pub has_headers: bool,
+    pub language: Option<Language>,
+    /// Asks the API for `safe_content`, with unsafe text replaced.
+    pub replace: bool,
+    /// The score at which a category is listed.
+    pub category_threshold: f64,
+    /// How many rows are moderated at once.
+    pub concurrency: usize,
+}
+
+impl CsvOptions {
+    pub fn column(column: CsvColumn) -> Self {
+        Self {
+            column,
+            has_headers: true,
+            language: None,
+            replace: false,
+            category_threshold: DEFAULT_CATEGORY_THRESHOLD,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CsvSummary {
+    pub rows: u64,
+    pub clean: u64,
+    pub flagged: u64,
+    pub errors: u64,
+}
+
+impl SafeCommsClient {
+    /// Moderates one column of a CSV file and writes the file back out with
+    /// `verdict`, `severity`, `categories`, `safe_content` and `error`
+    /// columns appended.
+    ///
+    /// Rows that fail to moderate are written with a verdict of `error` and
+    /// the reason in the `error` column, so one bad row does not lose the
+    /// rest. Categories are written as `name:score` pairs separated by `;`.
+    pub async fn moderate_csv<R: Read, W: Write>(
+        &self,
+        input: R,
+        output: W,
+        options: &CsvOptions,
+    ) -> Result<CsvSummary, SafeCommsError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(options.has_headers)
+            .flexible(true)
+            .from_reader(input);
+        let mut writer = csv::Writer::from_writer(output);
+
+        let column = match &options.column {
+            CsvColumn::Index(index) => *index,
+            CsvColumn::Name(name) => reader
+                .headers()?
+                .iter()
+                .position(|header| header == name)
+                .ok_or_else(|| SafeCommsError::ApiError(format!("No CSV column named {}", name)))?,
+        };
+        if options.has_headers {
+            let mut headers = reader.headers()?.clone();
+            headers.extend(APPENDED_COLUMNS);
+            writer.write_record(&headers)?;
+        }
+
+        let records = reader.records().collect::<Result<Vec<_>, _>>()?;
+        let language = &options.language;
+        let replace = options.replace.then_some(true);
+        let mut results = stream::iter(&records)
+            .map(|record| async move {
+                let content = record.get(column).unwrap_or_default();
+                let result = self
+                    .moderate_text(content, language.clone(), replace, None, None, None)
+                    .await;
+                (record, result)
+            })
+            .buffered(options.concurrency.max(1));
+
+        let mut summary = CsvSummary::default();
+        while let Some((record, result)) = results.next().await {
+            let mut record = record.clone();
+            summary.rows += 1;
+            match result {
+                Ok(response) => {
+                    if response.is_clean {
+                        summary.clean += 1;
+                    } else {
+                        summary.flagged += 1;
+                    }
+                    record.extend(verdict_columns(&response, options.category_threshold));
+                }
+                Err(e) => {
+                    summary.errors += 1;
+                    record.extend(["error", "", "", "", &e.to_string()]);
+                }
+            }
+            writer.write_record(&record)?;
+        }
+
+        writer
+            .flush()
+            .map_err(|e| SafeCommsError::ApiError(format!("Failed to write CSV: {}", e)))?;
+        Ok(summary)
+    }
+}
+
+fn verdict_columns(response: &ModerationResponse, category_threshold: f64) -> [String; 5] {
+    let mut categories: Vec<(&String, f64)> = response
+        .category_scores
+        .iter()
+        .flatten()
+        .filter_map(|(category, score)| Some((category, score.parse().ok()?)))
+        .filter(|(_, score)| *score >= category_threshold)
+        .collect();
+    categories.sort_by(|a, b| a.0.cmp(b.0));
+
+    [
+        if response.is_clean { "clean" } else { "flagged" }.to_string(),
+        response.severity.clone().unwrap_or_default(),
+        categories
+            .iter()
+            .map(|(category, score)| format!("{}:{}", category, score))
+            .collect::<Vec<_>>()
+            .join(";"),
+        response.safe_content.clone().unwrap_or_default(),
+        String::new(),
+    ]
+}
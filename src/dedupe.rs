@@ -0,0 +1,191 @@
+use crate::normalize::canonicalize;
+use crate::{Language, ModerationResponse, SafeCommsClient, SafeCommsError};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const DEFAULT_WINDOW: Duration = Duration::from_secs(5 * 60);
+const DEFAULT_MAX_DISTANCE: u32 = 3;
+const DEFAULT_CAPACITY: usize = 10_000;
+/// Length of the character shingles a fingerprint is built from.
+const SHINGLE: usize = 3;
+/// Texts with fewer shingles, such as a few emoji or a short word, are too
+/// alike to tell apart by fingerprint and are always moderated.
+const MIN_SHINGLES: usize = 8;
+
+/// What a [`Deduper`] has seen so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DedupeStats {
+    pub checked: u64,
+    /// Texts that reused the verdict of a near-duplicate instead of calling
+    /// the API.
+    pub suppressed: u64,
+}
+
+struct Seen {
+    fingerprint: u64,
+    at: Instant,
+    response: ModerationResponse,
+}
+
+/// Reuses verdicts for near-identical texts, such as the thousands of
+/// variations of one message in a spam wave.
+///
+/// Texts are fingerprinted with a 64-bit simhash of their canonical form, so
+/// changed letters, added words and leetspeak barely move the fingerprint.
+/// A text whose fingerprint is within `max_distance` bits of one moderated
+/// in the last `window` gets that text's response, metadata included,
+/// without calling the API.
+///
+/// Texts that arrive before the first of their kind has been moderated are
+/// all sent to the API, as are texts too short to fingerprint reliably,
+/// including those made only of emoji or punctuation.
+pub struct Deduper {
+    window: Duration,
+    max_distance: u32,
+    capacity: usize,
+    seen: Mutex<VecDeque<Seen>>,
+    stats: Mutex<DedupeStats>,
+}
+
+impl Deduper {
+    /// Reuses verdicts for five minutes, for texts within 3 bits of each
+    /// other, remembering up to 10,000 texts.
+    pub fn new() -> Self {
+        Self {
+            window: DEFAULT_WINDOW,
+            max_distance: DEFAULT_MAX_DISTANCE,
+            capacity: DEFAULT_CAPACITY,
+            seen: Mutex::default(),
+            stats: Mutex::default(),
+        }
+    }
+
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// How many of the 64 fingerprint bits may differ for texts to count as
+    /// duplicates. 0 only matches texts that are the same once canonical.
+    pub fn max_distance(mut self, bits: u32) -> Self {
+        self.max_distance = bits;
+        self
+    }
+
+    /// How many recent texts are compared against, oldest dropped first.
+    pub fn capacity(mut self, texts: usize) -> Self {
+        self.capacity = texts;
+        self
+    }
+
+    /// Moderates `content`, unless a near-duplicate was moderated within the
+    /// window, in which case its response is returned.
+    pub async fn moderate(
+        &self,
+        client: &SafeCommsClient,
+        content: &str,
+        language: Option<&str>,
+    ) -> Result<ModerationResponse, SafeCommsError> {
+        let fingerprint = fingerprint(content);
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).checked += 1;
+        let cached = fingerprint.and_then(|fingerprint| self.lookup(fingerprint));
+        if fingerprint.is_some() {
+            client.stats.record_cache_lookup(cached.is_some());
+            #[cfg(feature = "metrics")]
+            crate::meters::record_cache_lookup(cached.is_some());
+        }
+        if let Some(response) = cached {
+            self.stats.lock().unwrap_or_else(|e| e.into_inner()).suppressed += 1;
+            return Ok(response);
+        }
+
+        let response = client
+            .moderate_text(content, language.map(Language::from), None, None, None, None)
+            .await?;
+        let Some(fingerprint) = fingerprint else {
+            return Ok(response);
+        };
+
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        if self.capacity > 0 {
+            if seen.len() == self.capacity {
+                seen.pop_front();
+            }
+            seen.push_back(Seen {
+                fingerprint,
+                at: Instant::now(),
+                response: response.clone(),
+            });
+        }
+        Ok(response)
+    }
+
+    pub fn stats(&self) -> DedupeStats {
+        *self.stats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lookup(&self, fingerprint: u64) -> Option<ModerationResponse> {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        while seen.front().is_some_and(|oldest| now.duration_since(oldest.at) > self.window) {
+            seen.pop_front();
+        }
+        seen.iter()
+            .rev()
+            .find(|seen| (seen.fingerprint ^ fingerprint).count_ones() <= self.max_distance)
+            .map(|seen| seen.response.clone())
+    }
+}
+
+impl Default for Deduper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A 64-bit simhash of the canonical text's character shingles, with words
+/// separated by single spaces. Text with no letters or digits hashes to 0.
+pub fn simhash(text: &str) -> u64 {
+    hash_shingles(&shingle_chars(text))
+}
+
+/// The simhash of `text`, if it has enough shingles to tell it apart from
+/// unrelated text.
+fn fingerprint(text: &str) -> Option<u64> {
+    let chars = shingle_chars(text);
+    (chars.len() + 1 >= SHINGLE + MIN_SHINGLES).then(|| hash_shingles(&chars))
+}
+
+fn shingle_chars(text: &str) -> Vec<char> {
+    canonicalize(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .collect()
+}
+
+fn hash_shingles(chars: &[char]) -> u64 {
+    let mut weights = [0i32; 64];
+    for shingle in chars.windows(SHINGLE.min(chars.len()).max(1)) {
+        let hash = fnv1a(shingle);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            *weight += if hash >> bit & 1 == 1 { 1 } else { -1 };
+        }
+    }
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |fingerprint, (bit, _)| fingerprint | 1 << bit)
+}
+
+/// FNV-1a, for fingerprints that are stable across processes.
+fn fnv1a(chars: &[char]) -> u64 {
+    chars.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &c| {
+        (hash ^ u64::from(u32::from(c))).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
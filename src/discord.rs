@@ -0,0 +1,75 @@
+use crate::{AttachmentVerdict, MessageVerdict, SafeCommsClient, SafeCommsError};
+use futures_util::future::join_all;
+use serenity::model::channel::{Attachment, Message};
+
+const MAX_ATTACHMENT_BYTES: u32 = 20 * 1024 * 1024;
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "gif", "webp"];
+
+impl SafeCommsClient {
+    /// Moderates a Discord message's text and image attachments concurrently.
+    ///
+    /// Attachments are downloaded and uploaded to the API; non-image
+    /// attachments are skipped. A failure to moderate the text fails the whole
+    /// call, while attachment failures are reported per attachment and
+    /// counted in the verdict's `failed_parts`.
+    pub async fn moderate_discord_message(
+        &self,
+        message: &Message,
+    ) -> Result<MessageVerdict, SafeCommsError> {
+        let text = async {
+            if message.content.trim().is_empty() {
+                return Ok(None);
+            }
+            self.moderate_text(&message.content, None, None, None, None, None)
+                .await
+                .map(Some)
+        };
+
+        let attachments = join_all(
+            message
+                .attachments
+                .iter()
+                .filter(|attachment| is_image(attachment))
+                .map(|attachment| self.moderate_discord_attachment(attachment)),
+        );
+
+        let (text, attachments) = futures_util::join!(text, attachments);
+        Ok(MessageVerdict::new(text?, attachments))
+    }
+
+    async fn moderate_discord_attachment(&self, attachment: &Attachment) -> AttachmentVerdict {
+        let result = async {
+            if attachment.size > MAX_ATTACHMENT_BYTES {
+                return Err(SafeCommsError::ApiError(format!(
+                    "Attachment is larger than {} bytes",
+                    MAX_ATTACHMENT_BYTES
+                )));
+            }
+
+            let bytes = attachment.download().await.map_err(|e| {
+                SafeCommsError::ApiError(format!("Failed to download attachment: {}", e))
+            })?;
+
+            self.moderate_image_bytes(bytes, &attachment.filename, Default::default())
+                .await
+        };
+
+        AttachmentVerdict {
+            file_name: attachment.filename.clone(),
+            result: result.await,
+        }
+    }
+}
+
+fn is_image(attachment: &Attachment) -> bool {
+    if let Some(content_type) = &attachment.content_type {
+        return content_type.starts_with("image/");
+    }
+
+    attachment
+        .filename
+        .rsplit_once('.')
+        .is_some_and(|(_, extension)| {
+            IMAGE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        })
+}
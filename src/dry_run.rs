@@ -0,0 +1,86 @@
+use crate::SafeCommsError;
+use reqwest::RequestBuilder;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+/// Synthetic responses returned instead of calling the API.
+///
+/// Requests are still built and serialized, so invalid input fails exactly as
+/// it would against the real API, and every call is logged.
+#[derive(Debug, Clone)]
+pub struct DryRun {
+    responses: HashMap<String, Value>,
+}
+
+impl DryRun {
+    pub fn new() -> Self {
+        let clean = json!({ "isClean": true, "isBypassAttempt": false });
+        let usage = json!({
+            "tier": "dry-run",
+            "rateLimit": 0,
+            "tokensUsed": 0,
+            "remainingTokens": 0,
+        });
+
+        Self {
+            responses: HashMap::new(),
+        }
+        .respond("/moderation/text", clean.clone())
+        .respond("/moderation/image", clean.clone())
+        .respond("/moderation/image/upload", clean.clone())
+        .respond(
+            "/moderation/image/async",
+            json!({ "jobId": "dry-run", "status": "completed", "result": clean }),
+        )
+        .respond(
+            "/moderation/prompt-injection",
+            json!({ "isInjection": false, "score": 0.0 }),
+        )
+        .respond("/usage", usage)
+    }
+
+    /// Overrides the response returned for requests to `path`, e.g.
+    /// `"/moderation/text"`.
+    pub fn respond(mut self, path: &str, response: Value) -> Self {
+        self.responses.insert(path.to_string(), response);
+        self
+    }
+
+    pub(crate) fn execute<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<T, SafeCommsError> {
+        let request = request.build()?;
+        let path = request.url().path();
+        let body_len = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map_or(0, |bytes| bytes.len());
+
+        log::info!(
+            "dry run: {} {} ({} byte body)",
+            request.method(),
+            request.url(),
+            body_len
+        );
+
+        let response = self
+            .responses
+            .iter()
+            .filter(|(suffix, _)| path.ends_with(suffix.as_str()))
+            .max_by_key(|(suffix, _)| suffix.len())
+            .map(|(_, response)| response.clone())
+            .ok_or_else(|| {
+                SafeCommsError::ApiError(format!("No dry-run response configured for {}", path))
+            })?;
+
+        Ok(serde_json::from_value(response)?)
+    }
+}
+
+impl Default for DryRun {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,302 @@
+use crate::{
+    ModerationRequest, ModerationResponse, PiiEntity, SafeCommsClient, SafeCommsError,
+    TextModerationRequest,
+};
+use std::ops::Range;
+
+/// How much unchanged text on either side of an edit is sent with it, in
+/// bytes, so the change is judged in context.
+const EDIT_CONTEXT: usize = 200;
+
+impl SafeCommsClient {
+    /// Moderates an edited message, given the request the original was
+    /// moderated with and the response it got. The edit is moderated with
+    /// the same language, profile, replacement options, metadata and
+    /// context.
+    ///
+    /// An unchanged message keeps its response. If the original was clean
+    /// and left as written, only the changed part is sent, with some of the
+    /// text around it, and the result is merged into a response for the
+    /// whole message: safe content is spliced back in and PII offsets are
+    /// moved to where they are in `edited`. Otherwise `edited` is moderated
+    /// in full.
+    pub async fn moderate_edit(
+        &self,
+        request: &ModerationRequest,
+        edited: &str,
+        previous: &ModerationResponse,
+    ) -> Result<ModerationResponse, SafeCommsError> {
+        let original = request.content.as_str();
+        if original == edited {
+            return Ok(previous.clone());
+        }
+
+        let approved = previous.is_clean
+            && previous.safe_content.as_deref().is_none_or(|safe| safe == original);
+        if !approved {
+            let request = TextModerationRequest {
+                content: edited,
+                ..self.text_request(request)
+            };
+            return self.send_text(request).await;
+        }
+
+        let edit = Edit::between(original, edited);
+        let excerpt = TextModerationRequest {
+            content: &edited[edit.edited.clone()],
+            ..self.text_request(request)
+        };
+        let mut response = self.send_text(excerpt).await?;
+
+        if let Some(safe) = response.safe_content.take() {
+            response.safe_content = Some(format!(
+                "{}{}{}",
+                &edited[..edit.edited.start],
+                safe,
+                &edited[edit.edited.end..]
+            ));
+        }
+        if previous.pii_entities.is_some() || response.pii_entities.is_some() {
+            response.pii_entities = Some(edit.merge_entities(
+                original,
+                edited,
+                previous.pii_entities.as_deref().unwrap_or_default(),
+                response.pii_entities.as_deref().unwrap_or_default(),
+            ));
+        }
+        Ok(response)
+    }
+}
+
+/// Where two versions of a text differ, widened by [`EDIT_CONTEXT`] to
+/// whole words, as byte ranges in each.
+struct Edit {
+    original: Range<usize>,
+    edited: Range<usize>,
+}
+
+impl Edit {
+    fn between(original: &str, edited: &str) -> Self {
+        let prefix = original
+            .char_indices()
+            .zip(edited.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((offset, c), _)| offset + c.len_utf8());
+        let max_suffix = original.len().min(edited.len()) - prefix;
+        let suffix = original[prefix..]
+            .chars()
+            .rev()
+            .zip(edited[prefix..].chars().rev())
+            .take_while(|(a, b)| a == b)
+            .map(|(c, _)| c.len_utf8())
+            .scan(0, |total, len| {
+                *total += len;
+                Some(*total)
+            })
+            .take_while(|&total| total <= max_suffix)
+            .last()
+            .unwrap_or(0);
+
+        let start = word_before(edited, prefix.saturating_sub(EDIT_CONTEXT), prefix);
+        let end = word_after(edited, edited.len() - suffix, EDIT_CONTEXT);
+        let trailing = edited.len() - end;
+        Self {
+            original: start..original.len() - trailing,
+            edited: start..end,
+        }
+    }
+
+    /// The previous entities outside the edited range, moved to where they
+    /// are now, followed by those found in the excerpt.
+    fn merge_entities(
+        &self,
+        original: &str,
+        edited: &str,
+        previous: &[PiiEntity],
+        excerpt: &[PiiEntity],
+    ) -> Vec<PiiEntity> {
+        let start = original[..self.original.start].chars().count();
+        let original_end = original[..self.original.end].chars().count();
+        let edited_end = edited[..self.edited.end].chars().count();
+        let moved = |offset: usize| offset - original_end + edited_end;
+
+        let mut entities: Vec<PiiEntity> = previous
+            .iter()
+            .filter(|entity| entity.end <= start)
+            .cloned()
+            .collect();
+        entities.extend(excerpt.iter().map(|entity| PiiEntity {
+            start: entity.start + start,
+            end: entity.end + start,
+            ..entity.clone()
+        }));
+        entities.extend(
+            previous
+                .iter()
+                .filter(|entity| entity.start >= original_end)
+                .map(|entity| PiiEntity {
+                    start: moved(entity.start),
+                    end: moved(entity.end),
+                    ..entity.clone()
+                }),
+        );
+        entities
+    }
+}
+
+/// Moves `from` forward to the start of a word, unless that passes `to`.
+fn word_before(text: &str, from: usize, to: usize) -> usize {
+    let from = text.floor_char_boundary(from);
+    if from == 0 {
+        return 0;
+    }
+    match text[from..to].find(char::is_whitespace) {
+        Some(offset) => from + offset,
+        None => from,
+    }
+}
+
+/// Extends `at` by up to `margin` bytes, stopping at the end of a word.
+fn word_after(text: &str, at: usize, margin: usize) -> usize {
+    let to = text.ceil_char_boundary((at + margin).min(text.len()));
+    if to == text.len() {
+        return to;
+    }
+    match text[at..to].rfind(char::is_whitespace) {
+        Some(offset) => at + offset,
+        None => to,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PiiEntityType;
+
+    /// Words with a two-byte character, so any offset that splits it panics.
+    const WORD: &str = "słowo ";
+
+    fn words(count: usize) -> String {
+        WORD.repeat(count)
+    }
+
+    /// The edit between the two texts, checked to leave only matching text
+    /// outside its ranges.
+    fn between(original: &str, edited: &str) -> Edit {
+        let edit = Edit::between(original, edited);
+        assert_eq!(original[..edit.original.start], edited[..edit.edited.start]);
+        assert_eq!(original[edit.original.end..], edited[edit.edited.end..]);
+        edit
+    }
+
+    fn entity(start: usize, end: usize) -> PiiEntity {
+        PiiEntity {
+            kind: PiiEntityType::Email,
+            start,
+            end,
+            confidence: 1.0,
+            masked_value: None,
+        }
+    }
+
+    #[test]
+    fn edit_in_the_middle_keeps_context_on_word_boundaries() {
+        let original = format!("{}żółw {}", words(100), words(100));
+        let edited = format!("{}kot {}", words(100), words(100));
+        let edit = between(&original, &edited);
+
+        let excerpt = &edited[edit.edited.clone()];
+        assert!(excerpt.contains("kot"));
+        assert!(excerpt.len() <= 2 * EDIT_CONTEXT + "kot".len());
+        assert!(edit.edited.start > 0 && edit.edited.end < edited.len());
+        assert!(excerpt.starts_with(' '));
+        assert!(edited[edit.edited.end..].starts_with(' '));
+    }
+
+    #[test]
+    fn edit_at_the_start_or_end() {
+        let edit = between(&format!("żółw {}", words(100)), &format!("kot {}", words(100)));
+        assert_eq!(edit.original.start, 0);
+        assert_eq!(edit.edited.start, 0);
+
+        let original = format!("{}żółw", words(100));
+        let edited = format!("{}kot", words(100));
+        let edit = between(&original, &edited);
+        assert_eq!(edit.original.end, original.len());
+        assert_eq!(edit.edited.end, edited.len());
+    }
+
+    #[test]
+    fn multibyte_characters_sharing_a_first_byte() {
+        let edit = between("café", "cafè");
+        assert_eq!(edit.original, 0.."café".len());
+        assert_eq!(edit.edited, 0.."cafè".len());
+    }
+
+    #[test]
+    fn repeated_text_is_not_counted_twice() {
+        let edit = between("ąą", "ąąą");
+        assert!(edit.original.end <= "ąą".len());
+        assert_eq!(&"ąąą"[edit.edited], "ąąą");
+    }
+
+    #[test]
+    fn empty_and_identical_texts() {
+        let edit = between("", "");
+        assert_eq!((edit.original, edit.edited), (0..0, 0..0));
+
+        let edit = between("", "słowo");
+        assert_eq!((edit.original, edit.edited), (0..0, 0.."słowo".len()));
+
+        let edit = between("słowo", "");
+        assert_eq!((edit.original, edit.edited), (0.."słowo".len(), 0..0));
+
+        let text = words(100);
+        let edit = between(&text, &text);
+        assert_eq!(edit.original, edit.edited);
+    }
+
+    #[test]
+    fn entities_are_kept_moved_or_replaced() {
+        let original = format!("{}żółw żółw {}", words(100), words(100));
+        let edited = format!("{}kot {}", words(100), words(100));
+        let edit = between(&original, &edited);
+
+        let start = original[..edit.original.start].chars().count();
+        let end = original[..edit.original.end].chars().count();
+        let original_len = original.chars().count();
+        let edited_len = edited.chars().count();
+
+        let before = entity(0, 5);
+        let straddling = entity(start - 2, start + 2);
+        let inside = entity(start + 3, start + 4);
+        let after = entity(original_len - 6, original_len - 1);
+        assert!(end < after.start);
+
+        let merged = edit.merge_entities(
+            &original,
+            &edited,
+            &[before.clone(), straddling, inside, after],
+            &[entity(0, 3)],
+        );
+        assert_eq!(
+            merged,
+            vec![
+                before,
+                entity(start, start + 3),
+                entity(edited_len - 6, edited_len - 1),
+            ]
+        );
+        let last: String = edited.chars().skip(edited_len - 6).take(5).collect();
+        assert_eq!(last, "słowo");
+    }
+
+    #[test]
+    fn identical_texts_keep_entities_before_the_context() {
+        let text = words(100);
+        let edit = between(&text, &text);
+        let merged = edit.merge_entities(&text, &text, &[entity(0, 5)], &[]);
+        assert_eq!(merged, vec![entity(0, 5)]);
+    }
+}
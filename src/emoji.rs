@@ -0,0 +1,150 @@
+//! Spells out emoji and emoticons as words, since text moderation reads
+//! `"🍆💦"` as meaningless symbols.
+
+const ZWJ: char = '\u{200D}';
+
+/// Characters that adjust how the preceding emoji is drawn without changing
+/// what it means: variation selectors and skin tones.
+fn is_modifier(c: char) -> bool {
+    matches!(c, '\u{FE0E}' | '\u{FE0F}' | '\u{1F3FB}'..='\u{1F3FF}')
+}
+
+/// What an emoji is commonly used to mean. Emoji with an innocent primary
+/// meaning are described by their innuendo where that is the common reading
+/// in abusive messages.
+pub fn emoji_description(emoji: char) -> Option<&'static str> {
+    let description = match emoji {
+        '🍆' => "eggplant penis",
+        '🍑' => "peach butt",
+        '💦' => "splashing sweat semen",
+        '🌮' => "taco vagina",
+        '🍒' => "cherries breasts",
+        '🍌' => "banana penis",
+        '👅' => "tongue licking",
+        '👄' => "mouth",
+        '🥵' => "hot face horny",
+        '😈' => "smiling devil",
+        '🔞' => "adults only",
+        '👙' => "bikini",
+        '🖕' => "middle finger fuck you",
+        '💩' => "pile of poo shit",
+        '🤡' => "clown",
+        '🐷' | '🐖' => "pig",
+        '🐒' | '🐵' | '🦍' => "monkey",
+        '🐍' => "snake",
+        '🐀' => "rat",
+        '🤮' => "vomiting",
+        '🤢' => "nauseated",
+        '🙄' => "rolling eyes",
+        '😡' | '😠' | '🤬' => "angry face cursing",
+        '🔪' | '🗡' => "knife",
+        '🔫' => "gun",
+        '💣' => "bomb",
+        '💀' | '☠' => "skull death",
+        '⚰' => "coffin",
+        '🪦' => "headstone",
+        '🩸' => "blood",
+        '🔥' => "fire",
+        '🪓' => "axe",
+        '🧨' => "firecracker",
+        '👊' | '🤜' | '🤛' => "punch",
+        '💊' => "pill drugs",
+        '💉' => "syringe drugs",
+        '🍁' | '🌿' => "weed",
+        '❄' => "snowflake cocaine",
+        '💰' | '💸' | '💵' => "money",
+        '🏳' => "white flag",
+        '🔗' => "link",
+        '😂' | '🤣' => "laughing",
+        '😀' | '😃' | '😄' | '😁' | '🙂' | '😊' => "smiling",
+        '😉' => "winking",
+        '😘' | '😚' => "kissing",
+        '😍' | '🥰' => "heart eyes",
+        '😢' | '😭' => "crying",
+        '😏' => "smirking",
+        '😳' => "flushed",
+        '🤤' => "drooling",
+        '❤' | '💕' | '💖' | '💗' => "heart",
+        '💔' => "broken heart",
+        '👍' => "thumbs up",
+        '👎' => "thumbs down",
+        '👉' => "pointing right",
+        '👌' => "ok hand",
+        '✂' => "scissors",
+        '🧠' => "brain",
+        '🪳' => "cockroach",
+        _ => return None,
+    };
+    Some(description)
+}
+
+/// What a text emoticon such as `:)` stands for.
+pub fn emoticon_description(emoticon: &str) -> Option<&'static str> {
+    let description = match emoticon {
+        ":)" | ":-)" | "(:" | ":]" | "=)" => "smiling",
+        ":D" | ":-D" | "xD" | "XD" => "laughing",
+        ":(" | ":-(" | "):" | ":[" | "=(" => "sad",
+        ";)" | ";-)" => "winking",
+        ":P" | ":-P" | ":p" | ":-p" => "sticking out tongue",
+        ":O" | ":-O" | ":o" => "surprised",
+        ":/" | ":-/" | ":\\" => "skeptical",
+        ":*" | ":-*" => "kissing",
+        ":'(" => "crying",
+        "<3" => "heart",
+        "</3" => "broken heart",
+        ">:(" | ">:-(" => "angry",
+        "-_-" => "annoyed",
+        "o/" | "\\o" => "waving",
+        _ => return None,
+    };
+    Some(description)
+}
+
+/// Replaces known emoji and standalone emoticons with their descriptions,
+/// so `"you 🐷🤮"` becomes `"you pig vomiting"`. Skin tones, variation
+/// selectors and zero width joiners are dropped from around described
+/// emoji; everything else, including emoji without a description and the
+/// joiners of scripts such as Devanagari, is kept as it is. Text with
+/// nothing to describe is returned unchanged.
+pub fn describe(text: &str) -> String {
+    let mut described = String::with_capacity(text.len());
+
+    for (i, token) in text.split(' ').enumerate() {
+        if i > 0 {
+            described.push(' ');
+        }
+        if let Some(description) = emoticon_description(token) {
+            described.push_str(description);
+            continue;
+        }
+
+        let mut after_description = false;
+        for c in token.chars() {
+            if after_description && (is_modifier(c) || c == ZWJ) {
+                continue;
+            }
+            match emoji_description(c) {
+                Some(description) => {
+                    // A joiner only ties this emoji to the one before it.
+                    if described.ends_with(ZWJ) {
+                        described.pop();
+                    }
+                    if !described.is_empty() && !described.ends_with(char::is_whitespace) {
+                        described.push(' ');
+                    }
+                    described.push_str(description);
+                    after_description = true;
+                }
+                None => {
+                    if after_description && !c.is_whitespace() {
+                        described.push(' ');
+                    }
+                    described.push(c);
+                    after_description = false;
+                }
+            }
+        }
+    }
+
+    described
+}
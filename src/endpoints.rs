@@ -0,0 +1,229 @@
+use crate::retry::{self, Backoff, RetryConfig};
+use crate::{HttpTransport, TransportError};
+use reqwest::{Request, Response, Url};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+const FAILURE_THRESHOLD: u32 = 3;
+const COOLDOWN: Duration = Duration::from_secs(30);
+/// Paths requested on every call of their kind, whose URLs are resolved
+/// once per endpoint rather than per request.
+const FIXED_PATHS: [&str; 6] = [
+    "/moderation/text",
+    "/moderation/prompt-injection",
+    "/moderation/image",
+    "/moderation/image/async",
+    "/moderation/image/upload",
+    "/usage",
+];
+
+/// The route `path` belongs to, with any job ID replaced by `{id}`, for
+/// labelling metrics without a new series per job.
+#[cfg(any(feature = "metrics", feature = "otel"))]
+pub(crate) fn route(path: &str) -> &str {
+    match path.strip_prefix("/jobs/") {
+        Some(rest) if rest.ends_with("/events") => "/jobs/{id}/events",
+        Some(_) => "/jobs/{id}",
+        None => path,
+    }
+}
+
+/// A base URL, with the URLs of the fixed paths resolved against it.
+pub(crate) struct Endpoint {
+    base_url: String,
+    urls: Vec<(&'static str, Url)>,
+}
+
+impl Endpoint {
+    fn new(base_url: &str) -> Self {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        let urls = FIXED_PATHS
+            .iter()
+            .filter_map(|&path| Some((path, Url::parse(&format!("{}{}", base_url, path)).ok()?)))
+            .collect();
+        Self { base_url, urls }
+    }
+
+    pub(crate) fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The URL of `path`, if it is one of the fixed paths.
+    pub(crate) fn url(&self, path: &str) -> Option<&Url> {
+        self.urls
+            .iter()
+            .find(|(fixed, _)| *fixed == path)
+            .map(|(_, url)| url)
+    }
+}
+
+#[derive(Default)]
+struct EndpointHealth {
+    consecutive_failures: u32,
+    unhealthy_until: Option<Instant>,
+}
+
+impl EndpointHealth {
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.unhealthy_until.is_none_or(|until| now >= until)
+    }
+}
+
+/// An ordered list of base URLs. Requests go to the first healthy endpoint;
+/// endpoints that refuse connections or keep returning 5xx are skipped until
+/// their cooldown expires, at which point traffic returns to them.
+///
+/// Requests that fail on every endpoint, or get a 429 or 5xx, are retried up
+/// to `max_retries` times after the shared backoff.
+pub(crate) struct Endpoints {
+    endpoints: Vec<Endpoint>,
+    health: Mutex<Vec<EndpointHealth>>,
+    retries: AtomicU64,
+    retry: RetryConfig,
+    backoff: Backoff,
+}
+
+impl Endpoints {
+    pub(crate) fn new(urls: Vec<String>) -> Self {
+        let endpoints: Vec<Endpoint> = urls.iter().map(|url| Endpoint::new(url)).collect();
+        let health = endpoints
+            .iter()
+            .map(|_| EndpointHealth::default())
+            .collect();
+        Self {
+            endpoints,
+            health: Mutex::new(health),
+            retries: AtomicU64::new(0),
+            retry: RetryConfig::default(),
+            backoff: Backoff::default(),
+        }
+    }
+
+    pub(crate) fn with_retry(self, retry: RetryConfig) -> Self {
+        Self { retry, ..self }
+    }
+
+    /// Whether a request may be sent more than once.
+    pub(crate) fn may_resend(&self) -> bool {
+        self.endpoints.len() > 1 || self.retry.max_retries > 0
+    }
+
+    pub(crate) fn primary(&self) -> &Endpoint {
+        &self.endpoints[0]
+    }
+
+    /// How many requests have been retried against another endpoint.
+    pub(crate) fn retries(&self) -> u64 {
+        self.retries.load(Ordering::Relaxed)
+    }
+
+    /// Sends a request, calling `observe` with the outcome of every attempt.
+    pub(crate) async fn send(
+        &self,
+        transport: &dyn HttpTransport,
+        build: impl Fn(&Endpoint) -> Result<Request, reqwest::Error>,
+        observe: impl Fn(Result<&Response, &TransportError>),
+    ) -> Result<Response, TransportError> {
+        let mut attempt = 0;
+        for round in 0..=self.retry.max_retries {
+            if round > 0 {
+                self.backoff.wait().await;
+            }
+            let result = self
+                .send_once(transport, &build, &observe, &mut attempt)
+                .await;
+            let retry_after = match &result {
+                Ok(response) if retry::is_retryable(response) => retry::retry_after(response),
+                Err(e) if e.is_connect() => None,
+                _ => {
+                    self.backoff.success();
+                    return result;
+                }
+            };
+            self.backoff.failure(&self.retry, retry_after);
+            if round == self.retry.max_retries || !self.start_retry() {
+                return result;
+            }
+        }
+        unreachable!("the last round always returns")
+    }
+
+    /// Tries each endpoint in turn until one answers. `attempt` counts the
+    /// attempts made for the request so far.
+    async fn send_once(
+        &self,
+        transport: &dyn HttpTransport,
+        build: &impl Fn(&Endpoint) -> Result<Request, reqwest::Error>,
+        observe: &impl Fn(Result<&Response, &TransportError>),
+        attempt: &mut usize,
+    ) -> Result<Response, TransportError> {
+        let mut last_error = None;
+
+        for (failover, index) in self.candidates().into_iter().enumerate() {
+            if failover > 0 && !self.start_retry() {
+                break;
+            }
+            *attempt += 1;
+            #[cfg(feature = "tracing")]
+            crate::telemetry::record_attempt(*attempt);
+            let result = transport.execute(build(&self.endpoints[index])?).await;
+            observe(result.as_ref());
+            match result {
+                Ok(response) => {
+                    if response.status().is_server_error() {
+                        self.record_failure(index, false);
+                    } else {
+                        self.record_success(index);
+                    }
+                    return Ok(response);
+                }
+                Err(e) if e.is_connect() => {
+                    self.record_failure(index, true);
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error.expect("endpoint list is never empty"))
+    }
+
+    /// Takes a retry from the budget, if there is one, and counts it.
+    fn start_retry(&self) -> bool {
+        if let Some(budget) = &self.retry.budget
+            && !budget.try_spend()
+        {
+            return false;
+        }
+        self.retries.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        crate::meters::record_retry();
+        true
+    }
+
+    /// Healthy endpoints in priority order, followed by unhealthy ones as a
+    /// last resort.
+    fn candidates(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        let (mut healthy, unhealthy): (Vec<usize>, Vec<usize>) =
+            (0..self.endpoints.len()).partition(|&i| health[i].is_healthy(now));
+        healthy.extend(unhealthy);
+        healthy
+    }
+
+    fn record_success(&self, index: usize) {
+        let mut health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        health[index] = EndpointHealth::default();
+    }
+
+    fn record_failure(&self, index: usize, trip: bool) {
+        let mut health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        let endpoint = &mut health[index];
+        endpoint.consecutive_failures += 1;
+        if trip || endpoint.consecutive_failures >= FAILURE_THRESHOLD {
+            endpoint.unhealthy_until = Some(Instant::now() + COOLDOWN);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{
+    ModerationIssue, ModerationProvider, ModerationResponse, SafeCommsError, Severity,
+    local_response,
+};
+use futures_util::future::{BoxFuture, join_all};
+use std::collections::HashMap;
+
+/// How an [`Ensemble`] turns its providers' verdicts into one. Providers
+/// that fail are left out; only if all of them fail does the ensemble fail.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnsembleStrategy {
+    /// Flagged if any provider flags the content.
+    AnyBlock,
+    /// Flagged if more than half of the providers flag the content.
+    Majority,
+    /// Flagged if the providers flagging the content carry at least
+    /// `threshold` of the total weight, from 0.0 to 1.0.
+    Weighted { threshold: f64 },
+}
+
+/// One provider's part in an [`EnsembleVerdict`].
+#[derive(Debug)]
+pub struct Vote {
+    pub provider: String,
+    pub weight: f64,
+    pub result: Result<ModerationResponse, SafeCommsError>,
+}
+
+#[derive(Debug)]
+pub struct EnsembleVerdict {
+    /// The combined verdict. Category scores are the weighted mean of the
+    /// scores reported for each category; severity, reasons and issues come
+    /// from the providers that flagged the content.
+    pub response: ModerationResponse,
+    pub votes: Vec<Vote>,
+}
+
+/// Sends content to several providers or profiles at once and combines their
+/// verdicts.
+pub struct Ensemble {
+    members: Vec<(Box<dyn ModerationProvider>, f64)>,
+    strategy: EnsembleStrategy,
+}
+
+impl Ensemble {
+    pub fn new(strategy: EnsembleStrategy) -> Self {
+        Self {
+            members: Vec::new(),
+            strategy,
+        }
+    }
+
+    /// Adds a provider with a weight of 1.
+    pub fn provider(self, provider: impl ModerationProvider + 'static) -> Self {
+        self.weighted(provider, 1.0)
+    }
+
+    /// Adds a provider whose vote and scores count `weight` times.
+    pub fn weighted(mut self, provider: impl ModerationProvider + 'static, weight: f64) -> Self {
+        self.members.push((Box::new(provider), weight));
+        self
+    }
+
+    pub async fn moderate(
+        &self,
+        content: &str,
+        language: Option<&str>,
+    ) -> Result<EnsembleVerdict, SafeCommsError> {
+        let results = join_all(
+            self.members
+                .iter()
+                .map(|(provider, _)| provider.moderate_text(content, language)),
+        )
+        .await;
+        let votes: Vec<Vote> = self
+            .members
+            .iter()
+            .zip(results)
+            .map(|((provider, weight), result)| Vote {
+                provider: provider.name().to_string(),
+                weight: *weight,
+                result,
+            })
+            .collect();
+
+        if votes.iter().all(|vote| vote.result.is_err()) {
+            return match votes.into_iter().next() {
+                Some(Vote { result: Err(e), .. }) => Err(e),
+                _ => Err(SafeCommsError::ApiError("The ensemble has no providers".to_string())),
+            };
+        }
+
+        let response = self.combine(&votes);
+        Ok(EnsembleVerdict { response, votes })
+    }
+
+    fn combine(&self, votes: &[Vote]) -> ModerationResponse {
+        let answered: Vec<(&str, f64, &ModerationResponse)> = votes
+            .iter()
+            .filter_map(|vote| {
+                let response = vote.result.as_ref().ok()?;
+                Some((vote.provider.as_str(), vote.weight, response))
+            })
+            .collect();
+        let flagging: Vec<&(&str, f64, &ModerationResponse)> = answered
+            .iter()
+            .filter(|(_, _, response)| !response.is_clean)
+            .collect();
+
+        let flagged = match self.strategy {
+            EnsembleStrategy::AnyBlock => !flagging.is_empty(),
+            EnsembleStrategy::Majority => flagging.len() * 2 > answered.len(),
+            EnsembleStrategy::Weighted { threshold } => {
+                let total: f64 = answered.iter().map(|(_, weight, _)| weight).sum();
+                let against: f64 = flagging.iter().map(|(_, weight, _)| weight).sum();
+                total > 0.0 && against / total >= threshold
+            }
+        };
+
+        let mut scores: HashMap<&str, (f64, f64)> = HashMap::new();
+        for (_, weight, response) in &answered {
+            for (category, score) in response.category_scores.iter().flatten() {
+                if let Ok(score) = score.parse::<f64>() {
+                    let (sum, weights) = scores.entry(category).or_default();
+                    *sum += score * weight;
+                    *weights += weight;
+                }
+            }
+        }
+        let category_scores = scores
+            .into_iter()
+            .filter(|(_, (_, weights))| *weights > 0.0)
+            .map(|(category, (sum, weights))| (category.to_string(), (sum / weights).to_string()))
+            .collect();
+
+        let severity = flagging
+            .iter()
+            .filter_map(|(_, _, response)| response.severity_level())
+            .max()
+            .map(|severity| severity_name(severity).to_string());
+        let reasons: Vec<String> = flagging
+            .iter()
+            .filter_map(|(name, _, response)| {
+                Some(format!("{}: {}", name, response.reason.as_ref()?))
+            })
+            .collect();
+        let issues: Vec<ModerationIssue> = flagging
+            .iter()
+            .flat_map(|(_, _, response)| response.issues.iter().flatten().cloned())
+            .collect();
+
+        ModerationResponse {
+            severity: severity.filter(|_| flagged),
+            category_scores: Some(category_scores),
+            is_bypass_attempt: answered.iter().any(|(_, _, response)| response.is_bypass_attempt),
+            ..local_response(
+                !flagged,
+                (flagged && !reasons.is_empty()).then(|| reasons.join("; ")),
+                (flagged && !issues.is_empty()).then_some(issues),
+            )
+        }
+    }
+}
+
+impl ModerationProvider for Ensemble {
+    fn name(&self) -> &str {
+        "ensemble"
+    }
+
+    fn moderate_text<'a>(
+        &'a self,
+        content: &'a str,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<ModerationResponse, SafeCommsError>> {
+        Box::pin(async move { Ok(self.moderate(content, language).await?.response) })
+    }
+}
+
+fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Low => "Low",
+        Severity::Medium => "Medium",
+        Severity::High => "High",
+        Severity::Critical => "Critical",
+    }
+}
@@ -0,0 +1,24 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Environment {
+    #[default]
+    Production,
+    /// Test traffic that is routed to the sandbox API and never counts
+    /// against production quota.
+    Sandbox,
+}
+
+impl Environment {
+    pub fn base_url(&self) -> &'static str {
+        match self {
+            Environment::Production => crate::DEFAULT_BASE_URL,
+            Environment::Sandbox => "https://sandbox.api.safecomms.dev",
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Environment::Production => "production",
+            Environment::Sandbox => "sandbox",
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use crate::SafeCommsError;
+use reqwest::header::LOCATION;
+use reqwest::{Client as HttpClient, Method, StatusCode, Url, redirect};
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+const DEFAULT_MAX_REDIRECTS: usize = 5;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+const SHORTENERS: [&str; 16] = [
+    "bit.ly", "buff.ly", "cutt.ly", "goo.gl", "is.gd", "lnkd.in", "ow.ly", "rb.gy",
+    "rebrand.ly", "s.id", "shorturl.at", "t.co", "t.ly", "tiny.cc", "tinyurl.com", "v.gd",
+];
+
+/// Where a shortened link leads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpandedUrl {
+    pub original: String,
+    pub destination: String,
+    /// How many redirects were followed.
+    pub redirects: usize,
+}
+
+/// Resolves shortened links to their destinations, so that moderation sees
+/// where a link really goes.
+///
+/// Redirects are followed one at a time, up to `max_redirects`, within an
+/// overall timeout. Every hop's host is resolved first and, unless allowed,
+/// links that lead to private, loopback or link-local addresses are refused,
+/// so that user-supplied links cannot probe the internal network. The
+/// connection is pinned to the checked addresses and does not go through a
+/// proxy.
+#[derive(Debug, Clone)]
+pub struct UrlExpander {
+    max_redirects: usize,
+    timeout: Duration,
+    allow_private: bool,
+    shorteners: HashSet<String>,
+}
+
+impl UrlExpander {
+    /// Expands links from common shorteners such as bit.ly and t.co, following
+    /// up to 5 redirects within 5 seconds.
+    pub fn new() -> Self {
+        Self {
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            timeout: DEFAULT_TIMEOUT,
+            allow_private: false,
+            shorteners: SHORTENERS.iter().map(|host| host.to_string()).collect(),
+        }
+    }
+
+    pub fn max_redirects(mut self, redirects: usize) -> Self {
+        self.max_redirects = redirects;
+        self
+    }
+
+    /// How long expanding one link may take, across all its redirects.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Follows links to private and loopback addresses, e.g. in tests.
+    pub fn allow_private_addresses(mut self, allow: bool) -> Self {
+        self.allow_private = allow;
+        self
+    }
+
+    /// Treats links to `host` as shortened, e.g. a company's own shortener.
+    pub fn shortener(mut self, host: &str) -> Self {
+        self.shorteners.insert(host.to_ascii_lowercase());
+        self
+    }
+
+    /// Whether `url` is on a known shortener.
+    pub fn is_shortened(&self, url: &Url) -> bool {
+        url.host_str().is_some_and(|host| {
+            let host = host.to_ascii_lowercase();
+            self.shorteners.contains(host.strip_prefix("www.").unwrap_or(&host))
+        })
+    }
+
+    /// Follows `url`'s redirects. Stops after `max_redirects` and reports the
+    /// link reached.
+    pub async fn expand(&self, url: &str) -> Result<ExpandedUrl, SafeCommsError> {
+        let deadline = Instant::now() + self.timeout;
+        let mut current = parse(url)?;
+        let mut redirects = 0;
+
+        while redirects < self.max_redirects {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let next = tokio::time::timeout(remaining, self.follow(&current))
+                .await
+                .map_err(|_| invalid(format!("{} timed out", url)))??;
+            match next {
+                Some(next) => {
+                    current = next;
+                    redirects += 1;
+                }
+                None => break,
+            }
+        }
+
+        Ok(ExpandedUrl {
+            original: url.to_string(),
+            destination: current.to_string(),
+            redirects,
+        })
+    }
+
+    /// Replaces the shortened links in `text` with their destinations. Links
+    /// that cannot be expanded are left as they are.
+    pub async fn expand_text(&self, text: &str) -> String {
+        let mut expanded = String::with_capacity(text.len());
+        let mut copied = 0;
+        for (range, url) in self.links(text) {
+            match self.expand(url.as_str()).await {
+                Ok(url) => {
+                    expanded.push_str(&text[copied..range.start]);
+                    expanded.push_str(&url.destination);
+                    copied = range.end;
+                }
+                Err(e) => log::warn!("Failed to expand {}: {}", url, e),
+            }
+        }
+        expanded.push_str(&text[copied..]);
+        expanded
+    }
+
+    /// The shortened links in `text`, with or without a scheme.
+    fn links(&self, text: &str) -> Vec<(Range<usize>, Url)> {
+        let mut links = Vec::new();
+        let mut offset = 0;
+        for token in text.split_inclusive(char::is_whitespace) {
+            let unquoted = token.trim_start_matches(['(', '<', '"', '\'']);
+            let start = offset + token.len() - unquoted.len();
+            let word = unquoted
+                .trim()
+                .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '>', '"', '\'']);
+            offset += token.len();
+
+            let url = if word.starts_with("http://") || word.starts_with("https://") {
+                Url::parse(word).ok()
+            } else if word.contains('/') {
+                Url::parse(&format!("https://{}", word)).ok()
+            } else {
+                None
+            };
+            if let Some(url) = url.filter(|url| self.is_shortened(url)) {
+                links.push((start..start + word.len(), url));
+            }
+        }
+        links
+    }
+
+    /// Requests `url` without following redirects, and returns where it
+    /// redirects to, if anywhere.
+    async fn follow(&self, url: &Url) -> Result<Option<Url>, SafeCommsError> {
+        let host = url.host_str().ok_or_else(|| invalid(format!("{} has no host", url)))?;
+        let port = url.port_or_known_default().unwrap_or(443);
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|e| invalid(format!("{} could not be resolved: {}", host, e)))?
+            .collect();
+        if !self.allow_private && addrs.iter().any(|addr| !is_public(addr.ip())) {
+            return Err(invalid(format!("{} resolves to a private address", host)));
+        }
+
+        let client = HttpClient::builder()
+            .redirect(redirect::Policy::none())
+            .no_proxy()
+            .resolve_to_addrs(host, &addrs)
+            .build()?;
+        let mut response = client.request(Method::HEAD, url.clone()).send().await?;
+        if matches!(
+            response.status(),
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+        ) {
+            response = client.get(url.clone()).send().await?;
+        }
+
+        if !response.status().is_redirection() {
+            return Ok(None);
+        }
+        let Some(location) = response.headers().get(LOCATION) else {
+            return Ok(None);
+        };
+        let location = location
+            .to_str()
+            .map_err(|_| invalid(format!("{} redirects to an invalid location", url)))?;
+        let next = url
+            .join(location)
+            .map_err(|e| invalid(format!("{} redirects to {}: {}", url, location, e)))?;
+        if !matches!(next.scheme(), "http" | "https") {
+            return Err(invalid(format!("{} redirects to {}", url, next)));
+        }
+        Ok(Some(next))
+    }
+}
+
+impl Default for UrlExpander {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parse(url: &str) -> Result<Url, SafeCommsError> {
+    let parsed = Url::parse(url).map_err(|e| invalid(format!("{}: {}", url, e)))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(invalid(format!("{} is not an http(s) link", url)));
+    }
+    Ok(parsed)
+}
+
+fn invalid(reason: String) -> SafeCommsError {
+    SafeCommsError::InvalidInput { field: "url", reason }
+}
+
+/// Whether `ip` is reachable on the public internet.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_v4(ip),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    let shared = a == 100 && (64..128).contains(&b);
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || shared
+        || a == 0)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    let unique_local = first & 0xfe00 == 0xfc00;
+    let link_local = first & 0xffc0 == 0xfe80;
+    !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || unique_local || link_local)
+}
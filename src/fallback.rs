@@ -0,0 +1,201 @@
+use crate::prefilter::{Prefilter, PrefilterDecision};
+use crate::{
+    Language, ModerationProvider, ModerationResponse, SafeCommsClient, SafeCommsError,
+    detect_bypass_signals, local_response,
+};
+#[cfg(feature = "onnx")]
+use crate::{ImagePrescreen, ImageUploadOptions};
+
+/// Below this NSFW score an image is let through while the API is down.
+#[cfg(feature = "onnx")]
+const DEGRADED_NSFW_THRESHOLD: f32 = 0.5;
+/// The source of verdicts reached by local heuristics.
+const LOCAL_SOURCE: &str = "local";
+
+/// The outcome of a moderation call made through a [`Fallback`].
+#[derive(Debug)]
+pub enum Verdict {
+    /// The API's verdict.
+    Moderated(ModerationResponse),
+    /// The API could not be reached, and this is a secondary provider's
+    /// verdict or the local heuristics' best guess. `error` is why the API
+    /// call failed.
+    Degraded {
+        response: ModerationResponse,
+        error: SafeCommsError,
+        /// The name of the provider that produced the response, or `local`.
+        source: String,
+    },
+}
+
+impl Verdict {
+    pub fn response(&self) -> &ModerationResponse {
+        match self {
+            Verdict::Moderated(response) | Verdict::Degraded { response, .. } => response,
+        }
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.response().is_clean
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        matches!(self, Verdict::Degraded { .. })
+    }
+
+    /// Where the verdict came from: `safecomms`, a secondary provider's name
+    /// or `local`.
+    pub fn source(&self) -> &str {
+        match self {
+            Verdict::Moderated(_) => "safecomms",
+            Verdict::Degraded { source, .. } => source,
+        }
+    }
+}
+
+/// Keeps moderation going while the API is unreachable, using secondary
+/// providers or local heuristics instead of failing closed.
+///
+/// Transport failures (refused connections, timeouts and the like), server
+/// errors and rate limiting count as an outage; other errors reported by the
+/// API are returned as usual.
+pub struct Fallback {
+    prefilter: Prefilter,
+    secondaries: Vec<Box<dyn ModerationProvider>>,
+    #[cfg(feature = "onnx")]
+    image_prescreen: Option<ImagePrescreen>,
+}
+
+impl Fallback {
+    /// Text is judged by `prefilter` during an outage. Text the prefilter
+    /// finds ambiguous is let through unless it shows signs of a bypass
+    /// attempt.
+    pub fn new(prefilter: Prefilter) -> Self {
+        Self {
+            prefilter,
+            secondaries: Vec::new(),
+            #[cfg(feature = "onnx")]
+            image_prescreen: None,
+        }
+    }
+
+    /// Consults `provider` for text during an outage, before falling back to
+    /// the local heuristics. Secondary providers are tried in the order they
+    /// were added.
+    pub fn secondary(mut self, provider: impl ModerationProvider + 'static) -> Self {
+        self.secondaries.push(Box::new(provider));
+        self
+    }
+
+    /// Judges images with `prescreen` during an outage.
+    #[cfg(feature = "onnx")]
+    pub fn image_prescreen(mut self, prescreen: ImagePrescreen) -> Self {
+        self.image_prescreen = Some(prescreen);
+        self
+    }
+
+    pub async fn moderate_text(
+        &self,
+        client: &SafeCommsClient,
+        content: &str,
+        language: Option<&str>,
+    ) -> Result<Verdict, SafeCommsError> {
+        match client
+            .moderate_text(content, language.map(Language::from), None, None, None, None)
+            .await
+        {
+            Ok(response) => Ok(Verdict::Moderated(response)),
+            Err(error) if is_outage(&error) => {
+                Ok(self.moderate_degraded(content, language, error).await)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    async fn moderate_degraded(
+        &self,
+        content: &str,
+        language: Option<&str>,
+        error: SafeCommsError,
+    ) -> Verdict {
+        for provider in &self.secondaries {
+            match provider.moderate_text(content, language).await {
+                Ok(response) => {
+                    return Verdict::Degraded {
+                        response,
+                        error,
+                        source: provider.name().to_string(),
+                    };
+                }
+                Err(e) => log::warn!("Fallback provider {} failed: {}", provider.name(), e),
+            }
+        }
+
+        Verdict::Degraded {
+            response: self.judge_text(content),
+            error,
+            source: LOCAL_SOURCE.to_string(),
+        }
+    }
+
+    /// Uploads an image, falling back to the local pre-screen during an
+    /// outage. Without a pre-screen, outages are returned as errors.
+    #[cfg(feature = "onnx")]
+    pub async fn moderate_image_bytes(
+        &self,
+        client: &SafeCommsClient,
+        image: Vec<u8>,
+        file_name: &str,
+        options: ImageUploadOptions<'_>,
+    ) -> Result<Verdict, SafeCommsError> {
+        let Some(prescreen) = &self.image_prescreen else {
+            return client
+                .moderate_image_bytes(image, file_name, options)
+                .await
+                .map(Verdict::Moderated);
+        };
+
+        match client
+            .moderate_image_bytes(image.clone(), file_name, options)
+            .await
+        {
+            Ok(response) => Ok(Verdict::Moderated(response)),
+            Err(error) if is_outage(&error) => {
+                // Inference is CPU-bound, so keep it off the async workers.
+                let prescreen = prescreen.clone();
+                let score = tokio::task::spawn_blocking(move || prescreen.score(&image))
+                    .await
+                    .map_err(|e| {
+                        SafeCommsError::ApiError(format!("Image prescreen failed: {}", e))
+                    })??;
+                let is_clean = score < DEGRADED_NSFW_THRESHOLD;
+                let reason = (!is_clean).then(|| "Flagged by the local image model".to_string());
+                Ok(Verdict::Degraded {
+                    response: local_response(is_clean, reason, None),
+                    error,
+                    source: LOCAL_SOURCE.to_string(),
+                })
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    fn judge_text(&self, content: &str) -> ModerationResponse {
+        match self.prefilter.check(content) {
+            PrefilterDecision::Block(term) => Prefilter::blocked_response(term),
+            PrefilterDecision::Allow => local_response(true, None, None),
+            PrefilterDecision::Ambiguous => {
+                let suspicious = !detect_bypass_signals(content).is_empty();
+                let reason = suspicious.then(|| "Shows signs of a bypass attempt".to_string());
+                ModerationResponse {
+                    is_bypass_attempt: suspicious,
+                    ..local_response(!suspicious, reason, None)
+                }
+            }
+        }
+    }
+}
+
+fn is_outage(error: &SafeCommsError) -> bool {
+    error.is_transient()
+}
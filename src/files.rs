@@ -0,0 +1,63 @@
+use crate::{ImageUploadOptions, ModerationResponse, SafeCommsClient, SafeCommsError};
+use std::path::Path;
+
+pub(crate) const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp", "bmp"];
+const TEXT_EXTENSIONS: &[&str] = &["txt", "md"];
+
+/// How a file is moderated, judging by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FileKind {
+    Image,
+    Text,
+}
+
+impl FileKind {
+    pub(crate) fn of(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+            Some(FileKind::Image)
+        } else if TEXT_EXTENSIONS.contains(&extension.as_str()) {
+            Some(FileKind::Text)
+        } else {
+            None
+        }
+    }
+
+    /// Recognizes image formats from their first bytes.
+    pub(crate) fn sniff(header: &[u8]) -> Option<Self> {
+        let image = header.starts_with(&[0xFF, 0xD8, 0xFF])
+            || header.starts_with(b"\x89PNG")
+            || header.starts_with(b"GIF8")
+            || header.starts_with(b"BM")
+            || (header.starts_with(b"RIFF") && header.get(8..12) == Some(b"WEBP"));
+        image.then_some(FileKind::Image)
+    }
+}
+
+pub(crate) async fn moderate_file(
+    client: &SafeCommsClient,
+    path: &Path,
+    kind: FileKind,
+) -> Result<ModerationResponse, SafeCommsError> {
+    let contents = tokio::fs::read(path)
+        .await
+        .map_err(|e| SafeCommsError::ApiError(format!("Failed to read file: {}", e)))?;
+
+    match kind {
+        FileKind::Image => {
+            let file_name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("image.jpg");
+            client
+                .moderate_image_bytes(contents, file_name, ImageUploadOptions::default())
+                .await
+        }
+        FileKind::Text => {
+            let text = String::from_utf8_lossy(&contents);
+            client
+                .moderate_text(&text, None, None, None, None, None)
+                .await
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{ModerationResponse, SafeCommsClient, SafeCommsError};
+use futures_util::{Stream, StreamExt, stream};
+use std::pin::Pin;
+use thiserror::Error;
+
+const DEFAULT_MAX_BUFFER: usize = 500;
+
+/// Why a guarded stream was cut off. It is always the last item the stream
+/// yields.
+#[derive(Error, Debug)]
+pub enum GuardrailStop {
+    #[error("Output was flagged as unsafe")]
+    Unsafe {
+        /// The segment that was withheld.
+        text: String,
+        result: Box<ModerationResponse>,
+    },
+    #[error("Output could not be moderated")]
+    Error(#[from] SafeCommsError),
+}
+
+/// Moderates a stream of LLM output tokens before they reach the user.
+///
+/// Tokens are buffered up to a sentence boundary and each sentence is
+/// moderated before it is passed through, so the user never sees an unsafe
+/// sentence. The first unsafe sentence, or the first moderation failure, ends
+/// the stream.
+#[derive(Clone)]
+pub struct Guardrail {
+    client: SafeCommsClient,
+    max_buffer: usize,
+}
+
+struct GuardState<S> {
+    guardrail: Guardrail,
+    tokens: Pin<Box<S>>,
+    buffer: String,
+    finished: bool,
+}
+
+impl Guardrail {
+    pub fn new(client: SafeCommsClient) -> Self {
+        Self {
+            client,
+            max_buffer: DEFAULT_MAX_BUFFER,
+        }
+    }
+
+    /// How many bytes may be buffered without a sentence boundary before the
+    /// buffer is moderated anyway. Larger values give the API more context
+    /// but delay output that has no punctuation.
+    pub fn max_buffer(mut self, max_buffer: usize) -> Self {
+        self.max_buffer = max_buffer.max(1);
+        self
+    }
+
+    /// Wraps `tokens`, yielding moderated text segments. Concatenated, the
+    /// segments are exactly the text of the tokens that were let through.
+    pub fn guard<S>(
+        &self,
+        tokens: S,
+    ) -> impl Stream<Item = Result<String, GuardrailStop>> + use<S>
+    where
+        S: Stream,
+        S::Item: AsRef<str>,
+    {
+        let state = GuardState {
+            guardrail: self.clone(),
+            tokens: Box::pin(tokens),
+            buffer: String::new(),
+            finished: false,
+        };
+
+        stream::unfold(state, |mut state| async move {
+            if state.finished {
+                return None;
+            }
+
+            loop {
+                if let Some(end) = segment_end(&state.buffer, state.guardrail.max_buffer) {
+                    let segment: String = state.buffer.drain(..end).collect();
+                    let item = state.check(segment).await;
+                    return Some((item, state));
+                }
+
+                match state.tokens.next().await {
+                    Some(token) => state.buffer.push_str(token.as_ref()),
+                    None => {
+                        state.finished = true;
+                        if state.buffer.is_empty() {
+                            return None;
+                        }
+                        let segment = std::mem::take(&mut state.buffer);
+                        let item = state.check(segment).await;
+                        return Some((item, state));
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl<S> GuardState<S> {
+    async fn check(&mut self, segment: String) -> Result<String, GuardrailStop> {
+        if segment.trim().is_empty() {
+            return Ok(segment);
+        }
+
+        let result = self
+            .guardrail
+            .client
+            .moderate_text(&segment, None, None, None, None, None)
+            .await;
+
+        match result {
+            Ok(result) if result.is_clean => Ok(segment),
+            Ok(result) => {
+                self.finished = true;
+                Err(GuardrailStop::Unsafe {
+                    text: segment,
+                    result: Box::new(result),
+                })
+            }
+            Err(e) => {
+                self.finished = true;
+                Err(GuardrailStop::Error(e))
+            }
+        }
+    }
+}
+
+/// The end of the last complete sentence in `buffer`, or of the last word if
+/// the buffer has grown past `max_buffer` without one.
+///
+/// A full stop only ends a sentence once the following whitespace has
+/// arrived, so "3.14" split across tokens is not cut in two.
+fn segment_end(buffer: &str, max_buffer: usize) -> Option<usize> {
+    let mut chars = buffer.char_indices().peekable();
+    let mut end = None;
+
+    while let Some((index, c)) = chars.next() {
+        let is_boundary = match c {
+            '\n' => true,
+            '.' | '!' | '?' => chars.peek().is_some_and(|&(_, next)| next.is_whitespace()),
+            _ => false,
+        };
+        if is_boundary {
+            end = Some(index + c.len_utf8());
+        }
+    }
+
+    if end.is_some() || buffer.len() < max_buffer {
+        return end;
+    }
+
+    match buffer.rfind(char::is_whitespace) {
+        Some(index) if index > 0 => Some(index),
+        _ => Some(buffer.len()),
+    }
+}
@@ -0,0 +1,18 @@
+use reqwest::{RequestBuilder, Response};
+
+/// Hooks run around every request the client sends. Add them with
+/// [`SafeCommsClientBuilder::middleware`](crate::SafeCommsClientBuilder::middleware);
+/// they run in the order they were added.
+///
+/// `before_request` runs whenever a request is built, so it runs again for
+/// each retry and each failover to another endpoint; keep side effects for
+/// `after_response`.
+pub trait Middleware: Send + Sync {
+    /// Adjusts a request before it is sent, e.g. to add headers.
+    fn before_request(&self, request: RequestBuilder) -> RequestBuilder {
+        request
+    }
+
+    /// Observes a response before it is checked for errors and parsed.
+    fn after_response(&self, _response: &Response) {}
+}
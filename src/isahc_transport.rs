@@ -0,0 +1,90 @@
+//! An [`HttpTransport`] on `isahc`, for executors other than Tokio.
+
+use crate::{HttpTransport, TransportError};
+use futures_util::future::BoxFuture;
+use http_body_util::BodyExt;
+use isahc::config::Configurable;
+use isahc::error::ErrorKind;
+use isahc::{AsyncReadResponseExt, HttpClient};
+use reqwest::{Request, Response};
+
+/// Sends requests with `isahc`, whose transfers are driven by its own
+/// background thread instead of a Tokio reactor, so the client works under
+/// async-std, smol or any other executor.
+///
+/// ```ignore
+/// let client = SafeCommsClient::builder(api_key)
+///     .transport(IsahcTransport::new()?)
+///     .build()?;
+/// ```
+///
+/// Proxy, TLS and connection pool settings on the builder configure the
+/// built-in `reqwest` client, so they do not apply; configure them on the
+/// `HttpClient` passed to [`with_client`](Self::with_client) instead.
+pub struct IsahcTransport {
+    client: HttpClient,
+}
+
+impl IsahcTransport {
+    pub fn new() -> Result<Self, TransportError> {
+        let client = HttpClient::new().map_err(|e| TransportError::Other(Box::new(e)))?;
+        Ok(Self { client })
+    }
+
+    pub fn with_client(client: HttpClient) -> Self {
+        Self { client }
+    }
+}
+
+impl HttpTransport for IsahcTransport {
+    fn execute(&self, mut request: Request) -> BoxFuture<'_, Result<Response, TransportError>> {
+        Box::pin(async move {
+            let mut builder = isahc::Request::builder()
+                .method(request.method().as_str())
+                .uri(request.url().as_str());
+            for (name, value) in request.headers() {
+                builder = builder.header(name.as_str(), value.as_bytes());
+            }
+            if let Some(timeout) = request.timeout() {
+                builder = builder.timeout(*timeout);
+            }
+
+            // Multipart uploads are streamed, but generated in memory, so
+            // collecting them needs no runtime.
+            let body = match request.body_mut().take() {
+                Some(body) => body
+                    .collect()
+                    .await
+                    .map_err(|e| TransportError::Other(Box::new(e)))?
+                    .to_bytes()
+                    .to_vec(),
+                None => Vec::new(),
+            };
+            let request = builder
+                .body(body)
+                .map_err(|e| TransportError::Other(Box::new(e)))?;
+
+            let mut response = self.client.send_async(request).await.map_err(|e| {
+                match e.kind() {
+                    ErrorKind::ConnectionFailed | ErrorKind::NameResolution => {
+                        TransportError::Connect(Box::new(e))
+                    }
+                    _ => TransportError::Other(Box::new(e)),
+                }
+            })?;
+            let body = response
+                .bytes()
+                .await
+                .map_err(|e| TransportError::Other(Box::new(e)))?;
+
+            let mut converted = http::Response::builder().status(response.status().as_u16());
+            for (name, value) in response.headers() {
+                converted = converted.header(name.as_str(), value.as_bytes());
+            }
+            let converted = converted
+                .body(body)
+                .map_err(|e| TransportError::Other(Box::new(e)))?;
+            Ok(Response::from(converted))
+        })
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{
+    ImageModerationRequest, Job, JobId, SafeCommsClient, SafeCommsError, error_for_status,
+};
+use crate::runtime;
+use futures_util::{Stream, stream};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use reqwest::header::ACCEPT;
+use reqwest::{Method, Response};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+const LAST_EVENT_ID_HEADER: &str = "Last-Event-ID";
+const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+/// Escaped in a path segment: all but the characters RFC 3986 leaves
+/// unreserved.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// A progress update for an asynchronous moderation job.
+#[derive(Debug, Clone)]
+pub struct JobEvent {
+    pub id: Option<String>,
+    pub event: String,
+    pub data: Value,
+}
+
+impl JobEvent {
+    /// Whether this is the last event the job will produce.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self.event.as_str(), "completed" | "failed")
+    }
+}
+
+impl SafeCommsClient {
+    /// Queues an image for moderation without waiting for the result.
+    ///
+    /// If `request.callback_url` is set the result is pushed there when the
+    /// job completes. The returned job can always be polled with
+    /// [`get_job`](Self::get_job) or followed with
+    /// [`job_events`](Self::job_events) as a fallback.
+    pub async fn moderate_image_async(
+        &self,
+        request: ImageModerationRequest<'_>,
+    ) -> Result<Job, SafeCommsError> {
+        self.limits.image(request.image)?;
+        let tenant_metadata;
+        let request = match &self.tenant {
+            Some(tenant) => {
+                tenant_metadata = tenant.metadata(request.metadata);
+                ImageModerationRequest {
+                    language: request.language.or_else(|| tenant.language.clone()),
+                    moderation_profile_id: request
+                        .moderation_profile_id
+                        .or(tenant.moderation_profile_id.as_ref()),
+                    metadata: Some(&tenant_metadata),
+                    ..request
+                }
+            }
+            None => request,
+        };
+        let payload = self.wire_format.encode(&request)?;
+        let job = self
+            .send(Method::POST, "/moderation/image/async", |builder| payload.attach(builder))
+            .await?;
+        if let Some(tenant) = &self.tenant {
+            tenant.record("/moderation/image/async", false);
+        }
+        Ok(job)
+    }
+
+    pub async fn get_job(&self, job_id: &JobId) -> Result<Job, SafeCommsError> {
+        self.send(Method::GET, &job_path(job_id), |builder| builder)
+            .await
+    }
+
+    /// Streams progress events for a job until it completes or fails.
+    ///
+    /// Dropped connections are re-established automatically, resuming after
+    /// the last event received.
+    pub fn job_events(
+        &self,
+        job_id: &JobId,
+    ) -> impl Stream<Item = Result<JobEvent, SafeCommsError>> + use<> {
+        let events = EventStream {
+            client: self.clone(),
+            path: format!("{}/events", job_path(job_id)),
+            last_event_id: None,
+            event_id: None,
+            reconnect_delay: DEFAULT_RECONNECT_DELAY,
+            response: None,
+            buffer: Vec::new(),
+            data: Vec::new(),
+            event: None,
+            pending: VecDeque::new(),
+            failures: 0,
+            finished: false,
+        };
+
+        stream::unfold(events, |mut events| async move {
+            events.next().await.map(|item| (item, events))
+        })
+    }
+}
+
+/// The job's path, with the ID escaped so that it stays one segment.
+fn job_path(job_id: &JobId) -> String {
+    let job_id = utf8_percent_encode(job_id.as_str(), PATH_SEGMENT);
+    format!("/jobs/{}", job_id)
+}
+
+struct EventStream {
+    client: SafeCommsClient,
+    path: String,
+    /// The ID of the last dispatched event, sent when reconnecting.
+    last_event_id: Option<String>,
+    /// The ID read for the event being received, committed on dispatch.
+    event_id: Option<String>,
+    reconnect_delay: Duration,
+    response: Option<Response>,
+    buffer: Vec<u8>,
+    data: Vec<String>,
+    event: Option<String>,
+    pending: VecDeque<JobEvent>,
+    failures: u32,
+    finished: bool,
+}
+
+impl EventStream {
+    async fn next(&mut self) -> Option<Result<JobEvent, SafeCommsError>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                self.finished = event.is_terminal();
+                return Some(Ok(event));
+            }
+
+            if self.finished {
+                return None;
+            }
+
+            let Some(response) = &mut self.response else {
+                match self.connect().await {
+                    Ok(response) => self.response = Some(response),
+                    // An unknown job or a rejected key fails the same way on
+                    // every attempt.
+                    Err(e) if !e.is_transient() => {
+                        self.finished = true;
+                        return Some(Err(e));
+                    }
+                    Err(e) => {
+                        if let Some(e) = self.fail(e) {
+                            return Some(Err(e));
+                        }
+                        runtime::sleep(self.reconnect_delay).await;
+                    }
+                }
+                continue;
+            };
+
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    self.buffer.extend_from_slice(&chunk);
+                    self.drain_lines();
+                }
+                // The server closed the stream before the job finished. A
+                // partly received event is discarded; it is sent again after
+                // the last complete one.
+                result => {
+                    self.response = None;
+                    self.buffer.clear();
+                    self.data.clear();
+                    self.event = None;
+                    self.event_id = self.last_event_id.clone();
+
+                    let error = match result {
+                        Err(e) => SafeCommsError::RequestError(e),
+                        _ => SafeCommsError::ApiError(
+                            "Job event stream closed before the job finished".to_string(),
+                        ),
+                    };
+                    if let Some(e) = self.fail(error) {
+                        return Some(Err(e));
+                    }
+                    runtime::sleep(self.reconnect_delay).await;
+                }
+            }
+        }
+    }
+
+    /// Counts a failed connection, returning the error once there have been
+    /// too many in a row without a complete event.
+    fn fail(&mut self, error: SafeCommsError) -> Option<SafeCommsError> {
+        self.failures += 1;
+        if self.failures > MAX_RECONNECT_ATTEMPTS {
+            self.finished = true;
+            return Some(error);
+        }
+        None
+    }
+
+    async fn connect(&self) -> Result<Response, SafeCommsError> {
+        if self.client.dry_run.is_some() {
+            return Err(SafeCommsError::ApiError(
+                "Job events are not available in dry-run mode".to_string(),
+            ));
+        }
+
+        let response = self
+            .client
+            .execute(|endpoint, key| {
+                let mut request = self
+                    .client
+                    .request(&Method::GET, endpoint, &self.path, key, None)
+                    .header(ACCEPT, "text/event-stream");
+
+                if let Some(id) = &self.last_event_id {
+                    request = request.header(LAST_EVENT_ID_HEADER, id);
+                }
+
+                request
+            })
+            .await;
+        let response = response?;
+        self.client.after_response(&response);
+
+        error_for_status(response, self.client.error_body_limit).await
+    }
+
+    fn drain_lines(&mut self) {
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            self.process_line(line.trim_end_matches(['\n', '\r']));
+        }
+    }
+
+    fn process_line(&mut self, line: &str) {
+        if line.is_empty() {
+            self.dispatch();
+            return;
+        }
+
+        if line.starts_with(':') {
+            return;
+        }
+
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+
+        match field {
+            "data" => self.data.push(value.to_string()),
+            "event" => self.event = Some(value.to_string()),
+            "id" => self.event_id = Some(value.to_string()),
+            "retry" => {
+                if let Ok(millis) = value.parse() {
+                    self.reconnect_delay = Duration::from_millis(millis);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn dispatch(&mut self) {
+        self.last_event_id = self.event_id.clone();
+        let event = self.event.take().unwrap_or_else(|| "message".to_string());
+        if self.data.is_empty() {
+            return;
+        }
+
+        self.failures = 0;
+        let data = self.data.drain(..).collect::<Vec<_>>().join("\n");
+        self.pending.push_back(JobEvent {
+            id: self.last_event_id.clone(),
+            event,
+            data: serde_json::from_str(&data).unwrap_or(Value::String(data)),
+        });
+    }
+}
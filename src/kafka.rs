@@ -0,0 +1,215 @@
+use crate::{ModerationResponse, SafeCommsClient, SafeCommsError};
+use futures_util::StreamExt;
+use rdkafka::ClientConfig;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::message::{Message, OwnedMessage};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use serde::Serialize;
+use std::time::Duration;
+
+const DEFAULT_CONCURRENCY: usize = 16;
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(200);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Serialize)]
+struct ModeratedRecord<'a> {
+    topic: &'a str,
+    partition: i32,
+    offset: i64,
+    content: &'a str,
+    result: &'a ModerationResponse,
+}
+
+/// A message that can never be moderated, such as an empty one.
+#[derive(Serialize)]
+struct FailedRecord<'a> {
+    topic: &'a str,
+    partition: i32,
+    offset: i64,
+    content: &'a str,
+    error: String,
+}
+
+/// Reads text messages from an input topic, moderates them, and publishes the
+/// enriched results to an output topic.
+///
+/// Delivery is at-least-once: a message's offset is only stored after its
+/// result has been acknowledged by the broker, and results are published in
+/// input order so a restart never skips an unpublished message. Messages may
+/// be published twice after a crash, so consumers of the output topic should
+/// deduplicate on `(topic, partition, offset)`.
+///
+/// Messages the API rejects outright, such as empty ones, are published
+/// with an `error` instead of a `result`, to the dead-letter topic if there
+/// is one, and their offsets stored so they don't block the partition.
+pub struct KafkaPipeline {
+    client: SafeCommsClient,
+    consumer: StreamConsumer,
+    producer: FutureProducer,
+    output_topic: String,
+    dead_letter_topic: Option<String>,
+    concurrency: usize,
+    max_retries: u32,
+}
+
+impl KafkaPipeline {
+    /// Creates the consumer and producer from `config`, which needs at least
+    /// `bootstrap.servers` and `group.id`. Automatic offset storage is turned
+    /// off so that offsets only advance once results are published.
+    pub fn new(
+        client: SafeCommsClient,
+        config: &ClientConfig,
+        input_topic: &str,
+        output_topic: &str,
+    ) -> Result<Self, SafeCommsError> {
+        let consumer: StreamConsumer = config
+            .clone()
+            .set("enable.auto.offset.store", "false")
+            .create()?;
+        consumer.subscribe(&[input_topic])?;
+
+        let producer: FutureProducer = config.create()?;
+
+        Ok(Self {
+            client,
+            consumer,
+            producer,
+            output_topic: output_topic.to_string(),
+            dead_letter_topic: None,
+            concurrency: DEFAULT_CONCURRENCY,
+            max_retries: DEFAULT_MAX_RETRIES,
+        })
+    }
+
+    /// How many messages may be awaiting a verdict at once.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Publishes messages that cannot be moderated to `topic` instead of
+    /// the output topic.
+    pub fn dead_letter_topic(mut self, topic: &str) -> Self {
+        self.dead_letter_topic = Some(topic.to_string());
+        self
+    }
+
+    /// How many times a moderation call that failed transiently is retried,
+    /// with exponential backoff of up to 30 seconds, before the pipeline
+    /// stops.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Runs until the consumer fails, the API stays unavailable through every
+    /// retry, or a result cannot be published. Offsets of unfinished messages
+    /// are not stored, so they are redelivered on the next run.
+    pub async fn run(&self) -> Result<(), SafeCommsError> {
+        let mut results = self
+            .consumer
+            .stream()
+            .map(|message| async move {
+                let message = message?.detach();
+                let content = String::from_utf8_lossy(message.payload().unwrap_or_default())
+                    .into_owned();
+                let result = match self.moderate(&content).await {
+                    Err(e) if e.is_transient() => return Err(e),
+                    result => result,
+                };
+                Ok::<_, SafeCommsError>((message, content, result))
+            })
+            .buffered(self.concurrency);
+
+        while let Some(item) = results.next().await {
+            let (message, content, result) = item?;
+            match result {
+                Ok(result) => self.publish(&message, &content, &result).await?,
+                Err(e) => self.dead_letter(&message, &content, &e).await?,
+            }
+            self.consumer
+                .store_offset(message.topic(), message.partition(), message.offset() + 1)?;
+        }
+
+        Ok(())
+    }
+
+    async fn moderate(&self, content: &str) -> Result<ModerationResponse, SafeCommsError> {
+        let mut attempt = 0;
+        loop {
+            match self
+                .client
+                .moderate_text(content, None, None, None, None, None)
+                .await
+            {
+                Ok(result) => return Ok(result),
+                Err(e) if !e.is_transient() || attempt >= self.max_retries => return Err(e),
+                Err(_) => {
+                    let backoff = RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt));
+                    tokio::time::sleep(backoff.min(RETRY_MAX_DELAY)).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    async fn publish(
+        &self,
+        message: &OwnedMessage,
+        content: &str,
+        result: &ModerationResponse,
+    ) -> Result<(), SafeCommsError> {
+        let payload = serde_json::to_vec(&ModeratedRecord {
+            topic: message.topic(),
+            partition: message.partition(),
+            offset: message.offset(),
+            content,
+            result,
+        })?;
+        self.send(&self.output_topic, message, &payload).await
+    }
+
+    async fn dead_letter(
+        &self,
+        message: &OwnedMessage,
+        content: &str,
+        error: &SafeCommsError,
+    ) -> Result<(), SafeCommsError> {
+        log::warn!(
+            "cannot moderate {}/{}@{}: {}",
+            message.topic(),
+            message.partition(),
+            message.offset(),
+            error
+        );
+        let payload = serde_json::to_vec(&FailedRecord {
+            topic: message.topic(),
+            partition: message.partition(),
+            offset: message.offset(),
+            content,
+            error: error.to_string(),
+        })?;
+        let topic = self.dead_letter_topic.as_ref().unwrap_or(&self.output_topic);
+        self.send(topic, message, &payload).await
+    }
+
+    async fn send(
+        &self,
+        topic: &str,
+        message: &OwnedMessage,
+        payload: &[u8],
+    ) -> Result<(), SafeCommsError> {
+        let mut record = FutureRecord::<[u8], [u8]>::to(topic).payload(payload);
+        if let Some(key) = message.key() {
+            record = record.key(key);
+        }
+
+        self.producer
+            .send(record, Timeout::Never)
+            .await
+            .map_err(|(e, _)| SafeCommsError::KafkaError(e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use crate::TransportError;
+use crate::retry::retry_after;
+use reqwest::header::HeaderValue;
+use reqwest::{Response, StatusCode};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// How long a key that was rate limited is left out, unless the API says.
+const RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// How one API key has fared, as reported by
+/// [`SafeCommsClient::api_key_stats`](crate::SafeCommsClient::api_key_stats).
+/// Keys are identified by their position, the builder's key first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApiKeyStats {
+    pub index: usize,
+    pub requests: u64,
+    pub rate_limited: u64,
+    /// Whether the API rejected the key with a 401. Revoked keys are no
+    /// longer used while any other key is usable.
+    pub revoked: bool,
+    /// Until when the key is left out after being rate limited.
+    pub excluded_until: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct KeyState {
+    requests: u64,
+    rate_limited: u64,
+    revoked: bool,
+    excluded_until: Option<Instant>,
+}
+
+impl KeyState {
+    fn is_usable(&self, now: Instant) -> bool {
+        !self.revoked && self.excluded_until.is_none_or(|until| now >= until)
+    }
+}
+
+/// The API keys a client rotates between, one per request.
+pub(crate) struct ApiKeys {
+    keys: Vec<String>,
+    /// Each key's `Authorization` header, built once, or None if the key
+    /// cannot be sent in a header.
+    authorizations: Vec<Option<HeaderValue>>,
+    states: Mutex<Vec<KeyState>>,
+    next: AtomicUsize,
+}
+
+impl ApiKeys {
+    pub(crate) fn new(keys: Vec<String>) -> Self {
+        let states = keys.iter().map(|_| KeyState::default()).collect();
+        let authorizations = keys
+            .iter()
+            .map(|key| {
+                let mut value = HeaderValue::from_str(&format!("Bearer {}", key)).ok()?;
+                value.set_sensitive(true);
+                Some(value)
+            })
+            .collect();
+        Self {
+            keys,
+            authorizations,
+            states: Mutex::new(states),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn get(&self, index: usize) -> &str {
+        &self.keys[index]
+    }
+
+    pub(crate) fn authorization(&self, index: usize) -> Option<&HeaderValue> {
+        self.authorizations[index].as_ref()
+    }
+
+    /// The next usable key in turn. If no key is usable, the rate-limited
+    /// key that comes back soonest is used, and if every key is revoked, the
+    /// keys are used in turn regardless.
+    pub(crate) fn pick(&self) -> usize {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        if self.keys.len() == 1 {
+            return 0;
+        }
+
+        let now = Instant::now();
+        let states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        let in_turn = (0..self.keys.len()).map(|offset| (start + offset) % self.keys.len());
+        in_turn
+            .clone()
+            .find(|&index| states[index].is_usable(now))
+            .or_else(|| {
+                in_turn
+                    .filter(|&index| !states[index].revoked)
+                    .min_by_key(|&index| states[index].excluded_until)
+            })
+            .unwrap_or(start % self.keys.len())
+    }
+
+    /// Updates the key's standing from the outcome of a request made with it.
+    pub(crate) fn record(&self, index: usize, response: Result<&Response, &TransportError>) {
+        let mut states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        let state = &mut states[index];
+        state.requests += 1;
+
+        let Ok(response) = response else {
+            return;
+        };
+        match response.status() {
+            StatusCode::UNAUTHORIZED => state.revoked = true,
+            StatusCode::TOO_MANY_REQUESTS => {
+                state.rate_limited += 1;
+                let cooldown = retry_after(response).unwrap_or(RATE_LIMIT_COOLDOWN);
+                state.excluded_until = Some(Instant::now() + cooldown);
+            }
+            status if status.is_success() => {
+                state.revoked = false;
+                state.excluded_until = None;
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn stats(&self) -> Vec<ApiKeyStats> {
+        let states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        states
+            .iter()
+            .enumerate()
+            .map(|(index, state)| ApiKeyStats {
+                index,
+                requests: state.requests,
+                rate_limited: state.rate_limited,
+                revoked: state.revoked,
+                excluded_until: state.excluded_until,
+            })
+            .collect()
+    }
+}
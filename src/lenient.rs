@@ -0,0 +1,29 @@
+use crate::{ModerationResponse, SafeCommsError};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// Parses a moderation response field by field, leaving out fields that do
+/// not parse and describing each in `warnings` rather than failing.
+pub(crate) fn moderation_response(value: Value) -> Result<ModerationResponse, SafeCommsError> {
+    let Value::Object(fields) = value else {
+        return Ok(serde_json::from_value(value)?);
+    };
+
+    let mut warnings: Vec<String> = ["isClean", "isBypassAttempt"]
+        .into_iter()
+        .filter(|field| !fields.contains_key(*field))
+        .map(|field| format!("`{}` is missing and was read as false", field))
+        .collect();
+    let mut accepted = Map::new();
+    for (field, value) in fields {
+        accepted.insert(field.clone(), value);
+        if let Err(e) = ModerationResponse::deserialize(Value::Object(accepted.clone())) {
+            accepted.remove(&field);
+            warnings.push(format!("`{}` was ignored: {}", field, e));
+        }
+    }
+
+    let mut response: ModerationResponse = serde_json::from_value(Value::Object(accepted))?;
+    response.warnings = warnings;
+    Ok(response)
+}
@@ -1,273 +1,1003 @@
-use reqwest::{Client as HttpClient, multipart};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use reqwest::{Client as HttpClient, Method, RequestBuilder, Response, StatusCode};
+#[cfg(feature = "multipart")]
+use reqwest::multipart;
+use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, HeaderName, HeaderValue};
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+#[cfg(feature = "fs")]
 use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use thiserror::Error;
+use uuid::Uuid;
+
+mod ab;
+pub mod aggregate;
+mod builder;
+mod chat;
+#[cfg(feature = "compression")]
+mod compression;
+mod connection;
+mod context_window;
+#[cfg(feature = "csv")]
+mod csv_batch;
+mod bypass;
+#[cfg(feature = "record-replay")]
+mod cassette;
+mod dedupe;
+#[cfg(feature = "discord")]
+mod discord;
+mod dry_run;
+mod edit;
+pub mod emoji;
+mod endpoints;
+mod ensemble;
+mod environment;
+#[cfg(feature = "async")]
+mod expand;
+mod fallback;
+#[cfg(all(feature = "async", feature = "fs"))]
+mod files;
+mod guardrail;
+mod hooks;
+#[cfg(feature = "isahc")]
+mod isahc_transport;
+#[cfg(feature = "image-endpoints")]
+mod jobs;
+#[cfg(feature = "kafka")]
+mod kafka;
+mod keys;
+#[cfg(feature = "langdetect")]
+mod langdetect;
+mod lenient;
+#[cfg(feature = "live")]
+mod live;
+pub mod markup;
+#[cfg(feature = "matrix")]
+mod matrix;
+#[cfg(feature = "multipart")]
+mod mixed;
+#[cfg(feature = "metrics")]
+mod meters;
+#[cfg(any(feature = "actix", feature = "axum"))]
+pub mod middleware;
+#[cfg(feature = "onnx")]
+mod onnx;
+#[cfg(feature = "openai")]
+mod openai;
+#[cfg(feature = "otel")]
+mod otel;
+#[cfg(feature = "persistence")]
+mod persistence;
+#[cfg(feature = "perspective")]
+mod perspective;
+#[cfg(feature = "pii")]
+pub mod pii;
+mod policy;
+pub mod prefilter;
+mod preset;
+mod problem;
+mod prompt_injection;
+mod provider;
+mod proxy;
+mod redaction;
+mod region;
+mod report;
+mod retry;
+mod runtime;
+pub mod reputation;
+pub mod sanitize;
+#[cfg(all(feature = "async", feature = "fs"))]
+mod scan;
+mod scope;
+mod shadow;
+mod signing;
+mod stats;
+#[cfg(feature = "telegram")]
+mod telegram;
+#[cfg(feature = "tracing")]
+mod telemetry;
+mod tenants;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod timeouts;
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+mod tls;
+#[cfg(feature = "tower")]
+mod tower;
+mod transport;
+#[cfg(feature = "twitch")]
+mod twitch;
+mod validation;
+mod verdict;
+#[cfg(all(feature = "async", feature = "fs"))]
+mod watch;
+pub mod webhook;
+mod wire;
+
+pub use ab::{AbOutcome, AbReport, AbTest, Variant};
+pub use builder::SafeCommsClientBuilder;
+pub use bypass::{BypassSignal, detect_bypass_signals};
+#[cfg(feature = "record-replay")]
+pub use cassette::Cassette;
+pub use chat::{ChatModerator, ChatVerdict};
+#[cfg(feature = "compression")]
+pub use compression::Compression;
+pub use context_window::ContextWindow;
+#[cfg(feature = "csv")]
+pub use csv_batch::{CsvColumn, CsvOptions, CsvSummary};
+pub use dedupe::{DedupeStats, Deduper, simhash};
+pub use dry_run::DryRun;
+pub use ensemble::{Ensemble, EnsembleStrategy, EnsembleVerdict, Vote};
+#[cfg(feature = "compression")]
+use compression::RequestCompression;
+use endpoints::{Endpoint, Endpoints};
+use keys::ApiKeys;
+pub use environment::Environment;
+#[cfg(feature = "isahc")]
+pub use isahc_transport::IsahcTransport;
+#[cfg(feature = "async")]
+pub use expand::{ExpandedUrl, UrlExpander};
+pub use fallback::{Fallback, Verdict};
+pub use guardrail::{Guardrail, GuardrailStop};
+pub use hooks::Middleware;
+#[cfg(feature = "image-endpoints")]
+pub use jobs::JobEvent;
+#[cfg(feature = "kafka")]
+pub use kafka::KafkaPipeline;
+pub use keys::ApiKeyStats;
+#[cfg(feature = "langdetect")]
+pub use langdetect::detect_language;
+#[cfg(feature = "live")]
+pub use live::{LiveSession, LiveVerdict};
+#[cfg(feature = "matrix")]
+pub use matrix::RedactionRecommendation;
+#[cfg(feature = "multipart")]
+pub use mixed::{MessageAttachment, MixedMessage};
+#[cfg(feature = "onnx")]
+pub use onnx::{DecidedBy, ImagePrescreen, ModelSpec, PrescreenResult, TensorLayout};
+#[cfg(feature = "openai")]
+pub use openai::OpenAiModeration;
+#[cfg(feature = "persistence")]
+pub use persistence::{ModerationRecord, ResultStore};
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub use persistence::SqlxResultStore;
+#[cfg(feature = "perspective")]
+pub use perspective::PerspectiveModeration;
+pub use policy::{Action, ModerationResponseExt, Policy, Threshold};
+#[cfg(feature = "async")]
+pub use policy::PolicyWatcher;
+pub use preset::Preset;
+pub use problem::{ProblemDetails, ResponseError};
+pub use provider::{ModerationProvider, SafeCommsProfile};
+pub use proxy::Proxy;
+pub use redaction::RedactionPolicy;
+pub use region::Region;
+pub use report::Report;
+pub use retry::RetryBudget;
+#[cfg(all(feature = "async", feature = "fs"))]
+pub use scan::{ScanOptions, ScanReport, ScanSummary, ScannedFile};
+pub use safecomms_types::{
+    AddonUsage, ContextMessage, JobId, Language, Metadata, ModerationIssue, ModerationRequest,
+    ModerationResponse, PiiEntity, PiiEntityType, ProfileId, PromptInjectionResponse,
+    ReplaceSeverity, Replacement, ReplacementStyle, RequestId, Severity, TextModerationRequest,
+    normalize, spans, timestamp,
+};
+#[cfg(feature = "image-endpoints")]
+pub use safecomms_types::{ImageModerationRequest, Job};
+#[cfg(feature = "usage")]
+pub use safecomms_types::UsageResponse;
+pub use scope::{Scope, ScopedTask};
+pub use shadow::{Shadow, ShadowStats};
+pub use signing::{HmacSigner, RequestSigner};
+pub use stats::{ClientStats, ErrorCounts};
+use stats::StatsRecorder;
+#[cfg(feature = "telegram")]
+pub use telegram::TelegramModeration;
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+pub use tls::ClientIdentity;
+#[cfg(feature = "tower")]
+pub use tower::{Moderation, ModerationError, ModerationLayer};
+pub use tenants::{ClientManager, Pricing, TenantConfig, TenantUsage};
+use tenants::Tenant;
+pub use timeouts::EndpointClass;
+pub use transport::{HttpTransport, TransportError};
+#[cfg(feature = "twitch")]
+pub use twitch::{TwitchAction, TwitchModerator};
+pub use verdict::{AttachmentVerdict, EmbedVerdict, MessageVerdict, SuggestedAction};
+#[cfg(all(feature = "async", feature = "fs"))]
+pub use watch::{FolderWatcher, WatchResult};
+pub use wire::WireFormat;
+use timeouts::Timeouts;
+use validation::InputLimits;
+
+/// The client, its common types and the [`ModerationResponseExt`] methods,
+/// for `use safecomms::prelude::*;`.
+pub mod prelude {
+    pub use crate::{
+        Action, Language, Metadata, ModerationRequest, ModerationResponse, ModerationResponseExt,
+        Policy, ReplaceSeverity, SafeCommsClient, SafeCommsError, Severity,
+    };
+}
 
 const DEFAULT_BASE_URL: &str = "https://api.safecomms.dev";
+const ENVIRONMENT_HEADER: HeaderName = HeaderName::from_static("x-safecomms-environment");
+const IDEMPOTENCY_KEY_HEADER: HeaderName = HeaderName::from_static("idempotency-key");
+const CORRELATION_ID_HEADER: HeaderName = HeaderName::from_static("x-correlation-id");
+const REQUEST_ID_HEADER: &str = "X-Request-Id";
+const REGION_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How much of an error response body is kept, in bytes.
+const DEFAULT_ERROR_BODY_LIMIT: usize = 4096;
 
 #[derive(Error, Debug)]
 pub enum SafeCommsError {
     #[error("HTTP request failed")]
     RequestError(#[from] reqwest::Error),
+    #[error("HTTP transport failed")]
+    TransportError(#[source] TransportError),
     #[error("API error: {0}")]
     ApiError(String),
+    /// The API answered with a non-success status.
+    #[error("API error: {0}")]
+    ResponseError(Box<ResponseError>),
+    /// Input rejected locally, before a request was sent.
+    #[error("Invalid {field}: {reason}")]
+    InvalidInput { field: &'static str, reason: String },
+    /// A [`ClientManager`] was asked for a tenant it does not know.
+    #[error("Unknown tenant: {0}")]
+    UnknownTenant(String),
     #[error("Serialization error")]
     SerializationError(#[from] serde_json::Error),
+    #[cfg(feature = "csv")]
+    #[error("CSV error")]
+    CsvError(#[from] csv::Error),
+    #[cfg(feature = "kafka")]
+    #[error("Kafka error")]
+    KafkaError(#[from] rdkafka::error::KafkaError),
+    #[cfg(feature = "onnx")]
+    #[error("Image decoding error")]
+    ImageError(#[from] image::ImageError),
+    #[cfg(feature = "onnx")]
+    #[error("Model error: {0}")]
+    ModelError(#[from] tract_onnx::prelude::TractError),
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    #[error("Database error")]
+    DatabaseError(#[from] sqlx::Error),
+    #[cfg(feature = "simd-json")]
+    #[error("Serialization error")]
+    SimdJsonError(#[from] simd_json::Error),
+    #[cfg(feature = "toml")]
+    #[error("TOML error")]
+    TomlError(#[from] toml::de::Error),
+    #[cfg(feature = "live")]
+    #[error("WebSocket error")]
+    WebSocketError(#[from] tokio_tungstenite::tungstenite::Error),
+    #[cfg(any(feature = "msgpack", feature = "cbor"))]
+    #[error("Wire format error: {0}")]
+    WireFormatError(String),
+}
+
+impl SafeCommsError {
+    /// The HTTP status the API answered with, if it answered.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            SafeCommsError::ResponseError(e) => Some(e.status),
+            SafeCommsError::RequestError(e) => e.status(),
+            _ => None,
+        }
+    }
+
+    /// Whether the call may succeed if retried later: the API could not be
+    /// reached, timed out, was rate limited or failed with a 5xx. Invalid
+    /// input, rejected keys and other 4xx answers fail the same way again.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            SafeCommsError::TransportError(_) => true,
+            SafeCommsError::RequestError(e) if e.is_builder() || e.is_decode() => false,
+            SafeCommsError::RequestError(e) => e.status().is_none_or(is_transient_status),
+            SafeCommsError::ResponseError(e) => is_transient_status(e.status),
+            _ => false,
+        }
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(status, StatusCode::REQUEST_TIMEOUT | StatusCode::TOO_MANY_REQUESTS)
+        || status.is_server_error()
+}
+
+impl From<TransportError> for SafeCommsError {
+    fn from(e: TransportError) -> Self {
+        match e {
+            TransportError::Reqwest(e) => SafeCommsError::RequestError(e),
+            e => SafeCommsError::TransportError(e),
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct SafeCommsClient {
     client: HttpClient,
-    base_url: String,
-    api_key: String,
-}
-
-#[derive(Serialize)]
-pub struct TextModerationRequest<'a> {
-    pub content: &'a str,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub language: Option<&'a str>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub replace: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub pii: Option<bool>,
-    #[serde(rename = "replaceSeverity", skip_serializing_if = "Option::is_none")]
-    pub replace_severity: Option<&'a str>,
-    #[serde(rename = "moderationProfileId", skip_serializing_if = "Option::is_none")]
-    pub moderation_profile_id: Option<&'a str>,
+    transport: Arc<dyn HttpTransport>,
+    endpoints: Arc<Endpoints>,
+    keys: Arc<ApiKeys>,
+    environment: Environment,
+    dry_run: Option<Arc<DryRun>>,
+    idempotency_key: Option<String>,
+    correlation_id: Option<String>,
+    debug_logging: Option<RedactionPolicy>,
+    replacement_style: Option<ReplacementStyle>,
+    describe_emoji: bool,
+    lenient_parsing: bool,
+    error_body_limit: usize,
+    timeouts: Timeouts,
+    wire_format: WireFormat,
+    limits: InputLimits,
+    stats: Arc<StatsRecorder>,
+    middleware: Arc<Vec<Box<dyn Middleware>>>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    tenant: Option<Arc<Tenant>>,
+    #[cfg(feature = "async")]
+    url_expander: Option<Arc<UrlExpander>>,
+    #[cfg(feature = "compression")]
+    compression: Option<RequestCompression>,
+    #[cfg(feature = "langdetect")]
+    auto_language: bool,
+    #[cfg(feature = "persistence")]
+    result_store: Option<Arc<dyn ResultStore>>,
 }
 
-#[derive(Serialize)]
-pub struct ImageModerationRequest<'a> {
-    pub image: &'a str,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub language: Option<&'a str>,
-    #[serde(rename = "moderationProfileId", skip_serializing_if = "Option::is_none")]
-    pub moderation_profile_id: Option<&'a str>,
-    #[serde(rename = "enableOcr", skip_serializing_if = "Option::is_none")]
+/// Options for uploaded images; see [`SafeCommsClient::moderate_image_bytes`].
+#[derive(Debug, Clone, Default)]
+#[cfg(feature = "multipart")]
+pub struct ImageUploadOptions<'a> {
+    pub language: Option<Language>,
+    pub moderation_profile_id: Option<&'a ProfileId>,
     pub enable_ocr: Option<bool>,
-    #[serde(rename = "enhancedOcr", skip_serializing_if = "Option::is_none")]
     pub enhanced_ocr: Option<bool>,
-    #[serde(rename = "extractMetadata", skip_serializing_if = "Option::is_none")]
     pub extract_metadata: Option<bool>,
+    pub metadata: Option<&'a Metadata>,
 }
 
-#[derive(Deserialize, Debug)]
-pub struct ModerationResponse {
-    #[serde(rename = "isClean")]
-    pub is_clean: bool,
-    pub severity: Option<String>,
-    #[serde(rename = "categoryScores")]
-    pub category_scores: Option<HashMap<String, String>>,
-    pub issues: Option<Vec<ModerationIssue>>,
-    pub reason: Option<String>,
-    #[serde(rename = "isBypassAttempt")]
-    pub is_bypass_attempt: bool,
-    #[serde(rename = "safeContent")]
-    pub safe_content: Option<String>,
-    pub addons: Option<AddonUsage>,
-}
+impl SafeCommsClient {
+    pub fn new(api_key: String, base_url: Option<String>) -> Self {
+        let base_url = base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+        Self::with_endpoints(api_key, vec![base_url])
+    }
 
-#[derive(Deserialize, Debug)]
-pub struct ModerationIssue {
-    pub term: Option<String>,
-    pub context: Option<String>,
-}
+    /// Creates a client that fails over between `base_urls` in order, returning
+    /// to earlier endpoints once they recover.
+    pub fn with_endpoints(api_key: String, base_urls: Vec<String>) -> Self {
+        let base_urls = if base_urls.is_empty() {
+            vec![DEFAULT_BASE_URL.to_string()]
+        } else {
+            base_urls
+        };
 
-#[derive(Deserialize, Debug)]
-pub struct AddonUsage {
-    #[serde(rename = "replacedUnsafe")]
-    pub replaced_unsafe: bool,
-    #[serde(rename = "replacedPii")]
-    pub replaced_pii: bool,
-}
+        let client = HttpClient::new();
+        Self {
+            transport: Arc::new(client.clone()),
+            client,
+            endpoints: Arc::new(Endpoints::new(base_urls)),
+            keys: Arc::new(ApiKeys::new(vec![api_key])),
+            environment: Environment::default(),
+            dry_run: None,
+            idempotency_key: None,
+            correlation_id: None,
+            debug_logging: None,
+            replacement_style: None,
+            describe_emoji: false,
+            lenient_parsing: false,
+            error_body_limit: DEFAULT_ERROR_BODY_LIMIT,
+            timeouts: Timeouts::default(),
+            wire_format: WireFormat::default(),
+            limits: InputLimits::default(),
+            stats: Arc::default(),
+            middleware: Arc::default(),
+            signer: None,
+            tenant: None,
+            #[cfg(feature = "async")]
+            url_expander: None,
+            #[cfg(feature = "compression")]
+            compression: None,
+            #[cfg(feature = "langdetect")]
+            auto_language: false,
+            #[cfg(feature = "persistence")]
+            result_store: None,
+        }
+    }
 
-#[derive(Deserialize, Debug)]
-pub struct UsageResponse {
-    pub tier: String,
-    #[serde(rename = "rateLimit")]
-    pub rate_limit: i32,
-    #[serde(rename = "tokenLimit")]
-    pub token_limit: Option<i32>,
-    #[serde(rename = "tokensUsed")]
-    pub tokens_used: i32,
-    #[serde(rename = "remainingTokens")]
-    pub remaining_tokens: i32,
-}
+    pub fn builder(api_key: String) -> SafeCommsClientBuilder {
+        SafeCommsClientBuilder::new(api_key)
+    }
 
-#[derive(Deserialize, Debug)]
-struct ProblemDetails {
-    detail: Option<String>,
-    title: Option<String>,
-}
+    pub fn for_region(api_key: String, region: Region) -> Self {
+        Self::new(api_key, Some(region.base_url().to_string()))
+    }
 
-impl SafeCommsClient {
-    pub fn new(api_key: String, base_url: Option<String>) -> Self {
+    /// Probes each of `regions` and returns a client pinned to the one that
+    /// answered fastest.
+    pub async fn for_fastest_region(
+        api_key: String,
+        regions: &[Region],
+    ) -> Result<Self, SafeCommsError> {
+        let client = HttpClient::new();
+        let mut fastest: Option<(Region, Duration)> = None;
+        let mut last_error = None;
+
+        for &region in regions {
+            let started = Instant::now();
+            match client
+                .head(region.base_url())
+                .timeout(REGION_PROBE_TIMEOUT)
+                .send()
+                .await
+            {
+                Ok(_) => {
+                    let latency = started.elapsed();
+                    if fastest.is_none_or(|(_, best)| latency < best) {
+                        fastest = Some((region, latency));
+                    }
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        match (fastest, last_error) {
+            // The probe's connection is kept for the requests that follow.
+            (Some((region, _)), _) => Ok(Self {
+                transport: Arc::new(client.clone()),
+                client,
+                ..Self::for_region(api_key, region)
+            }),
+            (None, Some(e)) => Err(SafeCommsError::RequestError(e)),
+            (None, None) => Err(SafeCommsError::ApiError("No regions to probe".to_string())),
+        }
+    }
+
+    /// Returns a client that authenticates with `api_key` instead, sharing
+    /// this client's connection pool and configuration.
+    pub fn with_api_key(&self, api_key: String) -> Self {
+        Self {
+            keys: Arc::new(ApiKeys::new(vec![api_key])),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a client that sends `key` as the idempotency key of its
+    /// requests. Use the returned client for a single call.
+    pub fn with_idempotency_key(&self, key: String) -> Self {
+        Self {
+            idempotency_key: Some(key),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a client that sends `id` as the correlation ID of its
+    /// moderation requests, instead of a new UUID for each. Use the returned
+    /// client for a single call.
+    pub fn with_correlation_id(&self, id: String) -> Self {
         Self {
-            client: HttpClient::new(),
-            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
-                .trim_end_matches('/')
-                .to_string(),
-            api_key,
+            correlation_id: Some(id),
+            ..self.clone()
         }
     }
 
+    /// How each API key has fared, in the order they were given.
+    pub fn api_key_stats(&self) -> Vec<ApiKeyStats> {
+        self.keys.stats()
+    }
+
+    /// Counters and latencies for the requests this client has made.
+    pub fn stats(&self) -> ClientStats {
+        self.stats.snapshot(self.endpoints.retries())
+    }
+
+    /// Returns a client whose text moderation requests mask replaced content
+    /// with `style`.
+    pub fn with_replacement_style(&self, style: ReplacementStyle) -> Self {
+        Self {
+            replacement_style: Some(style),
+            ..self.clone()
+        }
+    }
+
+    /// A POST that may be sent more than once, to another endpoint or as a
+    /// retry, gets a key so the API can discard duplicates instead of
+    /// charging for them twice.
+    fn idempotency_key(&self, method: &Method) -> Option<String> {
+        match &self.idempotency_key {
+            Some(key) => Some(key.clone()),
+            None if method == Method::POST && self.endpoints.may_resend() => {
+                Some(Uuid::new_v4().to_string())
+            }
+            None => None,
+        }
+    }
+
+    fn request(
+        &self,
+        method: &Method,
+        endpoint: &Endpoint,
+        path: &str,
+        key: usize,
+        idempotency_key: Option<&str>,
+    ) -> RequestBuilder {
+        let request = match endpoint.url(path) {
+            Some(url) => self.client.request(method.clone(), url.clone()),
+            None => self
+                .client
+                .request(method.clone(), format!("{}{}", endpoint.base_url(), path)),
+        };
+        let mut request = match self.keys.authorization(key) {
+            Some(authorization) => request.header(AUTHORIZATION, authorization.clone()),
+            // Left to fail as an invalid header when the request is built.
+            None => request.header(AUTHORIZATION, format!("Bearer {}", self.keys.get(key))),
+        }
+        .header(ENVIRONMENT_HEADER, HeaderValue::from_static(self.environment.as_str()));
+
+        if let Some(key) = idempotency_key {
+            request = request.header(IDEMPOTENCY_KEY_HEADER, key);
+        }
+
+        #[cfg(feature = "otel")]
+        {
+            request = request.headers(otel::context_headers());
+        }
+
+        self.middleware
+            .iter()
+            .fold(request, |request, middleware| middleware.before_request(request))
+    }
+
+    /// Sends a request to the first healthy endpoint, compressing and
+    /// signing it once it is built. Every attempt picks its own API key, so
+    /// a retry after a 429 or 401 goes out with another key.
+    pub(crate) async fn execute(
+        &self,
+        build: impl Fn(&Endpoint, usize) -> RequestBuilder,
+    ) -> Result<Response, TransportError> {
+        let key = AtomicUsize::new(0);
+        let build = |endpoint: &Endpoint| {
+            key.store(self.keys.pick(), Ordering::Relaxed);
+            let mut request = build(endpoint, key.load(Ordering::Relaxed)).build()?;
+            // Logged before compression, so the body can still be read.
+            if let Some(policy) = self.debug_logging {
+                redaction::log_request(policy, &request);
+            }
+            #[cfg(feature = "compression")]
+            if let Some(compression) = self.compression {
+                compression.apply(&mut request);
+            }
+            if let Some(signer) = &self.signer {
+                signer.sign(&mut request);
+            }
+            Ok(request)
+        };
+        let observe = |response: Result<&Response, &TransportError>| {
+            self.keys.record(key.load(Ordering::Relaxed), response);
+            self.stats.record_attempt(response);
+        };
+        self.endpoints
+            .send(self.transport.as_ref(), build, observe)
+            .await
+    }
+
+    fn after_response(&self, response: &Response) {
+        for middleware in self.middleware.iter() {
+            middleware.after_response(response);
+        }
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> Result<T, SafeCommsError> {
+        #[cfg(feature = "tracing")]
+        let span = telemetry::request_span(&method, path);
+        let result = self.send_untraced(method, path, body);
+        #[cfg(feature = "tracing")]
+        let result = tracing::Instrument::instrument(result, span);
+        result.await
+    }
+
+    async fn send_untraced<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> Result<T, SafeCommsError> {
+        let idempotency_key = self.idempotency_key(&method);
+        let accept = self.wire_format.accept();
+        let timeout = self.timeouts.total(path);
+        let build = |endpoint: &Endpoint, key: usize| {
+            let mut request =
+                body(self.request(&method, endpoint, path, key, idempotency_key.as_deref()));
+            if let Some(accept) = &accept {
+                request = request.header(ACCEPT, accept.clone());
+            }
+            if let Some(timeout) = timeout {
+                request = request.timeout(timeout);
+            }
+            request
+        };
+
+        if let Some(dry_run) = &self.dry_run {
+            return dry_run.execute(build(self.endpoints.primary(), self.keys.pick()));
+        }
+
+        let started = Instant::now();
+        #[cfg(feature = "metrics")]
+        let in_flight = meters::InFlight::start();
+
+        let response = self.execute(build).await;
+        self.stats.record_latency(started.elapsed());
+        #[cfg(feature = "metrics")]
+        {
+            drop(in_flight);
+            meters::record_response(path, response.as_ref(), started.elapsed());
+        }
+        #[cfg(feature = "otel")]
+        otel::record_response(path, response.as_ref(), started.elapsed());
+        let response = response?;
+        self.after_response(&response);
+        #[cfg(feature = "tracing")]
+        telemetry::record_response(&response, started.elapsed());
+        let response = error_for_status(response, self.error_body_limit).await?;
+
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = response.bytes().await?;
+        if let Some(policy) = self.debug_logging {
+            redaction::log_response(policy, &method, path, status, &body);
+        }
+        wire::decode(content_type.as_deref(), body.into())
+    }
+
+    /// Sends a moderation request, recording the result in the result store
+    /// if there is one. `request` gives the body that identifies the content,
+    /// and is only called when there is a store to record it in; `metadata`
+    /// is what the request was tagged with. The correlation ID sent is added
+    /// to the response's metadata.
+    #[cfg_attr(not(feature = "persistence"), allow(unused_variables))]
+    async fn send_moderation<'a>(
+        &self,
+        path: &str,
+        request: impl FnOnce() -> Result<Cow<'a, [u8]>, SafeCommsError>,
+        metadata: Option<&Metadata>,
+        body: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> Result<ModerationResponse, SafeCommsError> {
+        let started = Instant::now();
+        let correlation_id = self
+            .correlation_id
+            .clone()
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let body = |builder| body(builder).header(CORRELATION_ID_HEADER, &correlation_id);
+        let mut result: ModerationResponse = if self.lenient_parsing {
+            lenient::moderation_response(self.send(Method::POST, path, body).await?)?
+        } else {
+            self.send(Method::POST, path, body).await?
+        };
+        tag(&mut result, metadata, correlation_id);
+        if let Some(tenant) = &self.tenant {
+            tenant.record(path, !result.is_clean);
+        }
+
+        #[cfg(feature = "persistence")]
+        if let Some(store) = &self.result_store {
+            match request() {
+                Ok(request) => {
+                    let latency = started.elapsed();
+                    persistence::record(store.as_ref(), path, &request, latency, &result).await;
+                }
+                Err(e) => log::warn!("Failed to record moderation result: {}", e),
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Moderates `content` with the given options. `language` and
+    /// `replace_severity` are typed; to pass them as strings such as `"en"`,
+    /// build a [`ModerationRequest`] and call
+    /// [`moderate_request`](Self::moderate_request).
     pub async fn moderate_text(
         &self,
         content: &str,
-        language: Option<&str>,
+        language: Option<Language>,
         replace: Option<bool>,
         pii: Option<bool>,
-        replace_severity: Option<&str>,
-        moderation_profile_id: Option<&str>,
+        replace_severity: Option<ReplaceSeverity>,
+        moderation_profile_id: Option<&ProfileId>,
     ) -> Result<ModerationResponse, SafeCommsError> {
-        let request = TextModerationRequest {
+        self.send_text(TextModerationRequest {
             content,
             language,
             replace,
             pii,
             replace_severity,
             moderation_profile_id,
-        };
+            replacement_style: self.replacement_style.as_ref(),
+            metadata: None,
+            context: None,
+        })
+        .await
+    }
 
-        let response = self.client
-            .post(format!("{}/moderation/text", self.base_url))
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&request)
-            .send()
-            .await?;
+    /// Moderates text like [`moderate_text`](Self::moderate_text), tagging
+    /// the request with its metadata.
+    pub async fn moderate_request(
+        &self,
+        request: &ModerationRequest,
+    ) -> Result<ModerationResponse, SafeCommsError> {
+        self.send_text(self.text_request(request)).await
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await?;
-            
-            // Try to parse ProblemDetails
-            if let Ok(problem) = serde_json::from_str::<ProblemDetails>(&error_text) {
-                return Err(SafeCommsError::ApiError(
-                    problem.detail.or(problem.title).unwrap_or_else(|| status.to_string())
-                ));
-            }
-            
-            return Err(SafeCommsError::ApiError(format!("{} - {}", status, error_text)));
+    fn text_request<'a>(&'a self, request: &'a ModerationRequest) -> TextModerationRequest<'a> {
+        TextModerationRequest {
+            content: &request.content,
+            language: request.language.clone(),
+            replace: request.replace,
+            pii: request.pii,
+            replace_severity: request.replace_severity.clone(),
+            moderation_profile_id: request.moderation_profile_id.as_ref(),
+            replacement_style: self.replacement_style.as_ref(),
+            metadata: (!request.metadata.is_empty()).then_some(&request.metadata),
+            context: (!request.context.is_empty()).then_some(request.context.as_slice()),
         }
+    }
 
-        let result = response.json::<ModerationResponse>().await?;
-        Ok(result)
+    async fn send_text(
+        &self,
+        request: TextModerationRequest<'_>,
+    ) -> Result<ModerationResponse, SafeCommsError> {
+        self.limits.text("content", request.content)?;
+        let tenant_metadata;
+        let request = match &self.tenant {
+            Some(tenant) => {
+                tenant_metadata = tenant.metadata(request.metadata);
+                TextModerationRequest {
+                    language: request.language.or_else(|| tenant.language.clone()),
+                    moderation_profile_id: request
+                        .moderation_profile_id
+                        .or(tenant.moderation_profile_id.as_ref()),
+                    metadata: Some(&tenant_metadata),
+                    ..request
+                }
+            }
+            None => request,
+        };
+        #[cfg(feature = "async")]
+        let expanded;
+        #[cfg(feature = "async")]
+        let request = match &self.url_expander {
+            Some(expander) => {
+                expanded = expander.expand_text(request.content).await;
+                TextModerationRequest {
+                    content: &expanded,
+                    ..request
+                }
+            }
+            None => request,
+        };
+        #[cfg(feature = "langdetect")]
+        let request = TextModerationRequest {
+            language: request.language.or_else(|| {
+                self.auto_language
+                    .then(|| detect_language(request.content))
+                    .flatten()
+                    .map(Language::from)
+            }),
+            ..request
+        };
+
+        let described;
+        let (request, rewritten) = if self.describe_emoji {
+            described = emoji::describe(request.content);
+            let rewritten = described != request.content;
+            let request = TextModerationRequest {
+                content: &described,
+                ..request
+            };
+            (request, rewritten)
+        } else {
+            (request, false)
+        };
+
+        let payload = self.wire_format.encode(&request)?;
+        let body = || payload.json(&request);
+        let mut response = self
+            .send_moderation("/moderation/text", body, request.metadata, |builder| {
+                payload.attach(builder)
+            })
+            .await?;
+        // The masked text and PII offsets are for the described text, which
+        // the caller never saw.
+        if rewritten {
+            response.safe_content = None;
+            response.pii_entities = None;
+        }
+        Ok(response)
     }
 
+    #[cfg(feature = "image-endpoints")]
     pub async fn moderate_image(
         &self,
         request: ImageModerationRequest<'_>,
     ) -> Result<ModerationResponse, SafeCommsError> {
-        let response = self.client
-            .post(format!("{}/moderation/image", self.base_url))
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&request)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await?;
-            
-            if let Ok(problem) = serde_json::from_str::<ProblemDetails>(&error_text) {
-                return Err(SafeCommsError::ApiError(
-                    problem.detail.or(problem.title).unwrap_or_else(|| status.to_string())
-                ));
+        self.limits.image(request.image)?;
+        let tenant_metadata;
+        let request = match &self.tenant {
+            Some(tenant) => {
+                tenant_metadata = tenant.metadata(request.metadata);
+                ImageModerationRequest {
+                    language: request.language.or_else(|| tenant.language.clone()),
+                    moderation_profile_id: request
+                        .moderation_profile_id
+                        .or(tenant.moderation_profile_id.as_ref()),
+                    metadata: Some(&tenant_metadata),
+                    ..request
+                }
             }
-            
-            return Err(SafeCommsError::ApiError(format!("{} - {}", status, error_text)));
-        }
-
-        let result = response.json::<ModerationResponse>().await?;
-        Ok(result)
+            None => request,
+        };
+        let payload = self.wire_format.encode(&request)?;
+        let body = || payload.json(&request);
+        self.send_moderation("/moderation/image", body, request.metadata, |builder| {
+            payload.attach(builder)
+        })
+        .await
     }
 
+    #[cfg(feature = "fs")]
     pub async fn moderate_image_file(
         &self,
         file_path: &str,
-        language: Option<&str>,
-        moderation_profile_id: Option<&str>,
+        language: Option<Language>,
+        moderation_profile_id: Option<&ProfileId>,
         enable_ocr: Option<bool>,
         enhanced_ocr: Option<bool>,
         extract_metadata: Option<bool>,
     ) -> Result<ModerationResponse, SafeCommsError> {
-        let file_bytes = tokio::fs::read(file_path).await
+        let file_bytes = runtime::read(Path::new(file_path)).await
             .map_err(|e| SafeCommsError::ApiError(format!("Failed to read file: {}", e)))?;
         
         let file_name = Path::new(file_path)
             .file_name()
             .and_then(|n| n.to_str())
-            .unwrap_or("image.jpg")
-            .to_string();
+            .unwrap_or("image.jpg");
 
-        let mut form = multipart::Form::new()
-            .part("image", multipart::Part::bytes(file_bytes).file_name(file_name));
+        let options = ImageUploadOptions {
+            language,
+            moderation_profile_id,
+            enable_ocr,
+            enhanced_ocr,
+            extract_metadata,
+            metadata: None,
+        };
 
-        if let Some(lang) = language {
-            form = form.text("language", lang.to_string());
-        }
-        
-        if let Some(profile_id) = moderation_profile_id {
-            form = form.text("moderationProfileId", profile_id.to_string());
-        }
+        self.moderate_image_bytes(file_bytes, file_name, options).await
+    }
 
-        if let Some(enable) = enable_ocr {
-            form = form.text("enableOcr", enable.to_string());
-        }
+    /// Uploads an in-memory image, e.g. a chat attachment that has just been
+    /// downloaded.
+    #[cfg(feature = "multipart")]
+    pub async fn moderate_image_bytes(
+        &self,
+        bytes: Vec<u8>,
+        file_name: &str,
+        options: ImageUploadOptions<'_>,
+    ) -> Result<ModerationResponse, SafeCommsError> {
+        self.limits.image_bytes(&bytes)?;
+        let tenant_metadata;
+        let options = match &self.tenant {
+            Some(tenant) => {
+                tenant_metadata = tenant.metadata(options.metadata);
+                ImageUploadOptions {
+                    language: options.language.or_else(|| tenant.language.clone()),
+                    moderation_profile_id: options
+                        .moderation_profile_id
+                        .or(tenant.moderation_profile_id.as_ref()),
+                    metadata: Some(&tenant_metadata),
+                    ..options
+                }
+            }
+            None => options,
+        };
+        let metadata = options.metadata.map(serde_json::to_string).transpose()?;
+        // A multipart form can only be sent once, so it is rebuilt for every
+        // endpoint attempted.
+        let build_form = || {
+            let mut form = multipart::Form::new()
+                .part("image", multipart::Part::bytes(bytes.clone()).file_name(file_name.to_string()));
 
-        if let Some(enhanced) = enhanced_ocr {
-            form = form.text("enhancedOcr", enhanced.to_string());
-        }
+            if let Some(lang) = &options.language {
+                form = form.text("language", lang.as_str().to_string());
+            }
 
-        if let Some(extract) = extract_metadata {
-            form = form.text("extractMetadata", extract.to_string());
-        }
+            if let Some(profile_id) = options.moderation_profile_id {
+                form = form.text("moderationProfileId", profile_id.to_string());
+            }
 
-        let response = self.client
-            .post(format!("{}/moderation/image/upload", self.base_url))
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .multipart(form)
-            .send()
-            .await?;
+            if let Some(enable) = options.enable_ocr {
+                form = form.text("enableOcr", enable.to_string());
+            }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await?;
-            
-            if let Ok(problem) = serde_json::from_str::<ProblemDetails>(&error_text) {
-                return Err(SafeCommsError::ApiError(
-                    problem.detail.or(problem.title).unwrap_or_else(|| status.to_string())
-                ));
+            if let Some(enhanced) = options.enhanced_ocr {
+                form = form.text("enhancedOcr", enhanced.to_string());
             }
-            
-            return Err(SafeCommsError::ApiError(format!("{} - {}", status, error_text)));
-        }
 
-        let result = response.json::<ModerationResponse>().await?;
-        Ok(result)
+            if let Some(extract) = options.extract_metadata {
+                form = form.text("extractMetadata", extract.to_string());
+            }
+
+            if let Some(metadata) = &metadata {
+                form = form.text("metadata", metadata.clone());
+            }
+
+            form
+        };
+
+        let body = || Ok(Cow::Borrowed(bytes.as_slice()));
+        self.send_moderation(
+            "/moderation/image/upload",
+            body,
+            options.metadata,
+            |builder| builder.multipart(build_form()),
+        )
+        .await
     }
 
+    #[cfg(feature = "usage")]
     pub async fn get_usage(&self) -> Result<UsageResponse, SafeCommsError> {
-        let response = self.client
-            .get(format!("{}/usage", self.base_url))
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .send()
-            .await?;
+        self.send(Method::GET, "/usage", |builder| builder).await
+    }
+}
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await?;
-             if let Ok(problem) = serde_json::from_str::<ProblemDetails>(&error_text) {
-                return Err(SafeCommsError::ApiError(
-                    problem.detail.or(problem.title).unwrap_or_else(|| status.to_string())
-                ));
-            }
-            return Err(SafeCommsError::ApiError(format!("{} - {}", status, error_text)));
-        }
+/// A response for a verdict reached locally, without the API.
+pub(crate) fn local_response(
+    is_clean: bool,
+    reason: Option<String>,
+    issues: Option<Vec<ModerationIssue>>,
+) -> ModerationResponse {
+    ModerationResponse {
+        is_clean,
+        severity: None,
+        category_scores: None,
+        issues,
+        reason,
+        is_bypass_attempt: false,
+        safe_content: None,
+        addons: None,
+        pii_entities: None,
+        metadata: Metadata::default(),
+        warnings: Vec::new(),
+    }
+}
 
-        let result = response.json::<UsageResponse>().await?;
-        Ok(result)
+/// Carries the request's metadata over to a response that does not echo it,
+/// along with the correlation ID that was sent.
+fn tag(response: &mut ModerationResponse, metadata: Option<&Metadata>, correlation_id: String) {
+    if let Some(metadata) = metadata
+        && response.metadata.is_empty()
+    {
+        response.metadata = metadata.clone();
     }
+    response.metadata = std::mem::take(&mut response.metadata).correlation_id(correlation_id);
+}
+
+async fn error_for_status(response: Response, limit: usize) -> Result<Response, SafeCommsError> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let error = ResponseError::read(response, limit).await?;
+    Err(SafeCommsError::ResponseError(Box::new(error)))
 }
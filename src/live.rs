@@ -0,0 +1,265 @@
+use crate::{ENVIRONMENT_HEADER, ModerationResponse, SafeCommsClient, SafeCommsError};
+use futures_util::{SinkExt, StreamExt};
+use reqwest::header::{AUTHORIZATION, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// A moderation verdict received over a live session.
+#[derive(Debug)]
+pub struct LiveVerdict {
+    pub id: String,
+    pub result: ModerationResponse,
+}
+
+/// A persistent connection for moderating a high volume of short messages,
+/// such as live chat.
+///
+/// Messages are tagged with a caller-chosen id, and verdicts arrive
+/// asynchronously carrying the same id. The connection is kept alive with
+/// pings and re-established if it drops; messages that had not been answered
+/// yet are sent again after reconnecting.
+///
+/// Each connection picks the next API key in turn and carries the client's
+/// environment, but it is made directly to the primary endpoint rather than
+/// through the client's HTTP stack. A custom [`HttpTransport`], proxies,
+/// custom root certificates, client certificates, request signing and
+/// middleware do not apply to it, and sessions cannot be opened in dry-run
+/// mode. Deployments that depend on any of these should moderate over HTTP
+/// instead.
+///
+/// [`HttpTransport`]: crate::HttpTransport
+pub struct LiveSession {
+    commands: mpsc::UnboundedSender<Command>,
+    verdicts: mpsc::UnboundedReceiver<Result<LiveVerdict, SafeCommsError>>,
+}
+
+impl LiveSession {
+    pub fn send(&self, id: String, content: String) -> Result<(), SafeCommsError> {
+        self.commands
+            .send(Command::Moderate(PendingMessage { id, content }))
+            .map_err(|_| SafeCommsError::ApiError("Live session is closed".to_string()))
+    }
+
+    /// Waits for the next verdict. Returns `None` once the session has closed.
+    pub async fn recv(&mut self) -> Option<Result<LiveVerdict, SafeCommsError>> {
+        self.verdicts.recv().await
+    }
+
+    pub fn close(&self) {
+        let _ = self.commands.send(Command::Close);
+    }
+}
+
+impl SafeCommsClient {
+    pub async fn open_session(&self) -> Result<LiveSession, SafeCommsError> {
+        if self.dry_run.is_some() {
+            return Err(SafeCommsError::ApiError(
+                "Live sessions are not available in dry-run mode".to_string(),
+            ));
+        }
+
+        let url = self
+            .endpoints
+            .primary()
+            .base_url()
+            .replacen("https://", "wss://", 1)
+            .replacen("http://", "ws://", 1)
+            + "/live";
+
+        let connector = Connector {
+            url,
+            client: self.clone(),
+        };
+        let socket = connector.connect().await?;
+
+        let (commands, command_rx) = mpsc::unbounded_channel();
+        let (verdict_tx, verdicts) = mpsc::unbounded_channel();
+        tokio::spawn(run(connector, socket, command_rx, verdict_tx));
+
+        Ok(LiveSession { commands, verdicts })
+    }
+}
+
+enum Command {
+    Moderate(PendingMessage),
+    Close,
+}
+
+struct PendingMessage {
+    id: String,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct Outgoing<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    id: &'a str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct Incoming {
+    id: Option<String>,
+    result: Option<ModerationResponse>,
+    error: Option<String>,
+}
+
+struct Connector {
+    url: String,
+    client: SafeCommsClient,
+}
+
+impl Connector {
+    async fn connect(&self) -> Result<Socket, SafeCommsError> {
+        let key = self.client.keys.pick();
+        let authorization = self
+            .client
+            .keys
+            .authorization(key)
+            .cloned()
+            .ok_or_else(|| SafeCommsError::ApiError("Invalid API key".to_string()))?;
+
+        let mut request = self.url.as_str().into_client_request()?;
+        let headers = request.headers_mut();
+        headers.insert(AUTHORIZATION, authorization);
+        headers.insert(
+            ENVIRONMENT_HEADER,
+            HeaderValue::from_static(self.client.environment.as_str()),
+        );
+
+        let (socket, _) = connect_async(request).await?;
+        Ok(socket)
+    }
+}
+
+enum Outcome {
+    Closed,
+    Disconnected,
+}
+
+async fn run(
+    connector: Connector,
+    mut socket: Socket,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    verdicts: mpsc::UnboundedSender<Result<LiveVerdict, SafeCommsError>>,
+) {
+    let mut pending = Vec::new();
+
+    loop {
+        if let Outcome::Closed = drive(&mut socket, &mut commands, &verdicts, &mut pending).await {
+            return;
+        }
+
+        let mut attempts = 0;
+        socket = loop {
+            tokio::time::sleep(RECONNECT_DELAY).await;
+            match connector.connect().await {
+                Ok(socket) => break socket,
+                Err(e) => {
+                    attempts += 1;
+                    if attempts >= MAX_RECONNECT_ATTEMPTS {
+                        let _ = verdicts.send(Err(e));
+                        return;
+                    }
+                }
+            }
+        };
+    }
+}
+
+async fn drive(
+    socket: &mut Socket,
+    commands: &mut mpsc::UnboundedReceiver<Command>,
+    verdicts: &mpsc::UnboundedSender<Result<LiveVerdict, SafeCommsError>>,
+    pending: &mut Vec<PendingMessage>,
+) -> Outcome {
+    for message in pending.iter() {
+        if socket.send(encode(message)).await.is_err() {
+            return Outcome::Disconnected;
+        }
+    }
+
+    let mut keepalive = tokio::time::interval(PING_INTERVAL);
+    keepalive.tick().await;
+    let mut awaiting_pong = false;
+
+    loop {
+        tokio::select! {
+            command = commands.recv() => match command {
+                Some(Command::Moderate(message)) => {
+                    let sent = socket.send(encode(&message)).await;
+                    pending.push(message);
+                    if sent.is_err() {
+                        return Outcome::Disconnected;
+                    }
+                }
+                Some(Command::Close) | None => {
+                    let _ = socket.close(None).await;
+                    return Outcome::Closed;
+                }
+            },
+            message = socket.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    if let Ok(incoming) = serde_json::from_str::<Incoming>(text.as_str()) {
+                        deliver(incoming, verdicts, pending);
+                    }
+                }
+                Some(Ok(Message::Pong(_))) => awaiting_pong = false,
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Outcome::Disconnected,
+                Some(Ok(_)) => {}
+            },
+            _ = keepalive.tick() => {
+                // No pong since the previous ping; assume the connection is dead.
+                if awaiting_pong {
+                    return Outcome::Disconnected;
+                }
+                awaiting_pong = true;
+                if socket.send(Message::Ping(Default::default())).await.is_err() {
+                    return Outcome::Disconnected;
+                }
+            }
+        }
+    }
+}
+
+fn encode(message: &PendingMessage) -> Message {
+    let outgoing = Outgoing {
+        kind: "moderate",
+        id: &message.id,
+        content: &message.content,
+    };
+    Message::text(serde_json::to_string(&outgoing).expect("live message serializes"))
+}
+
+fn deliver(
+    incoming: Incoming,
+    verdicts: &mpsc::UnboundedSender<Result<LiveVerdict, SafeCommsError>>,
+    pending: &mut Vec<PendingMessage>,
+) {
+    let Some(id) = incoming.id else {
+        return;
+    };
+    pending.retain(|message| message.id != id);
+
+    let verdict = match (incoming.result, incoming.error) {
+        (Some(result), _) => Ok(LiveVerdict { id, result }),
+        (None, error) => Err(SafeCommsError::ApiError(format!(
+            "{}: {}",
+            id,
+            error.unwrap_or_else(|| "No verdict returned".to_string())
+        ))),
+    };
+    let _ = verdicts.send(verdict);
+}
@@ -0,0 +1,211 @@
+//! Reduces Markdown and HTML to the text a reader would see, so that link
+//! targets, tags and code don't pollute moderation, while keeping track of
+//! where each piece of text came from.
+
+use std::ops::Range;
+
+/// Elements whose content is never shown to a reader.
+const HIDDEN_HTML_ELEMENTS: &[&str] = &["script", "style", "template"];
+/// Elements that start a new line of text.
+const BLOCK_HTML_ELEMENTS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "br", "dd", "div", "dl", "dt", "figcaption",
+    "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "li", "main", "nav", "ol", "p",
+    "pre", "section", "table", "td", "th", "tr", "ul",
+];
+
+/// A piece of text taken from the original markup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Segment {
+    start: usize,
+    original: Range<usize>,
+    len: usize,
+}
+
+/// Plain text extracted from markup, able to map positions back to the
+/// original.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stripped {
+    pub text: String,
+    segments: Vec<Segment>,
+}
+
+impl Stripped {
+    fn push(&mut self, text: &str, original: Range<usize>) {
+        if text.is_empty() {
+            return;
+        }
+        self.segments.push(Segment {
+            start: self.text.len(),
+            original,
+            len: text.len(),
+        });
+        self.text.push_str(text);
+    }
+
+    /// Adds a separator that has no counterpart in the original.
+    fn separate(&mut self, separator: char) {
+        if !self.text.is_empty() && !self.text.ends_with(['\n', separator]) {
+            self.text.push(separator);
+        }
+    }
+
+    /// The pieces of text that make up the stripped text, each with the byte
+    /// range it was taken from in the original.
+    pub fn segments(&self) -> impl Iterator<Item = (&str, Range<usize>)> {
+        self.segments.iter().map(|segment| {
+            (
+                &self.text[segment.start..segment.start + segment.len],
+                segment.original.clone(),
+            )
+        })
+    }
+
+    /// Maps a byte range of the stripped text to the corresponding range of
+    /// the original. Text that was unescaped (`&amp;` or `\*`) maps to the
+    /// whole escape.
+    pub fn original_range(&self, range: Range<usize>) -> Option<Range<usize>> {
+        let start = self.original_offset(range.start, false)?;
+        let end = self.original_offset(range.end.max(range.start + 1) - 1, true)?;
+        Some(start..end.max(start))
+    }
+
+    fn original_offset(&self, offset: usize, end: bool) -> Option<usize> {
+        let index = self
+            .segments
+            .partition_point(|segment| segment.start <= offset)
+            .checked_sub(1)?;
+        let segment = &self.segments[index];
+        let within = offset - segment.start;
+
+        if within >= segment.len {
+            // A separator after the segment.
+            return Some(segment.original.end);
+        }
+        if segment.len != segment.original.len() {
+            return Some(if end { segment.original.end } else { segment.original.start });
+        }
+        Some(segment.original.start + within + usize::from(end))
+    }
+}
+
+/// Extracts the visible text of an HTML fragment. Tags, comments and the
+/// content of `<script>` and `<style>` are dropped, block elements become
+/// line breaks and common entities are decoded.
+pub fn strip_html(html: &str) -> Stripped {
+    let mut stripped = Stripped::default();
+    let mut position = 0;
+
+    while position < html.len() {
+        let rest = &html[position..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            position += 4 + comment.find("-->").map_or(comment.len(), |end| end + 3);
+        } else if rest.starts_with('<') && let Some(end) = rest.find('>') {
+            let tag = &rest[1..end];
+            let name = tag_name(tag);
+            position += end + 1;
+
+            if BLOCK_HTML_ELEMENTS.contains(&name.as_str()) {
+                stripped.separate('\n');
+            }
+            if !tag.starts_with('/') && HIDDEN_HTML_ELEMENTS.contains(&name.as_str()) {
+                let closing = format!("</{}", name);
+                position += html[position..]
+                    .to_ascii_lowercase()
+                    .find(&closing)
+                    .unwrap_or(html.len() - position);
+            }
+        } else if rest.starts_with('&') && let Some((decoded, len)) = decode_entity(rest) {
+            stripped.push(decoded, position..position + len);
+            position += len;
+        } else {
+            // A `&` that is not an entity, or a `<` that opens no tag, is
+            // text like any other.
+            let first = rest.chars().next().map_or(1, char::len_utf8);
+            let len = rest[first..].find(['<', '&']).map_or(rest.len(), |len| len + first);
+            stripped.push(&rest[..len], position..position + len);
+            position += len;
+        }
+    }
+
+    stripped
+}
+
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('/')
+        .split(|c: char| c.is_whitespace() || c == '/')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+fn decode_entity(text: &str) -> Option<(&'static str, usize)> {
+    const ENTITIES: &[(&str, &str)] = &[
+        ("&amp;", "&"),
+        ("&lt;", "<"),
+        ("&gt;", ">"),
+        ("&quot;", "\""),
+        ("&#39;", "'"),
+        ("&apos;", "'"),
+        ("&nbsp;", " "),
+    ];
+    ENTITIES
+        .iter()
+        .find(|(entity, _)| text.starts_with(entity))
+        .map(|(entity, decoded)| (*decoded, entity.len()))
+}
+
+/// Extracts the visible text of a Markdown document. Link and image targets
+/// are dropped while their text is kept, and fenced or indented code blocks
+/// are dropped unless `keep_code` is set.
+#[cfg(feature = "markdown")]
+pub fn strip_markdown(markdown: &str, keep_code: bool) -> Stripped {
+    use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+    let mut stripped = Stripped::default();
+    let mut in_code_block = false;
+
+    for (event, range) in Parser::new(markdown).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code_block = true;
+                stripped.separate('\n');
+            }
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(text) | Event::Code(text) if !in_code_block || keep_code => {
+                stripped.push(&text, range)
+            }
+            Event::Html(html) | Event::InlineHtml(html) => {
+                let html = strip_html(&html);
+                for (text, original) in html.segments() {
+                    stripped.push(text, range.start + original.start..range.start + original.end);
+                }
+            }
+            Event::SoftBreak => stripped.separate(' '),
+            Event::HardBreak
+            | Event::Rule
+            | Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::Item
+                | TagEnd::BlockQuote(_)
+                | TagEnd::TableCell,
+            ) => stripped.separate('\n'),
+            _ => {}
+        }
+    }
+
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_ampersand_does_not_expose_markup() {
+        let stripped = strip_html("Tom & Jerry <script>alert('x')</script><b>bold</b>");
+        assert_eq!(stripped.text, "Tom & Jerry bold");
+        assert_eq!(strip_html("é & <b>ü</b>").text, "é & ü");
+    }
+}
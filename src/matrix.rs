@@ -0,0 +1,136 @@
+use crate::{AttachmentVerdict, MessageVerdict, SafeCommsClient, SafeCommsError, SuggestedAction};
+use futures_util::future::BoxFuture;
+use matrix_sdk::Room;
+use matrix_sdk::media::{MediaFormat, MediaRequestParameters};
+use matrix_sdk::ruma::events::room::message::{
+    ImageMessageEventContent, MessageType, OriginalSyncRoomMessageEvent,
+};
+use matrix_sdk::ruma::{OwnedEventId, OwnedRoomId, OwnedUserId};
+use std::future::Future;
+
+/// A suggestion to redact a message, with the verdict that prompted it.
+#[derive(Debug)]
+pub struct RedactionRecommendation {
+    pub room_id: OwnedRoomId,
+    pub event_id: OwnedEventId,
+    pub sender: OwnedUserId,
+    pub reason: String,
+    pub verdict: MessageVerdict,
+}
+
+impl SafeCommsClient {
+    /// Moderates an `m.room.message` event, downloading image media through
+    /// the room's client. Returns a recommendation when the message should be
+    /// redacted.
+    pub async fn moderate_matrix_message(
+        &self,
+        room: &Room,
+        event: &OriginalSyncRoomMessageEvent,
+    ) -> Result<Option<RedactionRecommendation>, SafeCommsError> {
+        let verdict = match &event.content.msgtype {
+            MessageType::Text(content) => self.moderate_matrix_text(&content.body).await?,
+            MessageType::Notice(content) => self.moderate_matrix_text(&content.body).await?,
+            MessageType::Emote(content) => self.moderate_matrix_text(&content.body).await?,
+            MessageType::Image(content) => self.moderate_matrix_image(room, content).await?,
+            _ => return Ok(None),
+        };
+
+        if verdict.action < SuggestedAction::Delete {
+            return Ok(None);
+        }
+
+        let reason = verdict
+            .text
+            .as_ref()
+            .and_then(|text| text.reason.clone())
+            .unwrap_or_else(|| "Flagged by content moderation".to_string());
+
+        Ok(Some(RedactionRecommendation {
+            room_id: room.room_id().to_owned(),
+            event_id: event.event_id.clone(),
+            sender: event.sender.clone(),
+            reason,
+            verdict,
+        }))
+    }
+
+    /// Builds a handler for `matrix_sdk::Client::add_event_handler` that
+    /// moderates every message from other users and calls `on_redaction`
+    /// with each recommendation. Moderation failures are logged and skipped.
+    pub fn matrix_event_handler<F, Fut>(
+        &self,
+        on_redaction: F,
+    ) -> impl Fn(OriginalSyncRoomMessageEvent, Room) -> BoxFuture<'static, ()>
+    + Clone
+    + Send
+    + Sync
+    + 'static
+    where
+        F: Fn(Room, RedactionRecommendation) -> Fut + Clone + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let client = self.clone();
+        move |event: OriginalSyncRoomMessageEvent, room: Room| {
+            let client = client.clone();
+            let on_redaction = on_redaction.clone();
+            Box::pin(async move {
+                if room.own_user_id() == event.sender {
+                    return;
+                }
+
+                match client.moderate_matrix_message(&room, &event).await {
+                    Ok(Some(recommendation)) => on_redaction(room, recommendation).await,
+                    Ok(None) => {}
+                    Err(e) => log::warn!("failed to moderate {}: {}", event.event_id, e),
+                }
+            })
+        }
+    }
+
+    async fn moderate_matrix_text(&self, body: &str) -> Result<MessageVerdict, SafeCommsError> {
+        let text = self.moderate_text(body, None, None, None, None, None).await?;
+        Ok(MessageVerdict::new(Some(text), Vec::new()))
+    }
+
+    async fn moderate_matrix_image(
+        &self,
+        room: &Room,
+        content: &ImageMessageEventContent,
+    ) -> Result<MessageVerdict, SafeCommsError> {
+        // When a filename is present the body is a caption.
+        let caption = async {
+            match &content.filename {
+                Some(_) if !content.body.trim().is_empty() => self
+                    .moderate_text(&content.body, None, None, None, None, None)
+                    .await
+                    .map(Some),
+                _ => Ok(None),
+            }
+        };
+
+        let file_name = content.filename.clone().unwrap_or_else(|| content.body.clone());
+        let image = async {
+            let request = MediaRequestParameters {
+                source: content.source.clone(),
+                format: MediaFormat::File,
+            };
+            let result = match room.client().media().get_media_content(&request, true).await {
+                Ok(bytes) => {
+                    self.moderate_image_bytes(bytes, &file_name, Default::default())
+                        .await
+                }
+                Err(e) => Err(SafeCommsError::ApiError(format!(
+                    "Failed to download media: {}",
+                    e
+                ))),
+            };
+            AttachmentVerdict {
+                file_name: file_name.clone(),
+                result,
+            }
+        };
+
+        let (caption, image) = futures_util::join!(caption, image);
+        Ok(MessageVerdict::new(caption?, vec![image]))
+    }
+}
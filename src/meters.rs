@@ -0,0 +1,75 @@
+//! Metrics emitted through the `metrics` facade, for whichever recorder the
+//! application has installed.
+
+use crate::TransportError;
+use crate::endpoints::route;
+use metrics::{counter, gauge, histogram};
+use reqwest::Response;
+use std::time::Duration;
+
+/// Counts requests waiting out the client's shared backoff before a retry
+/// for as long as it is alive.
+pub(crate) struct Queued;
+
+impl Queued {
+    pub(crate) fn start() -> Self {
+        gauge!("safecomms_queue_depth").increment(1.0);
+        Queued
+    }
+}
+
+impl Drop for Queued {
+    fn drop(&mut self) {
+        gauge!("safecomms_queue_depth").decrement(1.0);
+    }
+}
+
+/// Counts requests currently awaiting a response for as long as it is alive,
+/// so cancelled requests are not left counted.
+pub(crate) struct InFlight;
+
+impl InFlight {
+    pub(crate) fn start() -> Self {
+        gauge!("safecomms_requests_in_flight").increment(1.0);
+        InFlight
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        gauge!("safecomms_requests_in_flight").decrement(1.0);
+    }
+}
+
+pub(crate) fn record_response(
+    path: &str,
+    response: Result<&Response, &TransportError>,
+    latency: Duration,
+) {
+    let endpoint = route(path);
+    let status = match response {
+        Ok(response) => response.status().as_u16().to_string(),
+        Err(_) => "error".to_string(),
+    };
+
+    counter!("safecomms_requests_total", "endpoint" => endpoint.to_string(), "status" => status)
+        .increment(1);
+    histogram!("safecomms_request_duration_seconds", "endpoint" => endpoint.to_string())
+        .record(latency);
+}
+
+/// Called by the endpoint pool when a request is retried against another
+/// endpoint.
+pub(crate) fn record_retry() {
+    counter!("safecomms_retries_total").increment(1);
+}
+
+/// Called by a [`Deduper`](crate::Deduper) for each text it fingerprinted,
+/// with whether a near-duplicate's verdict was reused.
+pub(crate) fn record_cache_lookup(hit: bool) {
+    if hit {
+        counter!("safecomms_cache_hits_total").increment(1);
+    } else {
+        counter!("safecomms_cache_misses_total").increment(1);
+    }
+}
@@ -0,0 +1,172 @@
+//! Server-side middleware that moderates fields of incoming JSON request
+//! bodies before they reach your handlers.
+
+use crate::{ModerationResponse, SafeCommsClient, SafeCommsError};
+use futures_util::{StreamExt, TryStreamExt, stream};
+use serde_json::Value;
+
+#[cfg(feature = "actix")]
+mod actix;
+#[cfg(feature = "axum")]
+mod axum;
+
+#[cfg(feature = "actix")]
+pub use self::actix::{BodyModerationMiddleware, BodyModerationService};
+#[cfg(feature = "axum")]
+pub use self::axum::{BodyModeration, BodyModerationLayer};
+
+const DEFAULT_BODY_LIMIT: usize = 1024 * 1024;
+const DEFAULT_CONCURRENCY: usize = 8;
+
+/// What to do with a request whose content is not clean.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnsafeContentAction {
+    /// Reject the request with `422 Unprocessable Entity`.
+    #[default]
+    Reject,
+    /// Replace the offending fields with the API's safe content and let the
+    /// request through.
+    Rewrite,
+}
+
+pub(crate) enum Verdict {
+    Allow,
+    /// The body is not valid JSON, so it cannot be moderated.
+    Invalid,
+    Rewritten(Vec<u8>),
+    Reject { field: String, result: Box<ModerationResponse> },
+}
+
+/// The framework-independent part of the middleware.
+#[derive(Clone)]
+pub(crate) struct BodyModerator {
+    client: SafeCommsClient,
+    fields: Vec<String>,
+    action: UnsafeContentAction,
+    body_limit: usize,
+    /// How many fields of one body may be awaiting a verdict at once.
+    concurrency: usize,
+    fail_open: bool,
+}
+
+impl BodyModerator {
+    pub(crate) fn new(client: SafeCommsClient) -> Self {
+        Self {
+            client,
+            fields: Vec::new(),
+            action: UnsafeContentAction::default(),
+            body_limit: DEFAULT_BODY_LIMIT,
+            concurrency: DEFAULT_CONCURRENCY,
+            fail_open: false,
+        }
+    }
+
+    pub(crate) async fn moderate(&self, body: &[u8]) -> Result<Verdict, SafeCommsError> {
+        let Ok(mut json) = serde_json::from_slice::<Value>(body) else {
+            return Ok(Verdict::Invalid);
+        };
+
+        let mut pointers = Vec::new();
+        for field in &self.fields {
+            let segments: Vec<&str> = field.split('.').collect();
+            collect_pointers(&json, &segments, String::new(), &mut pointers);
+        }
+
+        let replace = (self.action == UnsafeContentAction::Rewrite).then_some(true);
+        // Blank text has nothing to moderate, and the client rejects it.
+        let texts: Vec<(String, String)> = pointers
+            .into_iter()
+            .filter_map(|pointer| {
+                let text = json.pointer(&pointer)?.as_str()?;
+                (!text.trim().is_empty()).then(|| (pointer, text.to_string()))
+            })
+            .collect();
+        let results: Vec<_> = stream::iter(texts)
+            .map(|(pointer, text)| async move {
+                let result = self
+                    .client
+                    .moderate_text(&text, None, replace, None, None, None)
+                    .await?;
+                Ok::<_, SafeCommsError>((pointer, result))
+            })
+            .buffered(self.concurrency)
+            .try_collect()
+            .await?;
+
+        let mut rewritten = false;
+        for (pointer, result) in results {
+            if result.is_clean {
+                continue;
+            }
+
+            match (self.action, &result.safe_content) {
+                (UnsafeContentAction::Rewrite, Some(safe_content)) => {
+                    if let Some(value) = json.pointer_mut(&pointer) {
+                        *value = Value::String(safe_content.clone());
+                        rewritten = true;
+                    }
+                }
+                _ => {
+                    return Ok(Verdict::Reject {
+                        field: pointer,
+                        result: Box::new(result),
+                    });
+                }
+            }
+        }
+
+        if rewritten {
+            Ok(Verdict::Rewritten(serde_json::to_vec(&json)?))
+        } else {
+            Ok(Verdict::Allow)
+        }
+    }
+}
+
+/// Whether a `Content-Type` names JSON: `application/json` or any
+/// `+json` type such as `application/merge-patch+json`, ignoring case and
+/// parameters.
+pub(crate) fn is_json_content_type(content_type: &str) -> bool {
+    let media_type = content_type.split(';').next().unwrap_or_default().trim();
+    media_type.eq_ignore_ascii_case("application/json")
+        || media_type
+            .get(media_type.len().saturating_sub(5)..)
+            .is_some_and(|suffix| suffix.eq_ignore_ascii_case("+json"))
+}
+
+/// Resolves a dotted field path, where `*` matches every element of an array
+/// or every value of an object, into JSON pointers.
+fn collect_pointers(value: &Value, segments: &[&str], pointer: String, out: &mut Vec<String>) {
+    let Some((segment, rest)) = segments.split_first() else {
+        out.push(pointer);
+        return;
+    };
+
+    match (value, *segment) {
+        (Value::Array(items), "*") => {
+            for (index, item) in items.iter().enumerate() {
+                collect_pointers(item, rest, format!("{}/{}", pointer, index), out);
+            }
+        }
+        (Value::Object(map), "*") => {
+            for (key, item) in map {
+                collect_pointers(item, rest, format!("{}/{}", pointer, escape(key)), out);
+            }
+        }
+        (Value::Array(items), index) => {
+            if let Some(item) = index.parse::<usize>().ok().and_then(|i| items.get(i)) {
+                collect_pointers(item, rest, format!("{}/{}", pointer, index), out);
+            }
+        }
+        (Value::Object(map), key) => {
+            if let Some(item) = map.get(key) {
+                collect_pointers(item, rest, format!("{}/{}", pointer, escape(key)), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
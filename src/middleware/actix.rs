@@ -0,0 +1,173 @@
+use super::{BodyModerator, UnsafeContentAction, Verdict, is_json_content_type};
+use crate::{SafeCommsClient, SafeCommsError};
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform, forward_ready};
+use actix_web::http::header::{CONTENT_LENGTH, CONTENT_TYPE, HeaderValue};
+use actix_web::web::{Bytes, BytesMut};
+use actix_web::{Error, HttpMessage, HttpResponse};
+use futures_util::StreamExt;
+use futures_util::future::{LocalBoxFuture, Ready, ready};
+use serde_json::json;
+use std::rc::Rc;
+
+/// Moderates configured fields of JSON request bodies.
+///
+/// ```ignore
+/// App::new()
+///     .wrap(BodyModerationMiddleware::new(client).field("body").fail_open(true))
+///     .route("/comments", web::post().to(create_comment))
+/// ```
+#[derive(Clone)]
+pub struct BodyModerationMiddleware {
+    moderator: BodyModerator,
+}
+
+impl BodyModerationMiddleware {
+    pub fn new(client: SafeCommsClient) -> Self {
+        Self {
+            moderator: BodyModerator::new(client),
+        }
+    }
+
+    /// Adds a dotted path to a string field to moderate. `*` matches every
+    /// element of an array.
+    pub fn field(mut self, path: &str) -> Self {
+        self.moderator.fields.push(path.to_string());
+        self
+    }
+
+    pub fn action(mut self, action: UnsafeContentAction) -> Self {
+        self.moderator.action = action;
+        self
+    }
+
+    /// Largest body, in bytes, that will be buffered for moderation.
+    pub fn body_limit(mut self, limit: usize) -> Self {
+        self.moderator.body_limit = limit;
+        self
+    }
+
+    /// How many fields of one body may be awaiting a verdict at once, so a
+    /// body with a long array cannot set off a flood of API calls.
+    /// Defaults to 8.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.moderator.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Whether to let requests through unmoderated when the API cannot be
+    /// reached. Defaults to failing closed with `503 Service Unavailable`.
+    /// Fields the client refuses to send, such as text over the configured
+    /// length limit, are always rejected with `422 Unprocessable Entity`.
+    pub fn fail_open(mut self, fail_open: bool) -> Self {
+        self.moderator.fail_open = fail_open;
+        self
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for BodyModerationMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = BodyModerationService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(BodyModerationService {
+            service: Rc::new(service),
+            moderator: self.moderator.clone(),
+        }))
+    }
+}
+
+pub struct BodyModerationService<S> {
+    service: Rc<S>,
+    moderator: BodyModerator,
+}
+
+impl<S, B> Service<ServiceRequest> for BodyModerationService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut request: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let moderator = self.moderator.clone();
+
+        Box::pin(async move {
+            if !is_json(&request) {
+                return Ok(service.call(request).await?.map_into_left_body());
+            }
+
+            let mut payload = request.take_payload();
+            let mut bytes = BytesMut::new();
+            while let Some(chunk) = payload.next().await {
+                let chunk = chunk?;
+                if bytes.len() + chunk.len() > moderator.body_limit {
+                    let response = HttpResponse::PayloadTooLarge().finish();
+                    return Ok(request.into_response(response).map_into_right_body());
+                }
+                bytes.extend_from_slice(&chunk);
+            }
+            let bytes = bytes.freeze();
+
+            match moderator.moderate(&bytes).await {
+                Ok(Verdict::Allow) => request.set_payload(Payload::from(bytes)),
+                Ok(Verdict::Invalid) => {
+                    let response = HttpResponse::BadRequest()
+                        .json(json!({ "error": "Request body is not valid JSON" }));
+                    return Ok(request.into_response(response).map_into_right_body());
+                }
+                Ok(Verdict::Rewritten(body)) => {
+                    request
+                        .headers_mut()
+                        .insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+                    request.set_payload(Payload::from(Bytes::from(body)));
+                }
+                Ok(Verdict::Reject { field, result }) => {
+                    let response = HttpResponse::UnprocessableEntity().json(json!({
+                        "error": "Content rejected by moderation",
+                        "field": field,
+                        "severity": result.severity,
+                        "reason": result.reason,
+                    }));
+                    return Ok(request.into_response(response).map_into_right_body());
+                }
+                Err(e) if e.is_transient() && moderator.fail_open => {
+                    request.set_payload(Payload::from(bytes))
+                }
+                Err(SafeCommsError::InvalidInput { reason, .. }) => {
+                    let response = HttpResponse::UnprocessableEntity().json(json!({
+                        "error": "Content cannot be moderated",
+                        "reason": reason,
+                    }));
+                    return Ok(request.into_response(response).map_into_right_body());
+                }
+                Err(_) => {
+                    let response = HttpResponse::ServiceUnavailable().finish();
+                    return Ok(request.into_response(response).map_into_right_body());
+                }
+            }
+
+            Ok(service.call(request).await?.map_into_left_body())
+        })
+    }
+}
+
+fn is_json(request: &ServiceRequest) -> bool {
+    request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(is_json_content_type)
+}
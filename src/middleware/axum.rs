@@ -0,0 +1,166 @@
+use super::{BodyModerator, UnsafeContentAction, Verdict, is_json_content_type};
+use crate::{SafeCommsClient, SafeCommsError};
+use axum::body::{Body, to_bytes};
+use axum::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use axum::http::{HeaderValue, Request, StatusCode};
+use axum::response::Response;
+use futures_util::future::BoxFuture;
+use serde_json::json;
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Moderates configured fields of JSON request bodies.
+///
+/// ```ignore
+/// let app = Router::new()
+///     .route("/comments", post(create_comment))
+///     .layer(BodyModerationLayer::new(client).field("body").field("replies.*.body"));
+/// ```
+#[derive(Clone)]
+pub struct BodyModerationLayer {
+    moderator: BodyModerator,
+}
+
+impl BodyModerationLayer {
+    pub fn new(client: SafeCommsClient) -> Self {
+        Self {
+            moderator: BodyModerator::new(client),
+        }
+    }
+
+    /// Adds a dotted path to a string field to moderate. `*` matches every
+    /// element of an array.
+    pub fn field(mut self, path: &str) -> Self {
+        self.moderator.fields.push(path.to_string());
+        self
+    }
+
+    pub fn action(mut self, action: UnsafeContentAction) -> Self {
+        self.moderator.action = action;
+        self
+    }
+
+    /// Largest body, in bytes, that will be buffered for moderation.
+    pub fn body_limit(mut self, limit: usize) -> Self {
+        self.moderator.body_limit = limit;
+        self
+    }
+
+    /// How many fields of one body may be awaiting a verdict at once, so a
+    /// body with a long array cannot set off a flood of API calls.
+    /// Defaults to 8.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.moderator.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Whether to let requests through unmoderated when the API cannot be
+    /// reached. Defaults to failing closed with `503 Service Unavailable`.
+    /// Fields the client refuses to send, such as text over the configured
+    /// length limit, are always rejected with `422 Unprocessable Entity`.
+    pub fn fail_open(mut self, fail_open: bool) -> Self {
+        self.moderator.fail_open = fail_open;
+        self
+    }
+}
+
+impl<S> Layer<S> for BodyModerationLayer {
+    type Service = BodyModeration<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BodyModeration {
+            inner,
+            moderator: self.moderator.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct BodyModeration<S> {
+    inner: S,
+    moderator: BodyModerator,
+}
+
+impl<S> Service<Request<Body>> for BodyModeration<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let moderator = self.moderator.clone();
+
+        Box::pin(async move {
+            if !is_json(&request) {
+                return inner.call(request).await;
+            }
+
+            let (mut parts, body) = request.into_parts();
+            let Ok(bytes) = to_bytes(body, moderator.body_limit).await else {
+                return Ok(status(StatusCode::PAYLOAD_TOO_LARGE));
+            };
+
+            let body = match moderator.moderate(&bytes).await {
+                Ok(Verdict::Allow) => Body::from(bytes),
+                Ok(Verdict::Invalid) => {
+                    let body = json!({ "error": "Request body is not valid JSON" });
+                    return Ok(json_response(StatusCode::BAD_REQUEST, &body));
+                }
+                Ok(Verdict::Rewritten(body)) => {
+                    parts.headers.insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+                    Body::from(body)
+                }
+                Ok(Verdict::Reject { field, result }) => {
+                    let body = json!({
+                        "error": "Content rejected by moderation",
+                        "field": field,
+                        "severity": result.severity,
+                        "reason": result.reason,
+                    });
+                    return Ok(json_response(StatusCode::UNPROCESSABLE_ENTITY, &body));
+                }
+                Err(e) if e.is_transient() && moderator.fail_open => Body::from(bytes),
+                Err(SafeCommsError::InvalidInput { reason, .. }) => {
+                    let body = json!({ "error": "Content cannot be moderated", "reason": reason });
+                    return Ok(json_response(StatusCode::UNPROCESSABLE_ENTITY, &body));
+                }
+                Err(_) => return Ok(status(StatusCode::SERVICE_UNAVAILABLE)),
+            };
+
+            inner.call(Request::from_parts(parts, body)).await
+        })
+    }
+}
+
+fn is_json(request: &Request<Body>) -> bool {
+    request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(is_json_content_type)
+}
+
+fn status(status: StatusCode) -> Response {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+fn json_response(status: StatusCode, body: &serde_json::Value) -> Response {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
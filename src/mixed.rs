@@ -0,0 +1,101 @@
+use crate::{
+    AttachmentVerdict, EmbedVerdict, ImageModerationRequest, MessageVerdict, SafeCommsClient,
+    SafeCommsError,
+};
+use futures_util::future::join_all;
+
+/// An image attached to a [`MixedMessage`].
+#[derive(Debug, Clone)]
+pub enum MessageAttachment {
+    /// A link the API fetches the image from.
+    Url(String),
+    /// An image that has already been downloaded.
+    Bytes { file_name: String, bytes: Vec<u8> },
+}
+
+impl MessageAttachment {
+    fn name(&self) -> &str {
+        match self {
+            MessageAttachment::Url(url) => url,
+            MessageAttachment::Bytes { file_name, .. } => file_name,
+        }
+    }
+}
+
+/// A chat message made of several parts, as on Discord: text, image
+/// attachments and embeds such as link previews.
+#[derive(Debug, Clone, Default)]
+pub struct MixedMessage {
+    pub text: Option<String>,
+    pub attachments: Vec<MessageAttachment>,
+    /// The text of each embed, e.g. a preview's title and description.
+    pub embeds: Vec<String>,
+}
+
+impl SafeCommsClient {
+    /// Moderates every part of a message concurrently: the text and each
+    /// embed as text, each attachment as an image, by link or by upload.
+    ///
+    /// A failure to moderate the text fails the whole call, while attachment
+    /// and embed failures are reported per part and raise the verdict's
+    /// action to at least [`SuggestedAction::Delete`](crate::SuggestedAction).
+    /// Blank text and embeds are skipped.
+    pub async fn moderate_message(
+        &self,
+        message: &MixedMessage,
+    ) -> Result<MessageVerdict, SafeCommsError> {
+        let text = async {
+            match message.text.as_deref().filter(|text| !text.trim().is_empty()) {
+                Some(text) => self
+                    .moderate_text(text, None, None, None, None, None)
+                    .await
+                    .map(Some),
+                None => Ok(None),
+            }
+        };
+
+        let attachments = join_all(
+            message
+                .attachments
+                .iter()
+                .map(|attachment| self.moderate_attachment(attachment)),
+        );
+
+        let embeds = join_all(
+            message
+                .embeds
+                .iter()
+                .filter(|embed| !embed.trim().is_empty())
+                .map(|embed| async move {
+                    EmbedVerdict {
+                        text: embed.clone(),
+                        result: self.moderate_text(embed, None, None, None, None, None).await,
+                    }
+                }),
+        );
+
+        let (text, attachments, embeds) = futures_util::join!(text, attachments, embeds);
+        Ok(MessageVerdict::new(text?, attachments).with_embeds(embeds))
+    }
+
+    async fn moderate_attachment(&self, attachment: &MessageAttachment) -> AttachmentVerdict {
+        let result = match attachment {
+            MessageAttachment::Url(url) => {
+                self.moderate_image(ImageModerationRequest {
+                    image: url,
+                    ..ImageModerationRequest::default()
+                })
+                .await
+            }
+            MessageAttachment::Bytes { file_name, bytes } => {
+                self.moderate_image_bytes(bytes.clone(), file_name, Default::default())
+                    .await
+            }
+        };
+
+        AttachmentVerdict {
+            file_name: attachment.name().to_string(),
+            result,
+        }
+    }
+}
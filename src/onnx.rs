@@ -0,0 +1,184 @@
+use crate::{ImageUploadOptions, ModerationResponse, SafeCommsClient, SafeCommsError};
+use image::imageops::FilterType;
+use std::path::Path;
+use tract_onnx::prelude::*;
+
+const DEFAULT_SAFE_BELOW: f32 = 0.2;
+const DEFAULT_UNSAFE_ABOVE: f32 = 0.9;
+
+/// How a model expects its input image laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TensorLayout {
+    /// `[1, height, width, 3]`, as exported from Keras.
+    Nhwc,
+    /// `[1, 3, height, width]`, as exported from PyTorch.
+    Nchw,
+}
+
+/// Describes an NSFW classifier's inputs and outputs. The defaults match the
+/// widely used Keras model from GantMan/nsfw_model, whose classes are
+/// drawings, hentai, neutral, porn and sexy.
+#[derive(Debug, Clone)]
+pub struct ModelSpec {
+    /// Width and height the image is resized to.
+    pub input_size: u32,
+    pub layout: TensorLayout,
+    /// Pixels are scaled to `0..=1`, then normalized as `(x - mean) / std`.
+    pub mean: f32,
+    pub std: f32,
+    /// Output classes whose probabilities add up to the NSFW score.
+    pub nsfw_classes: Vec<usize>,
+    /// Whether the model outputs logits that still need a softmax.
+    pub softmax: bool,
+}
+
+impl Default for ModelSpec {
+    fn default() -> Self {
+        Self {
+            input_size: 224,
+            layout: TensorLayout::Nhwc,
+            mean: 0.0,
+            std: 1.0,
+            nsfw_classes: vec![1, 3, 4],
+            softmax: false,
+        }
+    }
+}
+
+/// Which path produced a [`PrescreenResult`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecidedBy {
+    /// The local model was confident enough on its own.
+    LocalModel,
+    /// The image was borderline and was sent to the API.
+    Api,
+}
+
+#[derive(Debug)]
+pub struct PrescreenResult {
+    pub decided_by: DecidedBy,
+    pub is_clean: bool,
+    /// The local model's NSFW probability.
+    pub nsfw_score: f32,
+    /// The API's verdict, when it was consulted.
+    pub response: Option<ModerationResponse>,
+}
+
+/// Screens images with a local ONNX NSFW classifier, and only sends the
+/// borderline ones to the API.
+#[derive(Clone)]
+pub struct ImagePrescreen {
+    model: Arc<TypedRunnableModel>,
+    spec: ModelSpec,
+    safe_below: f32,
+    unsafe_above: f32,
+}
+
+impl ImagePrescreen {
+    pub fn load(path: impl AsRef<Path>, spec: ModelSpec) -> Result<Self, SafeCommsError> {
+        let size = spec.input_size as usize;
+        let shape = match spec.layout {
+            TensorLayout::Nhwc => [1, size, size, 3],
+            TensorLayout::Nchw => [1, 3, size, size],
+        };
+
+        let model = tract_onnx::onnx()
+            .model_for_path(path)?
+            .with_input_fact(0, f32::fact(shape).into())?
+            .into_optimized()?
+            .into_runnable()?;
+
+        Ok(Self {
+            model,
+            spec,
+            safe_below: DEFAULT_SAFE_BELOW,
+            unsafe_above: DEFAULT_UNSAFE_ABOVE,
+        })
+    }
+
+    /// Images scoring below `safe_below` are passed and images scoring above
+    /// `unsafe_above` are rejected without calling the API.
+    pub fn thresholds(mut self, safe_below: f32, unsafe_above: f32) -> Self {
+        self.safe_below = safe_below;
+        self.unsafe_above = unsafe_above;
+        self
+    }
+
+    /// The local model's NSFW probability for an encoded image.
+    pub fn score(&self, image: &[u8]) -> Result<f32, SafeCommsError> {
+        let size = self.spec.input_size;
+        let pixels = image::load_from_memory(image)?
+            .resize_exact(size, size, FilterType::Triangle)
+            .to_rgb8();
+
+        let normalize = |channel: u8| (channel as f32 / 255.0 - self.spec.mean) / self.spec.std;
+        let size = size as usize;
+        let input: Tensor = match self.spec.layout {
+            TensorLayout::Nhwc => tract_ndarray::Array4::from_shape_fn(
+                (1, size, size, 3),
+                |(_, y, x, c)| normalize(pixels.get_pixel(x as u32, y as u32)[c]),
+            )
+            .into(),
+            TensorLayout::Nchw => tract_ndarray::Array4::from_shape_fn(
+                (1, 3, size, size),
+                |(_, c, y, x)| normalize(pixels.get_pixel(x as u32, y as u32)[c]),
+            )
+            .into(),
+        };
+
+        let outputs = self.model.run(tvec!(input.into()))?;
+        let mut scores: Vec<f32> =
+            outputs[0].to_plain_array_view::<f32>()?.iter().copied().collect();
+        if self.spec.softmax {
+            let max = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            scores.iter_mut().for_each(|score| *score = (*score - max).exp());
+            let total: f32 = scores.iter().sum();
+            scores.iter_mut().for_each(|score| *score /= total);
+        }
+
+        Ok(self
+            .spec
+            .nsfw_classes
+            .iter()
+            .filter_map(|&class| scores.get(class))
+            .sum())
+    }
+
+    /// Scores `image` locally, and uploads it to the API only if the score
+    /// falls between the thresholds.
+    pub async fn moderate(
+        &self,
+        client: &SafeCommsClient,
+        image: Vec<u8>,
+        file_name: &str,
+        options: ImageUploadOptions<'_>,
+    ) -> Result<PrescreenResult, SafeCommsError> {
+        // Inference is CPU-bound, so keep it off the async workers.
+        let (image, nsfw_score) = {
+            let prescreen = self.clone();
+            tokio::task::spawn_blocking(move || {
+                let score = prescreen.score(&image);
+                score.map(|score| (image, score))
+            })
+            .await
+            .map_err(|e| SafeCommsError::ApiError(format!("Image prescreen failed: {}", e)))??
+        };
+
+        if nsfw_score < self.safe_below || nsfw_score > self.unsafe_above {
+            return Ok(PrescreenResult {
+                decided_by: DecidedBy::LocalModel,
+                is_clean: nsfw_score < self.safe_below,
+                nsfw_score,
+                response: None,
+            });
+        }
+
+        let response = client.moderate_image_bytes(image, file_name, options).await?;
+        Ok(PrescreenResult {
+            decided_by: DecidedBy::Api,
+            is_clean: response.is_clean,
+            nsfw_score,
+            response: Some(response),
+        })
+    }
+}
@@ -0,0 +1,127 @@
+use crate::provider::severity_for_score;
+use crate::{
+    DEFAULT_ERROR_BODY_LIMIT, ModerationProvider, ModerationResponse, SafeCommsError,
+    error_for_status, local_response,
+};
+use futures_util::future::BoxFuture;
+use reqwest::Client as HttpClient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+const DEFAULT_MODEL: &str = "omni-moderation-latest";
+
+#[derive(Serialize)]
+struct ModerationRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ModerationsResponse {
+    results: Vec<ModerationResult>,
+}
+
+#[derive(Deserialize)]
+struct ModerationResult {
+    flagged: bool,
+    categories: HashMap<String, bool>,
+    category_scores: HashMap<String, f64>,
+}
+
+/// The OpenAI moderation API as a [`ModerationProvider`].
+///
+/// Content is clean unless OpenAI flags it; the reason lists the flagged
+/// categories. The language is ignored, as the API detects it itself.
+#[derive(Clone)]
+pub struct OpenAiModeration {
+    client: HttpClient,
+    api_key: String,
+    base_url: String,
+    model: String,
+}
+
+impl OpenAiModeration {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            client: HttpClient::new(),
+            api_key,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            model: DEFAULT_MODEL.to_string(),
+        }
+    }
+
+    /// Defaults to `omni-moderation-latest`.
+    pub fn model(mut self, model: &str) -> Self {
+        self.model = model.to_string();
+        self
+    }
+
+    /// Sends requests somewhere other than `https://api.openai.com/v1`, e.g.
+    /// a compatible gateway.
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    async fn moderate(&self, content: &str) -> Result<ModerationResponse, SafeCommsError> {
+        let request = ModerationRequest {
+            model: &self.model,
+            input: content,
+        };
+        let response = self
+            .client
+            .post(format!("{}/moderations", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&request)
+            .send()
+            .await?;
+        let response: ModerationsResponse = error_for_status(response, DEFAULT_ERROR_BODY_LIMIT).await?.json().await?;
+        let result = response.results.into_iter().next().ok_or_else(|| {
+            SafeCommsError::ApiError("OpenAI returned no moderation result".to_string())
+        })?;
+        Ok(to_response(result))
+    }
+}
+
+impl ModerationProvider for OpenAiModeration {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn moderate_text<'a>(
+        &'a self,
+        content: &'a str,
+        _language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<ModerationResponse, SafeCommsError>> {
+        Box::pin(self.moderate(content))
+    }
+}
+
+fn to_response(result: ModerationResult) -> ModerationResponse {
+    let mut flagged: Vec<&str> = result
+        .categories
+        .iter()
+        .filter(|(_, flagged)| **flagged)
+        .map(|(category, _)| category.as_str())
+        .collect();
+    flagged.sort_unstable();
+    let reason = result
+        .flagged
+        .then(|| format!("Flagged by OpenAI moderation: {}", flagged.join(", ")));
+    let severity = result.flagged.then(|| {
+        let highest = result.category_scores.values().copied().fold(0.0, f64::max);
+        severity_for_score(highest).to_string()
+    });
+    let category_scores = result
+        .category_scores
+        .into_iter()
+        .map(|(category, score)| (category, score.to_string()))
+        .collect();
+
+    ModerationResponse {
+        severity,
+        category_scores: Some(category_scores),
+        ..local_response(!result.flagged, reason, None)
+    }
+}
@@ -0,0 +1,106 @@
+//! OpenTelemetry context propagation and metrics for outgoing requests.
+//!
+//! Metrics are recorded with the global meter provider, which must be
+//! installed before the first request is made.
+
+use crate::TransportError;
+use crate::endpoints::route;
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::propagation::Injector;
+use opentelemetry::{Context, KeyValue, global};
+use reqwest::Response;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const METER_NAME: &str = "safecomms";
+const TOKENS_USED_HEADER: &str = "X-SafeComms-Tokens-Used";
+
+struct Instruments {
+    requests: Counter<u64>,
+    errors: Counter<u64>,
+    duration: Histogram<f64>,
+    tokens: Counter<u64>,
+}
+
+fn instruments() -> &'static Instruments {
+    static INSTRUMENTS: OnceLock<Instruments> = OnceLock::new();
+    INSTRUMENTS.get_or_init(|| {
+        let meter = global::meter(METER_NAME);
+        Instruments {
+            requests: meter
+                .u64_counter("safecomms.client.requests")
+                .with_description("Requests sent to the SafeComms API")
+                .build(),
+            errors: meter
+                .u64_counter("safecomms.client.errors")
+                .with_description("Requests that failed or returned an error status")
+                .build(),
+            duration: meter
+                .f64_histogram("safecomms.client.duration")
+                .with_description("Time taken by requests to the SafeComms API")
+                .with_unit("s")
+                .build(),
+            tokens: meter
+                .u64_counter("safecomms.client.tokens")
+                .with_description("Tokens consumed, as reported by the API")
+                .build(),
+        }
+    })
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+/// The trace context headers for the current OpenTelemetry context, as
+/// produced by the global propagator.
+pub(crate) fn context_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&Context::current(), &mut HeaderInjector(&mut headers))
+    });
+    headers
+}
+
+pub(crate) fn record_response(
+    path: &str,
+    response: Result<&Response, &TransportError>,
+    latency: Duration,
+) {
+    let instruments = instruments();
+    let status = match response {
+        Ok(response) => response.status().as_u16().to_string(),
+        Err(_) => "error".to_string(),
+    };
+    let attributes = [
+        KeyValue::new("endpoint", route(path).to_string()),
+        KeyValue::new("status", status),
+    ];
+
+    instruments.requests.add(1, &attributes);
+    instruments.duration.record(latency.as_secs_f64(), &attributes);
+
+    match response {
+        Ok(response) if response.status().is_success() => {
+            if let Some(tokens) = response
+                .headers()
+                .get(TOKENS_USED_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+            {
+                instruments.tokens.add(tokens, &attributes[..1]);
+            }
+        }
+        _ => instruments.errors.add(1, &attributes),
+    }
+}
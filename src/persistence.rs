@@ -0,0 +1,132 @@
+use crate::{Metadata, ModerationResponse, SafeCommsError};
+use futures_util::future::BoxFuture;
+use sha2::{Digest, Sha256};
+use std::time::{Duration, SystemTime};
+
+/// One moderation call, as recorded by a [`ResultStore`].
+#[derive(Debug, Clone)]
+pub struct ModerationRecord {
+    /// Hex SHA-256 of the request body, so content can be matched without
+    /// being stored.
+    pub request_hash: String,
+    /// The API path that was called, e.g. `/moderation/text`.
+    pub endpoint: String,
+    pub is_clean: bool,
+    pub severity: Option<String>,
+    pub latency: Duration,
+    pub created_at: SystemTime,
+    /// The metadata the request was tagged with. Not written by
+    /// `SqlxResultStore`.
+    pub metadata: Metadata,
+}
+
+/// Somewhere to keep an audit trail of moderation results. Attach one with
+/// [`SafeCommsClientBuilder::result_store`](crate::SafeCommsClientBuilder::result_store)
+/// and every moderation call made through the client is recorded.
+///
+/// A failure to record is logged and does not fail the moderation call.
+pub trait ResultStore: Send + Sync {
+    fn record<'a>(
+        &'a self,
+        record: &'a ModerationRecord,
+    ) -> BoxFuture<'a, Result<(), SafeCommsError>>;
+}
+
+/// A [`ResultStore`] that writes to the `safecomms_results` table of a sqlx
+/// pool. Call `create_table` once to set up the schema.
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub struct SqlxResultStore<DB: sqlx::Database> {
+    pool: sqlx::Pool<DB>,
+}
+
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+impl<DB: sqlx::Database> SqlxResultStore<DB> {
+    pub fn new(pool: sqlx::Pool<DB>) -> Self {
+        Self { pool }
+    }
+}
+
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+macro_rules! impl_sqlx_result_store {
+    ($database:ty, $id_column:literal) => {
+        impl SqlxResultStore<$database> {
+            /// Creates the results table if it does not exist yet.
+            pub async fn create_table(&self) -> Result<(), SafeCommsError> {
+                sqlx::query(concat!(
+                    "CREATE TABLE IF NOT EXISTS safecomms_results (
+                        id ",
+                    $id_column,
+                    ",
+                        request_hash TEXT NOT NULL,
+                        endpoint TEXT NOT NULL,
+                        is_clean BOOLEAN NOT NULL,
+                        severity TEXT,
+                        latency_ms BIGINT NOT NULL,
+                        created_at BIGINT NOT NULL
+                    )"
+                ))
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            }
+        }
+
+        impl ResultStore for SqlxResultStore<$database> {
+            fn record<'a>(
+                &'a self,
+                record: &'a ModerationRecord,
+            ) -> BoxFuture<'a, Result<(), SafeCommsError>> {
+                Box::pin(async move {
+                    sqlx::query(
+                        "INSERT INTO safecomms_results
+                            (request_hash, endpoint, is_clean, severity, latency_ms, created_at)
+                        VALUES ($1, $2, $3, $4, $5, $6)",
+                    )
+                    .bind(&record.request_hash)
+                    .bind(&record.endpoint)
+                    .bind(record.is_clean)
+                    .bind(&record.severity)
+                    .bind(i64::try_from(record.latency.as_millis()).unwrap_or(i64::MAX))
+                    .bind(
+                        record
+                            .created_at
+                            .duration_since(std::time::UNIX_EPOCH)
+                            .map_or(0, |since| since.as_secs() as i64),
+                    )
+                    .execute(&self.pool)
+                    .await?;
+                    Ok(())
+                })
+            }
+        }
+    };
+}
+
+#[cfg(feature = "postgres")]
+impl_sqlx_result_store!(sqlx::Postgres, "BIGSERIAL PRIMARY KEY");
+#[cfg(feature = "sqlite")]
+impl_sqlx_result_store!(sqlx::Sqlite, "INTEGER PRIMARY KEY AUTOINCREMENT");
+
+/// Records a finished moderation call, logging rather than returning any
+/// error so that auditing never fails the call itself.
+pub(crate) async fn record(
+    store: &dyn ResultStore,
+    endpoint: &str,
+    request: &[u8],
+    latency: Duration,
+    result: &ModerationResponse,
+) {
+    let record = ModerationRecord {
+        request_hash: hex::encode(Sha256::digest(request)),
+        endpoint: endpoint.to_string(),
+        is_clean: result.is_clean,
+        severity: result.severity.clone(),
+        latency,
+        created_at: SystemTime::now(),
+        metadata: result.metadata.clone(),
+    };
+
+    if let Err(e) = store.record(&record).await {
+        log::warn!("Failed to record moderation result: {}", e);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::provider::severity_for_score;
+use crate::{
+    DEFAULT_ERROR_BODY_LIMIT, ModerationProvider, ModerationResponse, SafeCommsError,
+    error_for_status, local_response,
+};
+use futures_util::future::BoxFuture;
+use reqwest::Client as HttpClient;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+
+const DEFAULT_BASE_URL: &str = "https://commentanalyzer.googleapis.com/v1alpha1";
+const DEFAULT_ATTRIBUTES: [&str; 6] = [
+    "TOXICITY",
+    "SEVERE_TOXICITY",
+    "IDENTITY_ATTACK",
+    "INSULT",
+    "PROFANITY",
+    "THREAT",
+];
+/// Perspective's recommended threshold for moderation use.
+const DEFAULT_THRESHOLD: f64 = 0.7;
+
+#[derive(Deserialize)]
+struct AnalyzeResponse {
+    #[serde(rename = "attributeScores", default)]
+    attribute_scores: HashMap<String, AttributeScore>,
+}
+
+#[derive(Deserialize)]
+struct AttributeScore {
+    #[serde(rename = "summaryScore")]
+    summary_score: SummaryScore,
+}
+
+#[derive(Deserialize)]
+struct SummaryScore {
+    value: f64,
+}
+
+/// Google's Perspective API as a [`ModerationProvider`].
+///
+/// Content is flagged when any requested attribute scores at least the
+/// threshold. Categories are the attribute names, lowercased.
+#[derive(Clone)]
+pub struct PerspectiveModeration {
+    client: HttpClient,
+    api_key: String,
+    base_url: String,
+    attributes: Vec<String>,
+    threshold: f64,
+}
+
+impl PerspectiveModeration {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            client: HttpClient::new(),
+            api_key,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            attributes: DEFAULT_ATTRIBUTES.iter().map(|a| a.to_string()).collect(),
+            threshold: DEFAULT_THRESHOLD,
+        }
+    }
+
+    /// The attributes to request, e.g. `TOXICITY`. Defaults to the
+    /// production attributes.
+    pub fn attributes(mut self, attributes: &[&str]) -> Self {
+        self.attributes = attributes.iter().map(|a| a.to_string()).collect();
+        self
+    }
+
+    /// The score at which content is flagged. Defaults to 0.7.
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    async fn moderate(
+        &self,
+        content: &str,
+        language: Option<&str>,
+    ) -> Result<ModerationResponse, SafeCommsError> {
+        let attributes: Map<String, Value> =
+            self.attributes.iter().map(|a| (a.clone(), json!({}))).collect();
+        let mut request = json!({
+            "comment": { "text": content },
+            "requestedAttributes": attributes,
+            "doNotStore": true,
+        });
+        if let Some(language) = language {
+            request["languages"] = json!([language]);
+        }
+
+        let response = self
+            .client
+            .post(format!("{}/comments:analyze", self.base_url))
+            .query(&[("key", &self.api_key)])
+            .json(&request)
+            .send()
+            .await?;
+        let response: AnalyzeResponse = error_for_status(response, DEFAULT_ERROR_BODY_LIMIT).await?.json().await?;
+        Ok(self.to_response(response))
+    }
+
+    fn to_response(&self, response: AnalyzeResponse) -> ModerationResponse {
+        let scores: Vec<(String, f64)> = response
+            .attribute_scores
+            .into_iter()
+            .map(|(attribute, score)| (attribute.to_lowercase(), score.summary_score.value))
+            .collect();
+        let mut flagged: Vec<&str> = scores
+            .iter()
+            .filter(|(_, score)| *score >= self.threshold)
+            .map(|(category, _)| category.as_str())
+            .collect();
+        flagged.sort_unstable();
+
+        let is_clean = flagged.is_empty();
+        let reason =
+            (!is_clean).then(|| format!("Flagged by Perspective: {}", flagged.join(", ")));
+        let severity = (!is_clean).then(|| {
+            let highest = scores.iter().map(|(_, score)| *score).fold(0.0, f64::max);
+            severity_for_score(highest).to_string()
+        });
+        let category_scores = scores
+            .into_iter()
+            .map(|(category, score)| (category, score.to_string()))
+            .collect();
+
+        ModerationResponse {
+            severity,
+            category_scores: Some(category_scores),
+            ..local_response(is_clean, reason, None)
+        }
+    }
+}
+
+impl ModerationProvider for PerspectiveModeration {
+    fn name(&self) -> &str {
+        "perspective"
+    }
+
+    fn moderate_text<'a>(
+        &'a self,
+        content: &'a str,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<ModerationResponse, SafeCommsError>> {
+        Box::pin(self.moderate(content, language))
+    }
+}
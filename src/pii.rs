@@ -0,0 +1,204 @@
+//! Local redaction of personal data, for content that must not leave the
+//! organization unredacted.
+
+use crate::{Language, ModerationResponse, ReplacementStyle, SafeCommsClient, SafeCommsError};
+use regex::Regex;
+
+const EMAIL_PATTERN: &str = r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b";
+const PHONE_PATTERN: &str = r"\+?\(?\d[\d\s().-]{6,}\d";
+const IBAN_PATTERN: &str = r"\b[A-Z]{2}\d{2}(?:\s?[A-Z0-9]{4}){2,7}(?:\s?[A-Z0-9]{1,4})?\b";
+const CREDIT_CARD_PATTERN: &str = r"\b\d(?:[ -]?\d){12,18}\b";
+const MIN_PHONE_DIGITS: usize = 8;
+
+/// A kind of personal data.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PiiKind {
+    Email,
+    Phone,
+    Iban,
+    CreditCard,
+    /// Matched by a pattern added with [`PiiRedactor::custom`].
+    Custom(String),
+}
+
+impl PiiKind {
+    /// The tag used by [`ReplacementStyle::Tag`], e.g. `EMAIL`.
+    pub fn tag(&self) -> String {
+        match self {
+            PiiKind::Email => "EMAIL".to_string(),
+            PiiKind::Phone => "PHONE".to_string(),
+            PiiKind::Iban => "IBAN".to_string(),
+            PiiKind::CreditCard => "CREDIT_CARD".to_string(),
+            PiiKind::Custom(name) => name.to_uppercase(),
+        }
+    }
+}
+
+/// Personal data found in a text, as a byte range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiiMatch {
+    pub kind: PiiKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Text with its personal data masked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redacted {
+    pub text: String,
+    /// What was redacted, with ranges into the original text.
+    pub matches: Vec<PiiMatch>,
+}
+
+/// Finds and redacts emails, phone numbers, IBANs, credit card numbers and
+/// any custom patterns. IBANs and card numbers are checksummed, so numbers
+/// that merely look like them are not reported as such, although long digit
+/// runs may still be redacted as phone numbers.
+#[derive(Debug, Clone)]
+pub struct PiiRedactor {
+    patterns: Vec<(PiiKind, Regex)>,
+    style: ReplacementStyle,
+}
+
+impl PiiRedactor {
+    pub fn new() -> Self {
+        let builtin = [
+            (PiiKind::Email, EMAIL_PATTERN),
+            (PiiKind::Iban, IBAN_PATTERN),
+            (PiiKind::CreditCard, CREDIT_CARD_PATTERN),
+            (PiiKind::Phone, PHONE_PATTERN),
+        ];
+
+        Self {
+            patterns: builtin
+                .into_iter()
+                .map(|(kind, pattern)| {
+                    (kind, Regex::new(pattern).expect("built-in pattern is valid"))
+                })
+                .collect(),
+            style: ReplacementStyle::Tag,
+        }
+    }
+
+    /// How matches are masked.
+    pub fn style(mut self, style: ReplacementStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Also redacts matches of `pattern`, tagging them as `NAME`.
+    pub fn custom(mut self, name: &str, pattern: &str) -> Result<Self, regex::Error> {
+        self.patterns.push((PiiKind::Custom(name.to_string()), Regex::new(pattern)?));
+        Ok(self)
+    }
+
+    /// Personal data in `text`, in order. Where matches overlap, the one
+    /// starting first wins.
+    pub fn find(&self, text: &str) -> Vec<PiiMatch> {
+        let mut matches: Vec<PiiMatch> = self
+            .patterns
+            .iter()
+            .flat_map(|(kind, regex)| {
+                regex
+                    .find_iter(text)
+                    .filter(move |found| is_valid(kind, found.as_str()))
+                    .map(move |found| PiiMatch {
+                        kind: kind.clone(),
+                        start: found.start(),
+                        end: found.end(),
+                    })
+            })
+            .collect();
+
+        matches.sort_by_key(|found| (found.start, std::cmp::Reverse(found.end)));
+        let mut end = 0;
+        matches.retain(|found| {
+            let keep = found.start >= end;
+            if keep {
+                end = found.end;
+            }
+            keep
+        });
+        matches
+    }
+
+    pub fn redact(&self, text: &str) -> Redacted {
+        let matches = self.find(text);
+        let mut redacted = String::with_capacity(text.len());
+        let mut copied = 0;
+
+        for found in &matches {
+            redacted.push_str(&text[copied..found.start]);
+            redacted.push_str(&self.style.mask(&text[found.start..found.end], &found.kind.tag()));
+            copied = found.end;
+        }
+        redacted.push_str(&text[copied..]);
+
+        Redacted {
+            text: redacted,
+            matches,
+        }
+    }
+
+    /// Redacts `content` locally and moderates what is left, so personal data
+    /// never reaches the API.
+    pub async fn moderate(
+        &self,
+        client: &SafeCommsClient,
+        content: &str,
+        language: Option<&str>,
+    ) -> Result<ModerationResponse, SafeCommsError> {
+        let redacted = self.redact(content);
+        client
+            .moderate_text(&redacted.text, language.map(Language::from), None, None, None, None)
+            .await
+    }
+}
+
+impl Default for PiiRedactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_valid(kind: &PiiKind, found: &str) -> bool {
+    match kind {
+        PiiKind::Phone => found.chars().filter(char::is_ascii_digit).count() >= MIN_PHONE_DIGITS,
+        PiiKind::Iban => iban_checksum_ok(found),
+        PiiKind::CreditCard => luhn_ok(found),
+        PiiKind::Email | PiiKind::Custom(_) => true,
+    }
+}
+
+fn luhn_ok(number: &str) -> bool {
+    let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| match (i % 2 == 1, digit * 2) {
+            (true, doubled) if doubled > 9 => doubled - 9,
+            (true, doubled) => doubled,
+            (false, _) => digit,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// The ISO 13616 mod-97 check.
+fn iban_checksum_ok(iban: &str) -> bool {
+    let iban: String = iban.chars().filter(|c| !c.is_whitespace()).collect();
+    if iban.len() < 15 {
+        return false;
+    }
+
+    let (head, tail) = iban.split_at(4);
+    tail.chars()
+        .chain(head.chars())
+        .try_fold(0u32, |remainder, c| {
+            let value = c.to_digit(36)?;
+            let shift = if value >= 10 { 100 } else { 10 };
+            Some((remainder * shift + value) % 97)
+        })
+        == Some(1)
+}
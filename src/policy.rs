@@ -0,0 +1,267 @@
+use crate::{ModerationIssue, ModerationResponse, SafeCommsError, Severity};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+#[cfg(feature = "async")]
+use std::path::PathBuf;
+#[cfg(feature = "async")]
+use std::sync::{Arc, RwLock, Weak};
+#[cfg(feature = "async")]
+use std::time::{Duration, SystemTime};
+
+/// What to do with content, from least to most restrictive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Allow,
+    /// Let the content through, but mark it for later inspection.
+    Flag,
+    /// Hold the content until a human has looked at it.
+    Review,
+    Block,
+}
+
+/// A score above which a category triggers an action.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Threshold {
+    pub score: f64,
+    pub action: Action,
+}
+
+/// Turns moderation responses into actions.
+///
+/// Every rule that applies to a response proposes an action, and the most
+/// restrictive one wins. The default policy flags low and medium severity
+/// content, blocks high and critical severity content and bypass attempts,
+/// and sends anything else the API found unsafe to review.
+///
+/// Policies can also be loaded from a file, where omitted fields keep their
+/// defaults:
+///
+/// ```toml
+/// bypass = "review"
+///
+/// [severities]
+/// low = "allow"
+/// medium = "review"
+///
+/// [categories]
+/// sexual = [{ score = 0.5, action = "flag" }, { score = 0.8, action = "block" }]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    severities: HashMap<Severity, Action>,
+    #[serde(deserialize_with = "lowercase_keys")]
+    categories: HashMap<String, Vec<Threshold>>,
+    bypass: Action,
+    unrated: Action,
+}
+
+impl Policy {
+    /// A policy with no severity rules, which reviews everything the API
+    /// found unsafe.
+    pub fn new() -> Self {
+        Self {
+            severities: HashMap::new(),
+            categories: HashMap::new(),
+            bypass: Action::Block,
+            unrated: Action::Review,
+        }
+    }
+
+    /// The action for unsafe content of `severity`.
+    pub fn severity(mut self, severity: Severity, action: Action) -> Self {
+        self.severities.insert(severity, action);
+        self
+    }
+
+    /// Takes `action` when `category` scores above `score`, whether or not
+    /// the API found the content unsafe. A category may have several
+    /// thresholds.
+    pub fn category(mut self, category: &str, score: f64, action: Action) -> Self {
+        self.categories
+            .entry(category.to_lowercase())
+            .or_default()
+            .push(Threshold { score, action });
+        self
+    }
+
+    /// The action for content the API detected as an attempt to evade
+    /// moderation.
+    pub fn bypass(mut self, action: Action) -> Self {
+        self.bypass = action;
+        self
+    }
+
+    /// The action for unsafe content that no other rule covers, e.g. because
+    /// the API reported no severity.
+    pub fn unrated(mut self, action: Action) -> Self {
+        self.unrated = action;
+        self
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, SafeCommsError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> Result<Self, SafeCommsError> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    /// Loads a policy file, read as TOML if its extension is `.toml` and as
+    /// JSON otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SafeCommsError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| SafeCommsError::ApiError(format!("Failed to read policy: {}", e)))?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::from_toml(&contents),
+            #[cfg(not(feature = "toml"))]
+            Some("toml") => Err(SafeCommsError::ApiError(
+                "TOML policies require the toml feature".to_string(),
+            )),
+            _ => Self::from_json(&contents),
+        }
+    }
+
+    pub fn evaluate(&self, response: &ModerationResponse) -> Action {
+        let severity = response
+            .severity_level()
+            .filter(|_| !response.is_clean)
+            .and_then(|severity| self.severities.get(&severity).copied());
+        let bypass = response.is_bypass_attempt.then_some(self.bypass);
+        let categories = response
+            .category_scores
+            .iter()
+            .flatten()
+            .filter_map(|(category, score)| {
+                let thresholds = self.categories.get(&category.to_lowercase())?;
+                let score: f64 = score.parse().ok()?;
+                thresholds
+                    .iter()
+                    .filter(|threshold| score > threshold.score)
+                    .map(|threshold| threshold.action)
+                    .max()
+            })
+            .max();
+
+        match severity.into_iter().chain(bypass).chain(categories).max() {
+            Some(action) => action,
+            None if response.is_clean => Action::Allow,
+            None => self.unrated,
+        }
+    }
+
+    /// The issues `response` found, if this policy blocks the content;
+    /// otherwise none.
+    pub fn blocking_issues<'r>(&self, response: &'r ModerationResponse) -> &'r [ModerationIssue] {
+        if self.evaluate(response) < Action::Block {
+            return &[];
+        }
+        response.issues.as_deref().unwrap_or_default()
+    }
+}
+
+/// Policy checks as methods on [`ModerationResponse`], which lives in
+/// `safecomms-types` and so cannot have inherent methods that take a
+/// [`Policy`].
+pub trait ModerationResponseExt {
+    /// The issues this response found, if `policy` blocks the content;
+    /// otherwise none.
+    fn blocking_issues(&self, policy: &Policy) -> &[ModerationIssue];
+}
+
+impl ModerationResponseExt for ModerationResponse {
+    fn blocking_issues(&self, policy: &Policy) -> &[ModerationIssue] {
+        policy.blocking_issues(self)
+    }
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self::new()
+            .severity(Severity::Low, Action::Flag)
+            .severity(Severity::Medium, Action::Flag)
+            .severity(Severity::High, Action::Block)
+            .severity(Severity::Critical, Action::Block)
+    }
+}
+
+fn lowercase_keys<'de, D>(deserializer: D) -> Result<HashMap<String, Vec<Threshold>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let categories = HashMap::<String, Vec<Threshold>>::deserialize(deserializer)?;
+    Ok(categories
+        .into_iter()
+        .map(|(category, thresholds)| (category.to_lowercase(), thresholds))
+        .collect())
+}
+
+/// A policy file that is reloaded whenever it changes, so thresholds can be
+/// tuned without redeploying.
+///
+/// The file is checked for changes on a background task. If a changed file
+/// fails to load, the error is logged and the previous policy stays in
+/// effect. The task stops once every clone of the watcher has been dropped.
+#[cfg(feature = "async")]
+#[derive(Debug, Clone)]
+pub struct PolicyWatcher {
+    current: Arc<RwLock<Arc<Policy>>>,
+}
+
+#[cfg(feature = "async")]
+impl PolicyWatcher {
+    /// Loads the policy at `path` and checks it for changes every `interval`.
+    /// Must be called from within a Tokio runtime.
+    pub fn watch(path: impl Into<PathBuf>, interval: Duration) -> Result<Self, SafeCommsError> {
+        let path = path.into();
+        let policy = Policy::load(&path)?;
+        let current = Arc::new(RwLock::new(Arc::new(policy)));
+
+        tokio::spawn(reload(path, interval, Arc::downgrade(&current)));
+        Ok(Self { current })
+    }
+
+    /// The policy currently in effect.
+    pub fn policy(&self) -> Arc<Policy> {
+        self.current.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn evaluate(&self, response: &ModerationResponse) -> Action {
+        self.policy().evaluate(response)
+    }
+}
+
+#[cfg(feature = "async")]
+async fn reload(path: PathBuf, interval: Duration, current: Weak<RwLock<Arc<Policy>>>) {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut last_modified: Option<SystemTime> = modified(&path);
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+        let Some(current) = current.upgrade() else {
+            return;
+        };
+
+        let now_modified = modified(&path);
+        if now_modified == last_modified {
+            continue;
+        }
+        last_modified = now_modified;
+
+        match Policy::load(&path) {
+            Ok(policy) => {
+                *current.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(policy);
+                log::info!("Reloaded moderation policy from {}", path.display());
+            }
+            Err(e) => log::warn!("Keeping previous moderation policy: {}", e),
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! An offline word list that settles obvious cases without calling the API.
+//!
+//! Text containing a blocked word or phrase is rejected immediately, and text
+//! that consists entirely of an allowed phrase (a greeting, say) is accepted
+//! immediately. Everything else is ambiguous and is sent to the API.
+
+use crate::normalize::canonicalize;
+use crate::{
+    Language, ModerationIssue, ModerationResponse, SafeCommsClient, SafeCommsError, local_response,
+};
+
+const EN_BLOCK: &[&str] = &["fuck", "fucking", "motherfucker", "cunt", "shit", "bitch", "asshole"];
+const EN_ALLOW: &[&str] = &[
+    "hi", "hello", "hey", "thanks", "thank you", "ok", "okay", "yes", "no", "lol", "gg",
+    "good morning", "good night",
+];
+const ES_BLOCK: &[&str] = &["puta", "puto", "mierda", "cabrón", "gilipollas", "coño"];
+const ES_ALLOW: &[&str] = &[
+    "hola", "gracias", "muchas gracias", "vale", "sí", "no", "buenos días",
+];
+const DE_BLOCK: &[&str] = &["scheiße", "scheisse", "arschloch", "fotze", "wichser", "hurensohn"];
+const DE_ALLOW: &[&str] = &["hallo", "danke", "danke schön", "ok", "ja", "nein", "guten morgen"];
+const FR_BLOCK: &[&str] = &["putain", "merde", "connard", "salope", "enculé"];
+const FR_ALLOW: &[&str] = &["salut", "bonjour", "merci", "merci beaucoup", "ok", "oui", "non"];
+
+/// What the word list made of a piece of text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrefilterDecision {
+    /// The text contains this blocked word or phrase.
+    Block(String),
+    /// The text is an allowed phrase.
+    Allow,
+    /// The word list has no opinion; ask the API.
+    Ambiguous,
+}
+
+/// A configurable block and allow list. Matching is on whole words of the
+/// [canonicalized](crate::normalize::canonicalize) text, so blocking
+/// "asshole" also blocks "А$$h0le" but blocking "ass" does not block "class".
+#[derive(Debug, Clone, Default)]
+pub struct Prefilter {
+    block: Vec<Vec<String>>,
+    allow: Vec<Vec<String>>,
+}
+
+impl Prefilter {
+    /// An empty prefilter, which finds everything ambiguous.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A prefilter loaded with the default lists shipped for `language`
+    /// (`"en"`, `"es"`, `"de"` or `"fr"`). Other languages get empty lists.
+    pub fn for_language(language: &str) -> Self {
+        let (block, allow) = match language {
+            "en" => (EN_BLOCK, EN_ALLOW),
+            "es" => (ES_BLOCK, ES_ALLOW),
+            "de" => (DE_BLOCK, DE_ALLOW),
+            "fr" => (FR_BLOCK, FR_ALLOW),
+            _ => (&[][..], &[][..]),
+        };
+        Self::new().block(block.iter().copied()).allow(allow.iter().copied())
+    }
+
+    /// Adds words or phrases that block any text containing them.
+    pub fn block<I>(mut self, phrases: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.block.extend(phrases.into_iter().map(|phrase| words(phrase.as_ref())));
+        self
+    }
+
+    /// Adds phrases that are allowed when they make up the whole text.
+    pub fn allow<I>(mut self, phrases: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.allow.extend(phrases.into_iter().map(|phrase| words(phrase.as_ref())));
+        self
+    }
+
+    pub fn check(&self, text: &str) -> PrefilterDecision {
+        let text = words(text);
+
+        let blocked = self.block.iter().find(|phrase| {
+            !phrase.is_empty() && text.windows(phrase.len()).any(|window| window == *phrase)
+        });
+        if let Some(phrase) = blocked {
+            return PrefilterDecision::Block(phrase.join(" "));
+        }
+
+        if !text.is_empty() && self.allow.contains(&text) {
+            return PrefilterDecision::Allow;
+        }
+
+        PrefilterDecision::Ambiguous
+    }
+
+    /// The response reported for text that matched the block list.
+    pub(crate) fn blocked_response(term: String) -> ModerationResponse {
+        local_response(
+            false,
+            Some("Matched the local block list".to_string()),
+            Some(vec![ModerationIssue {
+                term: Some(term),
+                context: None,
+            }]),
+        )
+    }
+
+    /// Moderates `content`, only calling the API when the word list finds it
+    /// ambiguous. Decisions made locally are reported as a response with no
+    /// severity and, for blocks, the matched phrase as the only issue.
+    pub async fn moderate(
+        &self,
+        client: &SafeCommsClient,
+        content: &str,
+        language: Option<&str>,
+    ) -> Result<ModerationResponse, SafeCommsError> {
+        match self.check(content) {
+            PrefilterDecision::Block(term) => Ok(Self::blocked_response(term)),
+            PrefilterDecision::Allow => Ok(local_response(true, None, None)),
+            PrefilterDecision::Ambiguous => {
+                client
+                    .moderate_text(content, language.map(Language::from), None, None, None, None)
+                    .await
+            }
+        }
+    }
+}
+
+/// Canonical words, ignoring punctuation other than apostrophes.
+fn words(text: &str) -> Vec<String> {
+    canonicalize(text)
+        .split(|c: char| !(c.is_alphanumeric() || c == '\''))
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
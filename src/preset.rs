@@ -0,0 +1,100 @@
+use crate::{
+    Action, ModerationRequest, ModerationResponse, Policy, ReplaceSeverity, SafeCommsClient,
+    SafeCommsError, Severity,
+};
+use serde::{Deserialize, Serialize};
+
+/// Request options and a policy for a common kind of community, as a
+/// starting point for new integrations.
+///
+/// - `GamingChat` tolerates banter and masks swearing, but blocks hate and
+///   sexual content.
+/// - `KidsPlatform` blocks anything medium severity or above, masks
+///   everything it can and holds personal data back.
+/// - `ProfessionalForum` leaves posts as written and sends borderline ones to
+///   review rather than blocking them.
+/// - `Marketplace` masks contact details, so deals stay on the platform, and
+///   blocks scams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Preset {
+    GamingChat,
+    KidsPlatform,
+    ProfessionalForum,
+    Marketplace,
+}
+
+impl Preset {
+    /// The policy that turns responses into actions.
+    pub fn policy(self) -> Policy {
+        match self {
+            Preset::GamingChat => Policy::new()
+                .severity(Severity::Low, Action::Allow)
+                .severity(Severity::Medium, Action::Flag)
+                .severity(Severity::High, Action::Block)
+                .severity(Severity::Critical, Action::Block)
+                .category("hate", 0.8, Action::Block)
+                .category("sexual", 0.8, Action::Block)
+                .category("self_harm", 0.5, Action::Review)
+                .bypass(Action::Review)
+                .unrated(Action::Flag),
+            Preset::KidsPlatform => Policy::new()
+                .severity(Severity::Low, Action::Review)
+                .severity(Severity::Medium, Action::Block)
+                .severity(Severity::High, Action::Block)
+                .severity(Severity::Critical, Action::Block)
+                .category("sexual", 0.3, Action::Block)
+                .category("violence", 0.5, Action::Block)
+                .category("self_harm", 0.3, Action::Review)
+                .bypass(Action::Block)
+                .unrated(Action::Block),
+            Preset::ProfessionalForum => Policy::new()
+                .severity(Severity::Low, Action::Flag)
+                .severity(Severity::Medium, Action::Review)
+                .severity(Severity::High, Action::Block)
+                .severity(Severity::Critical, Action::Block)
+                .category("harassment", 0.7, Action::Review)
+                .category("hate", 0.7, Action::Block)
+                .category("spam", 0.8, Action::Review)
+                .bypass(Action::Review)
+                .unrated(Action::Review),
+            Preset::Marketplace => Policy::new()
+                .severity(Severity::Low, Action::Allow)
+                .severity(Severity::Medium, Action::Flag)
+                .severity(Severity::High, Action::Block)
+                .severity(Severity::Critical, Action::Block)
+                .category("scam", 0.6, Action::Block)
+                .category("spam", 0.7, Action::Review)
+                .bypass(Action::Block)
+                .unrated(Action::Review),
+        }
+    }
+
+    /// A text moderation request for `content` with the preset's options.
+    pub fn request(self, content: impl Into<String>) -> ModerationRequest {
+        let (replace, pii, replace_severity) = match self {
+            Preset::GamingChat => (true, true, Some(Severity::Medium)),
+            Preset::KidsPlatform => (true, true, Some(Severity::Low)),
+            Preset::ProfessionalForum => (false, false, None),
+            Preset::Marketplace => (true, true, Some(Severity::High)),
+        };
+        ModerationRequest {
+            content: content.into(),
+            replace: Some(replace),
+            pii: Some(pii),
+            replace_severity: replace_severity.map(ReplaceSeverity::from),
+            ..ModerationRequest::default()
+        }
+    }
+
+    /// Moderates `content` with the preset's options, and returns the action
+    /// its policy takes along with the response.
+    pub async fn moderate(
+        self,
+        client: &SafeCommsClient,
+        content: &str,
+    ) -> Result<(Action, ModerationResponse), SafeCommsError> {
+        let response = client.moderate_request(&self.request(content)).await?;
+        Ok((self.policy().evaluate(&response), response))
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{REQUEST_ID_HEADER, RequestId};
+use reqwest::{Response, StatusCode};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// An RFC 7807 problem returned by the API for a failed request.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ProblemDetails {
+    /// The HTTP status, taken from the response if the problem leaves it
+    /// out.
+    pub status: Option<u16>,
+    /// A URI identifying the kind of problem.
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub title: Option<String>,
+    pub detail: Option<String>,
+    /// Validation messages per request field, e.g. `content`.
+    #[serde(default)]
+    pub errors: HashMap<String, Vec<String>>,
+}
+
+impl ProblemDetails {
+    /// The messages for `field`, ignoring case.
+    pub fn field_errors(&self, field: &str) -> &[String] {
+        self.errors
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(field))
+            .map_or(&[], |(_, messages)| messages.as_slice())
+    }
+
+    /// The most specific description of the problem available.
+    pub fn message(&self) -> String {
+        match (&self.detail, &self.title, self.status) {
+            (Some(detail), _, _) => detail.clone(),
+            (None, Some(title), _) => title.clone(),
+            (None, None, Some(status)) => status.to_string(),
+            (None, None, None) => "Unknown problem".to_string(),
+        }
+    }
+}
+
+/// A non-success response from the API.
+#[derive(Debug, Clone)]
+pub struct ResponseError {
+    pub status: StatusCode,
+    /// The response body, cut to the client's
+    /// [`error_body_limit`](crate::SafeCommsClientBuilder::error_body_limit).
+    pub body: String,
+    /// The body parsed as a problem, if it is one.
+    pub problem: Option<ProblemDetails>,
+    /// The API's ID for the request, if it sent one.
+    pub request_id: Option<RequestId>,
+}
+
+impl ResponseError {
+    /// Reads the body of a failed `response`, keeping at most `limit` bytes
+    /// of it.
+    pub(crate) async fn read(response: Response, limit: usize) -> Result<Self, reqwest::Error> {
+        let status = response.status();
+        let request_id = response
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(RequestId::from);
+        let mut body = response.text().await?;
+
+        let problem = serde_json::from_str::<ProblemDetails>(&body)
+            .ok()
+            .map(|mut problem| {
+                problem.status.get_or_insert(status.as_u16());
+                problem
+            });
+        if body.len() > limit {
+            body.truncate(body.floor_char_boundary(limit));
+        }
+
+        Ok(Self {
+            status,
+            body,
+            problem,
+            request_id,
+        })
+    }
+}
+
+impl fmt::Display for ResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.problem {
+            Some(problem) => f.write_str(&problem.message()),
+            None => write!(f, "{} - {}", self.status, self.body),
+        }
+    }
+}
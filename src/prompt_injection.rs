@@ -0,0 +1,41 @@
+use crate::{ProfileId, PromptInjectionResponse, SafeCommsClient, SafeCommsError};
+use reqwest::Method;
+use serde::Serialize;
+
+const PROMPT_INJECTION_PATH: &str = "/moderation/prompt-injection";
+
+#[derive(Serialize)]
+struct PromptInjectionRequest<'a> {
+    content: &'a str,
+    #[serde(rename = "moderationProfileId", skip_serializing_if = "Option::is_none")]
+    moderation_profile_id: Option<&'a ProfileId>,
+}
+
+impl SafeCommsClient {
+    /// Checks user input destined for an LLM for prompt-injection and
+    /// jailbreak attempts.
+    pub async fn detect_prompt_injection(
+        &self,
+        content: &str,
+        moderation_profile_id: Option<&ProfileId>,
+    ) -> Result<PromptInjectionResponse, SafeCommsError> {
+        self.limits.text("content", content)?;
+        let request = PromptInjectionRequest {
+            content,
+            moderation_profile_id: moderation_profile_id.or_else(|| {
+                self.tenant
+                    .as_ref()
+                    .and_then(|tenant| tenant.moderation_profile_id.as_ref())
+            }),
+        };
+
+        let payload = self.wire_format.encode(&request)?;
+        let response: PromptInjectionResponse = self
+            .send(Method::POST, PROMPT_INJECTION_PATH, |builder| payload.attach(builder))
+            .await?;
+        if let Some(tenant) = &self.tenant {
+            tenant.record(PROMPT_INJECTION_PATH, response.is_injection);
+        }
+        Ok(response)
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{Language, ModerationResponse, ProfileId, SafeCommsClient, SafeCommsError};
+use futures_util::future::BoxFuture;
+
+/// A source of text moderation verdicts, so call sites can switch between
+/// SafeComms and other moderation APIs, or combine them.
+///
+/// Verdicts from every provider are expressed as a [`ModerationResponse`].
+/// Category scores keep the provider's own category names; providers that do
+/// not report a severity get one from their highest score.
+pub trait ModerationProvider: Send + Sync {
+    /// A short name for the provider, e.g. `safecomms`.
+    fn name(&self) -> &str;
+
+    fn moderate_text<'a>(
+        &'a self,
+        content: &'a str,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<ModerationResponse, SafeCommsError>>;
+}
+
+impl ModerationProvider for SafeCommsClient {
+    fn name(&self) -> &str {
+        "safecomms"
+    }
+
+    fn moderate_text<'a>(
+        &'a self,
+        content: &'a str,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<ModerationResponse, SafeCommsError>> {
+        Box::pin(SafeCommsClient::moderate_text(
+            self,
+            content,
+            language.map(Language::from),
+            None,
+            None,
+            None,
+            None,
+        ))
+    }
+}
+
+/// A severity for a provider that only reports scores, from the highest
+/// score of flagged content.
+#[cfg(any(feature = "openai", feature = "perspective"))]
+pub(crate) fn severity_for_score(score: f64) -> &'static str {
+    match score {
+        score if score >= 0.9 => "Critical",
+        score if score >= 0.75 => "High",
+        score if score >= 0.5 => "Medium",
+        _ => "Low",
+    }
+}
+
+/// SafeComms with a specific moderation profile, e.g. to fan out to several
+/// profiles in an [`Ensemble`](crate::Ensemble).
+#[derive(Clone)]
+pub struct SafeCommsProfile {
+    client: SafeCommsClient,
+    profile_id: ProfileId,
+    name: String,
+}
+
+impl SafeCommsProfile {
+    /// Named `safecomms:<profile_id>`.
+    pub fn new(client: SafeCommsClient, profile_id: impl Into<ProfileId>) -> Self {
+        let profile_id = profile_id.into();
+        Self {
+            client,
+            name: format!("safecomms:{}", profile_id),
+            profile_id,
+        }
+    }
+}
+
+impl ModerationProvider for SafeCommsProfile {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn moderate_text<'a>(
+        &'a self,
+        content: &'a str,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<ModerationResponse, SafeCommsError>> {
+        Box::pin(self.client.moderate_text(
+            content,
+            language.map(Language::from),
+            None,
+            None,
+            None,
+            Some(&self.profile_id),
+        ))
+    }
+}
@@ -0,0 +1,75 @@
+use reqwest::NoProxy;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    All,
+    Http,
+    Https,
+}
+
+/// A proxy to send API requests through; see
+/// [`SafeCommsClientBuilder::proxy`](crate::SafeCommsClientBuilder::proxy).
+///
+/// The URL may use the `http`, `https`, `socks5` or `socks5h` scheme. SOCKS
+/// proxies need the `socks` feature.
+#[derive(Debug, Clone)]
+pub struct Proxy {
+    url: String,
+    scope: Scope,
+    credentials: Option<(String, String)>,
+    no_proxy: Option<String>,
+}
+
+impl Proxy {
+    /// Proxies all requests.
+    pub fn all(url: &str) -> Self {
+        Self::new(url, Scope::All)
+    }
+
+    /// Proxies plain HTTP requests only.
+    pub fn http(url: &str) -> Self {
+        Self::new(url, Scope::Http)
+    }
+
+    /// Proxies HTTPS requests only.
+    pub fn https(url: &str) -> Self {
+        Self::new(url, Scope::Https)
+    }
+
+    fn new(url: &str, scope: Scope) -> Self {
+        Self {
+            url: url.to_string(),
+            scope,
+            credentials: None,
+            no_proxy: None,
+        }
+    }
+
+    /// Authenticates to the proxy with a username and password.
+    pub fn basic_auth(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// Hosts to connect to directly, in the comma-separated format of the
+    /// `NO_PROXY` environment variable.
+    pub fn no_proxy(mut self, hosts: &str) -> Self {
+        self.no_proxy = Some(hosts.to_string());
+        self
+    }
+
+    pub(crate) fn to_reqwest(&self) -> Result<reqwest::Proxy, reqwest::Error> {
+        let mut proxy = match self.scope {
+            Scope::All => reqwest::Proxy::all(&self.url)?,
+            Scope::Http => reqwest::Proxy::http(&self.url)?,
+            Scope::Https => reqwest::Proxy::https(&self.url)?,
+        };
+        if let Some((username, password)) = &self.credentials {
+            proxy = proxy.basic_auth(username, password);
+        }
+        if let Some(hosts) = &self.no_proxy {
+            proxy = proxy.no_proxy(NoProxy::from_string(hosts));
+        }
+        Ok(proxy)
+    }
+}
@@ -0,0 +1,119 @@
+use reqwest::{Method, Request, StatusCode};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+/// Fields whose values describe a request or verdict rather than the user's
+/// content, and are logged as they are. Every other string is redacted.
+const METADATA_FIELDS: &[&str] = &[
+    "category",
+    "enableOcr",
+    "enhancedOcr",
+    "extractMetadata",
+    "isBypassAttempt",
+    "isClean",
+    "isInjection",
+    "jobId",
+    "language",
+    "moderationProfileId",
+    "pii",
+    "replace",
+    "replaceSeverity",
+    "replacementStyle",
+    "score",
+    "severity",
+    "status",
+    "tier",
+];
+
+/// How user content appears in debug logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedactionPolicy {
+    /// Content is replaced by a short SHA-256 prefix, so repeated content can
+    /// still be correlated.
+    #[default]
+    Hash,
+    /// Content is cut to its first `n` characters.
+    Truncate(usize),
+    /// Only the length of the content is logged.
+    Omit,
+}
+
+impl RedactionPolicy {
+    fn redact(&self, content: &str) -> String {
+        match self {
+            RedactionPolicy::Hash => {
+                let digest = hex::encode(Sha256::digest(content.as_bytes()));
+                format!("sha256:{}", &digest[..12])
+            }
+            RedactionPolicy::Truncate(n) if content.chars().count() > *n => {
+                format!("{}…", content.chars().take(*n).collect::<String>())
+            }
+            RedactionPolicy::Truncate(_) => content.to_string(),
+            RedactionPolicy::Omit => format!("<{} bytes>", content.len()),
+        }
+    }
+
+    fn redact_value(&self, value: &Value) -> Value {
+        match value {
+            Value::String(content) => Value::String(self.redact(content)),
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|item| self.redact_value(item)).collect())
+            }
+            Value::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = if METADATA_FIELDS.contains(&key.as_str()) {
+                            value.clone()
+                        } else {
+                            self.redact_value(value)
+                        };
+                        (key.clone(), value)
+                    })
+                    .collect::<Map<_, _>>(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    fn summarize(&self, body: &[u8]) -> String {
+        match serde_json::from_slice::<Value>(body) {
+            Ok(value) => self.redact_value(&value).to_string(),
+            Err(_) => format!("<{} byte non-JSON body>", body.len()),
+        }
+    }
+}
+
+pub(crate) fn log_request(policy: RedactionPolicy, request: &Request) {
+    match request.body().and_then(|body| body.as_bytes()) {
+        Some(body) => log::debug!(
+            "SafeComms request: {} {} ({} bytes) {}",
+            request.method(),
+            request.url().path(),
+            body.len(),
+            policy.summarize(body)
+        ),
+        None => log::debug!(
+            "SafeComms request: {} {} (streamed body)",
+            request.method(),
+            request.url().path()
+        ),
+    }
+}
+
+pub(crate) fn log_response(
+    policy: RedactionPolicy,
+    method: &Method,
+    path: &str,
+    status: StatusCode,
+    body: &[u8],
+) {
+    log::debug!(
+        "SafeComms response: {} {} {} ({} bytes) {}",
+        method,
+        path,
+        status,
+        body.len(),
+        policy.summarize(body)
+    );
+}
@@ -0,0 +1,18 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Region {
+    Us,
+    Eu,
+    Asia,
+}
+
+impl Region {
+    pub const ALL: [Region; 3] = [Region::Us, Region::Eu, Region::Asia];
+
+    pub fn base_url(&self) -> &'static str {
+        match self {
+            Region::Us => "https://us.api.safecomms.dev",
+            Region::Eu => "https://eu.api.safecomms.dev",
+            Region::Asia => "https://asia.api.safecomms.dev",
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use crate::ModerationResponse;
+use crate::aggregate::Tally;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const CATEGORY_THRESHOLD: f64 = 0.5;
+const TOP_N: usize = 10;
+
+/// Aggregate statistics over a set of moderation results, for periodic
+/// moderation reports.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    #[serde(flatten)]
+    pub tally: Tally,
+    /// The share of results that were clean, if there were any.
+    pub clean_rate: Option<f64>,
+    /// The most frequent categories scoring at least 0.5, most frequent
+    /// first.
+    pub top_categories: Vec<(String, u64)>,
+    /// The most frequently flagged terms, lowercased, most frequent first.
+    pub top_terms: Vec<(String, u64)>,
+}
+
+impl Report {
+    pub fn from_results<'a>(results: impl IntoIterator<Item = &'a ModerationResponse>) -> Self {
+        let mut tally = Tally::default();
+        let mut terms: HashMap<String, u64> = HashMap::new();
+
+        for response in results {
+            tally.add(response, CATEGORY_THRESHOLD);
+            for term in response.issues.iter().flatten().filter_map(|i| i.term.as_deref()) {
+                *terms.entry(term.to_lowercase()).or_default() += 1;
+            }
+        }
+
+        let clean_rate = (tally.total > 0).then(|| tally.clean as f64 / tally.total as f64);
+        let top_categories = top(tally.categories.clone());
+        Self {
+            tally,
+            clean_rate,
+            top_categories,
+            top_terms: top(terms),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("reports serialize to JSON")
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from("# Moderation report\n\n");
+        let tally = &self.tally;
+        let _ = writeln!(markdown, "| | Count |\n|---|---:|");
+        let _ = writeln!(markdown, "| Total | {} |", tally.total);
+        let _ = writeln!(markdown, "| Clean | {} |", tally.clean);
+        let _ = writeln!(markdown, "| Flagged | {} |", tally.flagged);
+        let _ = writeln!(markdown, "| Bypass attempts | {} |", tally.bypass_attempts);
+        if let Some(rate) = self.clean_rate {
+            let _ = writeln!(markdown, "\nClean rate: {:.1}%", rate * 100.0);
+        }
+
+        let severities = tally.severities.iter().map(|(name, count)| (name.as_str(), *count));
+        let categories = self.top_categories.iter().map(|(name, count)| (name.as_str(), *count));
+        let terms = self.top_terms.iter().map(|(name, count)| (name.as_str(), *count));
+        table(&mut markdown, "Severity", "Severity", severities);
+        table(&mut markdown, "Top categories", "Category", categories);
+        table(&mut markdown, "Top flagged terms", "Term", terms);
+
+        markdown
+    }
+}
+
+fn table<'a>(
+    markdown: &mut String,
+    title: &str,
+    column: &str,
+    rows: impl ExactSizeIterator<Item = (&'a str, u64)>,
+) {
+    if rows.len() == 0 {
+        return;
+    }
+    let _ = writeln!(markdown, "\n## {}\n\n| {} | Count |\n|---|---:|", title, column);
+    for (name, count) in rows {
+        let _ = writeln!(markdown, "| {} | {} |", name.replace('|', "\\|"), count);
+    }
+}
+
+/// The `TOP_N` most frequent entries, most frequent first and then by name.
+fn top(counts: impl IntoIterator<Item = (String, u64)>) -> Vec<(String, u64)> {
+    let mut counts: Vec<(String, u64)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.truncate(TOP_N);
+    counts
+}
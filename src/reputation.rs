@@ -0,0 +1,324 @@
+//! Tracks how often each user has been caught, so repeat offenders can be
+//! escalated from warnings to mutes to bans.
+//!
+//! Every unsafe response adds strikes to its author, weighted by severity.
+//! Strikes decay exponentially, so users who behave recover their standing.
+
+use crate::{ModerationResponse, SafeCommsError, Severity};
+use futures_util::future::BoxFuture;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+const DEFAULT_HALF_LIFE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const DEFAULT_BYPASS_WEIGHT: f64 = 1.0;
+const DEFAULT_UNRATED_WEIGHT: f64 = 1.0;
+
+/// How to treat a user, from least to most restrictive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Tier {
+    Good,
+    Warn,
+    Mute,
+    Ban,
+}
+
+/// A user's strikes as of `updated_at`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StrikeRecord {
+    pub strikes: f64,
+    pub updated_at: SystemTime,
+}
+
+/// A user's current strikes and the tier they put the user in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Standing {
+    pub strikes: f64,
+    pub tier: Tier,
+    /// Whether the response just recorded moved the user to a worse tier.
+    pub escalated: bool,
+}
+
+/// Where strike counts are kept.
+pub trait StrikeStore: Send + Sync {
+    fn load<'a>(
+        &'a self,
+        user_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<StrikeRecord>, SafeCommsError>>;
+
+    fn save<'a>(
+        &'a self,
+        user_id: &'a str,
+        record: StrikeRecord,
+    ) -> BoxFuture<'a, Result<(), SafeCommsError>>;
+}
+
+/// Keeps strikes in memory, for a single process.
+#[derive(Debug, Default)]
+pub struct MemoryStrikeStore {
+    records: Mutex<HashMap<String, StrikeRecord>>,
+}
+
+impl MemoryStrikeStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StrikeStore for MemoryStrikeStore {
+    fn load<'a>(
+        &'a self,
+        user_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<StrikeRecord>, SafeCommsError>> {
+        let records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        let record = records.get(user_id).copied();
+        Box::pin(async move { Ok(record) })
+    }
+
+    fn save<'a>(
+        &'a self,
+        user_id: &'a str,
+        record: StrikeRecord,
+    ) -> BoxFuture<'a, Result<(), SafeCommsError>> {
+        self.records
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(user_id.to_string(), record);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Keeps strikes in the `safecomms_strikes` table of a SQLite pool. Call
+/// `create_table` once to set up the schema.
+#[cfg(feature = "sqlite")]
+pub struct SqliteStrikeStore {
+    pool: sqlx::SqlitePool,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStrikeStore {
+    pub fn new(pool: sqlx::SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Creates the strikes table if it does not exist yet.
+    pub async fn create_table(&self) -> Result<(), SafeCommsError> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS safecomms_strikes (
+                user_id TEXT PRIMARY KEY,
+                strikes REAL NOT NULL,
+                updated_at_ms BIGINT NOT NULL
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl StrikeStore for SqliteStrikeStore {
+    fn load<'a>(
+        &'a self,
+        user_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<StrikeRecord>, SafeCommsError>> {
+        Box::pin(async move {
+            let row: Option<(f64, i64)> = sqlx::query_as(
+                "SELECT strikes, updated_at_ms FROM safecomms_strikes WHERE user_id = $1",
+            )
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+            Ok(row.map(|(strikes, updated_at_ms)| StrikeRecord {
+                strikes,
+                updated_at: SystemTime::UNIX_EPOCH
+                    + Duration::from_millis(updated_at_ms.max(0) as u64),
+            }))
+        })
+    }
+
+    fn save<'a>(
+        &'a self,
+        user_id: &'a str,
+        record: StrikeRecord,
+    ) -> BoxFuture<'a, Result<(), SafeCommsError>> {
+        Box::pin(async move {
+            let updated_at_ms = record
+                .updated_at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |since| since.as_millis() as i64);
+
+            sqlx::query(
+                "INSERT INTO safecomms_strikes (user_id, strikes, updated_at_ms)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (user_id) DO UPDATE
+                    SET strikes = excluded.strikes, updated_at_ms = excluded.updated_at_ms",
+            )
+            .bind(user_id)
+            .bind(record.strikes)
+            .bind(updated_at_ms)
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        })
+    }
+}
+
+/// Maintains decaying strike counts per user and the tier they put each user
+/// in.
+///
+/// By default a low severity response is one strike, medium two, high three
+/// and critical five, with one more for a bypass attempt. Strikes halve
+/// every week, and users are warned from one strike, muted from three and
+/// banned from six.
+///
+/// Recording is a read followed by a write, so two responses recorded for
+/// the same user at the same moment may lose one of the updates.
+pub struct Reputation {
+    store: Box<dyn StrikeStore>,
+    half_life: Duration,
+    weights: HashMap<Severity, f64>,
+    bypass_weight: f64,
+    unrated_weight: f64,
+    tiers: Vec<(Tier, f64)>,
+}
+
+impl Reputation {
+    pub fn new(store: impl StrikeStore + 'static) -> Self {
+        Self {
+            store: Box::new(store),
+            half_life: DEFAULT_HALF_LIFE,
+            weights: HashMap::from([
+                (Severity::Low, 1.0),
+                (Severity::Medium, 2.0),
+                (Severity::High, 3.0),
+                (Severity::Critical, 5.0),
+            ]),
+            bypass_weight: DEFAULT_BYPASS_WEIGHT,
+            unrated_weight: DEFAULT_UNRATED_WEIGHT,
+            tiers: vec![(Tier::Warn, 1.0), (Tier::Mute, 3.0), (Tier::Ban, 6.0)],
+        }
+    }
+
+    /// How long it takes for strikes to halve.
+    pub fn half_life(mut self, half_life: Duration) -> Self {
+        self.half_life = half_life;
+        self
+    }
+
+    /// The strikes added by an unsafe response of `severity`.
+    pub fn weight(mut self, severity: Severity, strikes: f64) -> Self {
+        self.weights.insert(severity, strikes);
+        self
+    }
+
+    /// The strikes added on top for a bypass attempt.
+    pub fn bypass_weight(mut self, strikes: f64) -> Self {
+        self.bypass_weight = strikes;
+        self
+    }
+
+    /// The strikes added by an unsafe response without a known severity.
+    pub fn unrated_weight(mut self, strikes: f64) -> Self {
+        self.unrated_weight = strikes;
+        self
+    }
+
+    /// Puts users with at least `strikes` strikes in `tier`.
+    pub fn tier(mut self, tier: Tier, strikes: f64) -> Self {
+        self.tiers.retain(|(existing, _)| *existing != tier);
+        self.tiers.push((tier, strikes));
+        self
+    }
+
+    /// Adds the strikes `response` earns to `user_id`.
+    pub async fn record(
+        &self,
+        user_id: &str,
+        response: &ModerationResponse,
+    ) -> Result<Standing, SafeCommsError> {
+        let now = SystemTime::now();
+        let before = self.current(user_id, now).await?;
+        let strikes = before + self.strikes_for(response);
+
+        if strikes != before {
+            let record = StrikeRecord {
+                strikes,
+                updated_at: now,
+            };
+            self.store.save(user_id, record).await?;
+        }
+
+        let tier = self.tier_for(strikes);
+        Ok(Standing {
+            strikes,
+            tier,
+            escalated: tier > self.tier_for(before),
+        })
+    }
+
+    /// Adds the strikes `response` earns to the `user_id` in its metadata.
+    /// Responses without one are not recorded.
+    pub async fn record_tagged(
+        &self,
+        response: &ModerationResponse,
+    ) -> Result<Option<Standing>, SafeCommsError> {
+        match response.user_id() {
+            Some(user_id) => self.record(user_id, response).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The user's standing without recording anything.
+    pub async fn standing(&self, user_id: &str) -> Result<Standing, SafeCommsError> {
+        let strikes = self.current(user_id, SystemTime::now()).await?;
+        Ok(Standing {
+            strikes,
+            tier: self.tier_for(strikes),
+            escalated: false,
+        })
+    }
+
+    /// Clears the user's strikes, e.g. after a successful appeal.
+    pub async fn forgive(&self, user_id: &str) -> Result<(), SafeCommsError> {
+        let record = StrikeRecord {
+            strikes: 0.0,
+            updated_at: SystemTime::now(),
+        };
+        self.store.save(user_id, record).await
+    }
+
+    async fn current(&self, user_id: &str, now: SystemTime) -> Result<f64, SafeCommsError> {
+        let Some(record) = self.store.load(user_id).await? else {
+            return Ok(0.0);
+        };
+
+        let elapsed = now.duration_since(record.updated_at).unwrap_or_default();
+        let half_lives = elapsed.as_secs_f64() / self.half_life.as_secs_f64().max(f64::EPSILON);
+        Ok(record.strikes * 0.5f64.powf(half_lives))
+    }
+
+    fn strikes_for(&self, response: &ModerationResponse) -> f64 {
+        let mut strikes = 0.0;
+        if !response.is_clean {
+            strikes += response
+                .severity_level()
+                .and_then(|severity| self.weights.get(&severity).copied())
+                .unwrap_or(self.unrated_weight);
+        }
+        if response.is_bypass_attempt {
+            strikes += self.bypass_weight;
+        }
+        strikes
+    }
+
+    fn tier_for(&self, strikes: f64) -> Tier {
+        self.tiers
+            .iter()
+            .filter(|(_, threshold)| strikes >= *threshold)
+            .map(|(tier, _)| *tier)
+            .max()
+            .unwrap_or(Tier::Good)
+    }
+}
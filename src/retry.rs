@@ -0,0 +1,157 @@
+use crate::runtime;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Response, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const DEFAULT_BACKOFF_BASE: Duration = Duration::from_millis(200);
+const DEFAULT_BACKOFF_MAX: Duration = Duration::from_secs(10);
+
+/// A token bucket of retries, so that retries cannot multiply the load on an
+/// API that is already failing.
+///
+/// Every retry, including a failover to another endpoint, takes a token;
+/// once the bucket is empty, failed requests fail instead of retrying.
+/// Clones share the same bucket, so one budget can be given to several
+/// clients.
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    inner: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    per_second: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RetryBudget {
+    /// Allows `retries` retries per `window`, refilled gradually.
+    pub fn new(retries: u32, window: Duration) -> Self {
+        let capacity = f64::from(retries);
+        Self {
+            inner: Arc::new(Mutex::new(Bucket {
+                capacity,
+                per_second: capacity / window.as_secs_f64().max(f64::EPSILON),
+                tokens: capacity,
+                refilled_at: Instant::now(),
+            })),
+        }
+    }
+
+    /// How many retries may be made right now.
+    pub fn available(&self) -> u32 {
+        let mut bucket = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        bucket.refill(Instant::now());
+        bucket.tokens as u32
+    }
+
+    pub(crate) fn try_spend(&self) -> bool {
+        let mut bucket = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        bucket.refill(Instant::now());
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+        self.refilled_at = now;
+    }
+}
+
+/// Retry settings collected by the builder.
+#[derive(Debug, Clone)]
+pub(crate) struct RetryConfig {
+    pub(crate) max_retries: u32,
+    pub(crate) budget: Option<RetryBudget>,
+    pub(crate) backoff_base: Duration,
+    pub(crate) backoff_max: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            budget: None,
+            backoff_base: DEFAULT_BACKOFF_BASE,
+            backoff_max: DEFAULT_BACKOFF_MAX,
+        }
+    }
+}
+
+/// A backoff shared by every request of a client. Each retryable failure
+/// pushes back the moment retries may resume, and every request waits for
+/// it before retrying, so retries back off together rather than each on its
+/// own schedule.
+#[derive(Debug, Default)]
+pub(crate) struct Backoff {
+    state: Mutex<BackoffState>,
+}
+
+#[derive(Debug, Default)]
+struct BackoffState {
+    failures: u32,
+    until: Option<Instant>,
+}
+
+impl Backoff {
+    /// Doubles the backoff, or waits as long as `retry_after` if that is
+    /// longer.
+    pub(crate) fn failure(&self, config: &RetryConfig, retry_after: Option<Duration>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let exponential = config
+            .backoff_base
+            .saturating_mul(2u32.saturating_pow(state.failures))
+            .min(config.backoff_max);
+        let delay = retry_after.map_or(exponential, |after| after.max(exponential));
+        let until = Instant::now() + delay;
+
+        state.failures = state.failures.saturating_add(1);
+        state.until = Some(state.until.map_or(until, |current| current.max(until)));
+    }
+
+    pub(crate) fn success(&self) {
+        *self.state.lock().unwrap_or_else(|e| e.into_inner()) = BackoffState::default();
+    }
+
+    /// Waits until retries may resume, plus up to a tenth of the wait again
+    /// so that waiting requests do not all retry at the same instant.
+    pub(crate) async fn wait(&self) {
+        let until = self.state.lock().unwrap_or_else(|e| e.into_inner()).until;
+        let Some(remaining) = until.and_then(|until| until.checked_duration_since(Instant::now()))
+        else {
+            return;
+        };
+        let jitter = remaining.mul_f64(random_fraction() / 10.0);
+        #[cfg(feature = "metrics")]
+        let _queued = crate::meters::Queued::start();
+        runtime::sleep(remaining + jitter).await;
+    }
+}
+
+/// Whether a response means the request is worth retrying.
+pub(crate) fn is_retryable(response: &Response) -> bool {
+    let status = response.status();
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// The delay a response asks for in its `Retry-After` header, in seconds.
+pub(crate) fn retry_after(response: &Response) -> Option<Duration> {
+    let seconds = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    seconds.trim().parse().ok().map(Duration::from_secs)
+}
+
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
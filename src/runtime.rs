@@ -0,0 +1,191 @@
+//! The few things the core client needs from an async runtime: timers and
+//! reading files.
+//!
+//! With the `async` feature they are Tokio's. Without it, timers are kept by
+//! one shared timer thread and file reads run on a short-lived thread, each
+//! waking the awaiting task, which works under any executor, such as
+//! async-std or smol. Only retry backoff, job stream reconnects and file
+//! reads use them.
+
+#[cfg(feature = "fs")]
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+#[cfg(not(feature = "async"))]
+pub(crate) async fn sleep(duration: Duration) {
+    timer::sleep(duration).await;
+}
+
+#[cfg(all(feature = "async", feature = "fs"))]
+pub(crate) async fn read(path: &Path) -> io::Result<Vec<u8>> {
+    tokio::fs::read(path).await
+}
+
+#[cfg(all(not(feature = "async"), feature = "fs"))]
+pub(crate) async fn read(path: &Path) -> io::Result<Vec<u8>> {
+    let path = path.to_path_buf();
+    unblock(move || std::fs::read(path)).await
+}
+
+/// Runs `f` on its own thread, and completes with its result.
+#[cfg(all(not(feature = "async"), feature = "fs"))]
+fn unblock<T: Send + 'static>(
+    f: impl FnOnce() -> T + Send + 'static,
+) -> impl std::future::Future<Output = T> {
+    use std::sync::{Arc, Mutex};
+    use std::task::{Poll, Waker};
+
+    struct Shared<T> {
+        result: Option<T>,
+        waker: Option<Waker>,
+    }
+
+    let shared = Arc::new(Mutex::new(Shared {
+        result: None,
+        waker: None,
+    }));
+    let done = shared.clone();
+    std::thread::spawn(move || {
+        let result = f();
+        let mut shared = done.lock().unwrap_or_else(|e| e.into_inner());
+        shared.result = Some(result);
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    });
+
+    std::future::poll_fn(move |cx| {
+        let mut shared = shared.lock().unwrap_or_else(|e| e.into_inner());
+        match shared.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    })
+}
+
+/// A single thread that wakes sleeping tasks when their deadlines pass.
+#[cfg(not(feature = "async"))]
+mod timer {
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+    use std::sync::{Arc, Condvar, Mutex, OnceLock};
+    use std::task::{Poll, Waker};
+    use std::time::{Duration, Instant};
+
+    #[derive(Default)]
+    struct Wake {
+        fired: bool,
+        waker: Option<Waker>,
+    }
+
+    struct Entry {
+        deadline: Instant,
+        wake: Arc<Mutex<Wake>>,
+    }
+
+    impl PartialEq for Entry {
+        fn eq(&self, other: &Self) -> bool {
+            self.deadline == other.deadline
+        }
+    }
+
+    impl Eq for Entry {}
+
+    impl PartialOrd for Entry {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Entry {
+        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+            self.deadline.cmp(&other.deadline)
+        }
+    }
+
+    #[derive(Default)]
+    struct Timer {
+        entries: Mutex<BinaryHeap<Reverse<Entry>>>,
+        changed: Condvar,
+    }
+
+    fn timer() -> &'static Timer {
+        static TIMER: OnceLock<&'static Timer> = OnceLock::new();
+        TIMER.get_or_init(|| {
+            let timer: &'static Timer = Box::leak(Box::default());
+            std::thread::Builder::new()
+                .name("safecomms-timer".to_string())
+                .spawn(move || timer.run())
+                .expect("failed to spawn the timer thread");
+            timer
+        })
+    }
+
+    impl Timer {
+        fn run(&self) {
+            let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+            loop {
+                let now = Instant::now();
+                while entries
+                    .peek()
+                    .is_some_and(|Reverse(entry)| entry.deadline <= now)
+                {
+                    let Some(Reverse(entry)) = entries.pop() else {
+                        break;
+                    };
+                    let mut wake = entry.wake.lock().unwrap_or_else(|e| e.into_inner());
+                    wake.fired = true;
+                    if let Some(waker) = wake.waker.take() {
+                        waker.wake();
+                    }
+                }
+
+                entries = match entries.peek() {
+                    Some(Reverse(entry)) => {
+                        let timeout = entry.deadline.saturating_duration_since(now);
+                        self.changed
+                            .wait_timeout(entries, timeout)
+                            .unwrap_or_else(|e| e.into_inner())
+                            .0
+                    }
+                    None => self.changed.wait(entries).unwrap_or_else(|e| e.into_inner()),
+                };
+            }
+        }
+    }
+
+    pub(super) async fn sleep(duration: Duration) {
+        let wake = Arc::new(Mutex::new(Wake::default()));
+        let timer = timer();
+        timer
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Reverse(Entry {
+                deadline: Instant::now() + duration,
+                wake: wake.clone(),
+            }));
+        timer.changed.notify_one();
+
+        std::future::poll_fn(|cx| {
+            let mut wake = wake.lock().unwrap_or_else(|e| e.into_inner());
+            if wake.fired {
+                Poll::Ready(())
+            } else {
+                wake.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
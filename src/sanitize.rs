@@ -0,0 +1,83 @@
+//! Cleaning of text before it is displayed or moderated.
+
+use crate::bypass::{ZALGO_MARKS, is_combining_mark};
+use crate::normalize::is_invisible;
+
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+/// A character that was taken out of the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Removal {
+    /// Byte offset of the character in the original text.
+    pub offset: usize,
+    pub character: char,
+}
+
+/// Cleaned text, along with what was removed from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sanitized {
+    pub text: String,
+    pub removed: Vec<Removal>,
+}
+
+impl Sanitized {
+    pub fn is_modified(&self) -> bool {
+        !self.removed.is_empty()
+    }
+}
+
+/// Removes stacked combining marks, keeping the first two on each character
+/// so that ordinary accents and tone marks survive.
+pub fn strip_zalgo(text: &str) -> Sanitized {
+    let mut stack = 0;
+    strip(text, |_, c, _| {
+        if !is_combining_mark(c) {
+            stack = 0;
+            return false;
+        }
+        stack += 1;
+        stack > ZALGO_MARKS
+    })
+}
+
+/// Removes characters that render as nothing. Zero width joiners between
+/// emoji are kept, since they are what combine emoji sequences such as
+/// family emoji.
+pub fn strip_invisible(text: &str) -> Sanitized {
+    strip(text, |previous, c, next| {
+        let joins_emoji = c == ZERO_WIDTH_JOINER
+            && previous.is_some_and(is_emoji_like)
+            && next.is_some_and(is_emoji_like);
+        is_invisible(c) && !joins_emoji
+    })
+}
+
+fn is_emoji_like(c: char) -> bool {
+    !c.is_ascii() && !c.is_alphanumeric() && !c.is_whitespace()
+}
+
+/// Copies `text` without the characters `remove` picks, given each character
+/// with its neighbours in the original text.
+fn strip(
+    text: &str,
+    mut remove: impl FnMut(Option<char>, char, Option<char>) -> bool,
+) -> Sanitized {
+    let mut sanitized = Sanitized {
+        text: String::with_capacity(text.len()),
+        removed: Vec::new(),
+    };
+    let mut previous = None;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((offset, character)) = chars.next() {
+        let next = chars.peek().map(|&(_, next)| next);
+        if remove(previous, character, next) {
+            sanitized.removed.push(Removal { offset, character });
+        } else {
+            sanitized.text.push(character);
+        }
+        previous = Some(character);
+    }
+
+    sanitized
+}
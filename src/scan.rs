@@ -0,0 +1,196 @@
+use crate::files::{FileKind, IMAGE_EXTENSIONS, moderate_file};
+use crate::{ModerationResponse, SafeCommsClient, SafeCommsError};
+use futures_util::{Stream, StreamExt, stream};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+const DEFAULT_CONCURRENCY: usize = 4;
+/// Enough to recognize every sniffed format.
+const SNIFF_LEN: usize = 12;
+
+/// Which files [`SafeCommsClient::scan_directory`] moderates.
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// Extensions to moderate, lowercase and without the dot. Defaults to
+    /// common image formats; add `txt` or `md` to moderate text files too.
+    pub extensions: Vec<String>,
+    /// Also moderate files whose contents look like an image, whatever
+    /// their extension.
+    pub sniff: bool,
+    /// Files larger than this are skipped.
+    pub max_file_size: Option<u64>,
+    /// How many files are moderated at once.
+    pub concurrency: usize,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            extensions: IMAGE_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+            sniff: false,
+            max_file_size: None,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+/// The outcome for one file, or for a directory that could not be read.
+#[derive(Debug)]
+pub struct ScannedFile {
+    pub path: PathBuf,
+    pub result: Result<ModerationResponse, SafeCommsError>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanSummary {
+    pub scanned: u64,
+    pub clean: u64,
+    pub flagged: u64,
+    pub errors: u64,
+}
+
+impl ScanSummary {
+    pub fn add(&mut self, file: &ScannedFile) {
+        self.scanned += 1;
+        match &file.result {
+            Ok(response) if response.is_clean => self.clean += 1,
+            Ok(_) => self.flagged += 1,
+            Err(_) => self.errors += 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScanReport {
+    pub files: Vec<ScannedFile>,
+    pub summary: ScanSummary,
+}
+
+impl SafeCommsClient {
+    /// Moderates every matching file under `root`, for auditing an existing
+    /// content library. Symbolic links are not followed.
+    pub async fn scan_directory(&self, root: impl AsRef<Path>, options: ScanOptions) -> ScanReport {
+        let mut summary = ScanSummary::default();
+        let files: Vec<ScannedFile> = self
+            .scan_directory_stream(root, options)
+            .inspect(|file| summary.add(file))
+            .collect()
+            .await;
+        ScanReport { files, summary }
+    }
+
+    /// Like [`scan_directory`](Self::scan_directory), but yields each result
+    /// as soon as it is ready, in no particular order.
+    pub fn scan_directory_stream(
+        &self,
+        root: impl AsRef<Path>,
+        options: ScanOptions,
+    ) -> impl Stream<Item = ScannedFile> + '_ {
+        let root = root.as_ref().to_path_buf();
+        let concurrency = options.concurrency.max(1);
+
+        // Directories are read as the moderation below asks for more files,
+        // so a large tree is never held in memory at once.
+        let walk = Walk { options, dirs: vec![root], entries: None };
+        stream::unfold(walk, |mut walk| async move {
+            let found = walk.next().await?;
+            Some((found, walk))
+        })
+            .map(move |(path, kind)| async move {
+                let result = match kind {
+                    Ok(kind) => moderate_file(self, &path, kind).await,
+                    Err(e) => Err(e),
+                };
+                ScannedFile { path, result }
+            })
+            .buffer_unordered(concurrency)
+    }
+}
+
+type Found = (PathBuf, Result<FileKind, SafeCommsError>);
+
+/// A depth-first walk of a directory tree, yielding every file that should
+/// be moderated, with how to moderate it, and every directory that could not
+/// be read.
+struct Walk {
+    options: ScanOptions,
+    dirs: Vec<PathBuf>,
+    /// The directory being read, and what is left of it.
+    entries: Option<(PathBuf, tokio::fs::ReadDir)>,
+}
+
+impl Walk {
+    async fn next(&mut self) -> Option<Found> {
+        loop {
+            let Some((dir, entries)) = &mut self.entries else {
+                let dir = self.dirs.pop()?;
+                match tokio::fs::read_dir(&dir).await {
+                    Ok(entries) => self.entries = Some((dir, entries)),
+                    Err(e) => return Some((dir, Err(read_error(e)))),
+                }
+                continue;
+            };
+
+            let entry = match entries.next_entry().await {
+                Ok(Some(entry)) => entry,
+                Ok(None) => {
+                    self.entries = None;
+                    continue;
+                }
+                Err(e) => {
+                    // The rest of the directory cannot be listed, so report
+                    // it rather than leave its files out unnoticed.
+                    let dir = dir.clone();
+                    self.entries = None;
+                    return Some((dir, Err(read_error(e))));
+                }
+            };
+
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type().await else {
+                continue;
+            };
+            if file_type.is_dir() {
+                self.dirs.push(path);
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+
+            let too_large = match self.options.max_file_size {
+                Some(max) => entry.metadata().await.is_ok_and(|m| m.len() > max),
+                None => false,
+            };
+            if too_large {
+                continue;
+            }
+
+            if let Some(kind) = kind(&path, &self.options).await {
+                return Some((path, Ok(kind)));
+            }
+        }
+    }
+}
+
+async fn kind(path: &Path, options: &ScanOptions) -> Option<FileKind> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    if extension.is_some_and(|extension| options.extensions.contains(&extension)) {
+        return Some(FileKind::of(path).unwrap_or(FileKind::Image));
+    }
+    if !options.sniff {
+        return None;
+    }
+
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    let file = tokio::fs::File::open(path).await.ok()?;
+    file.take(SNIFF_LEN as u64).read_to_end(&mut header).await.ok()?;
+    FileKind::sniff(&header)
+}
+
+fn read_error(e: std::io::Error) -> SafeCommsError {
+    SafeCommsError::ApiError(format!("Failed to read directory: {}", e))
+}
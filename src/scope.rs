@@ -0,0 +1,116 @@
+use crate::{
+    ModerationRequest, ModerationResponse, PromptInjectionResponse, SafeCommsClient,
+    SafeCommsError,
+};
+#[cfg(feature = "image-endpoints")]
+use crate::ImageModerationRequest;
+#[cfg(feature = "multipart")]
+use crate::ImageUploadOptions;
+use futures_util::future::{BoxFuture, join_all};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+/// The result of a call started in a [`Scope`], available once the scope has
+/// finished.
+pub struct ScopedTask<T> {
+    slot: Arc<Mutex<Option<Result<T, SafeCommsError>>>>,
+}
+
+impl<T> ScopedTask<T> {
+    /// The call's result. Fails if the scope was dropped before the call
+    /// finished.
+    pub fn into_result(self) -> Result<T, SafeCommsError> {
+        let result = self.slot.lock().unwrap_or_else(|e| e.into_inner()).take();
+        result.unwrap_or_else(|| {
+            Err(SafeCommsError::ApiError("Scoped call was cancelled".to_string()))
+        })
+    }
+}
+
+/// Collects the calls to run together; see [`SafeCommsClient::scope`].
+pub struct Scope<'a> {
+    client: &'a SafeCommsClient,
+    tasks: Vec<BoxFuture<'a, ()>>,
+}
+
+impl<'a> Scope<'a> {
+    /// Runs any call that returns a result, e.g. one on another client.
+    pub fn spawn<T: Send + 'a>(
+        &mut self,
+        call: impl Future<Output = Result<T, SafeCommsError>> + Send + 'a,
+    ) -> ScopedTask<T> {
+        let slot = Arc::new(Mutex::new(None));
+        let filled = slot.clone();
+        self.tasks.push(Box::pin(async move {
+            let result = call.await;
+            *filled.lock().unwrap_or_else(|e| e.into_inner()) = Some(result);
+        }));
+        ScopedTask { slot }
+    }
+
+    pub fn spawn_text(&mut self, content: &'a str) -> ScopedTask<ModerationResponse> {
+        let client = self.client;
+        self.spawn(client.moderate_text(content, None, None, None, None, None))
+    }
+
+    pub fn spawn_request(
+        &mut self,
+        request: &'a ModerationRequest,
+    ) -> ScopedTask<ModerationResponse> {
+        let client = self.client;
+        self.spawn(client.moderate_request(request))
+    }
+
+    #[cfg(feature = "image-endpoints")]
+    pub fn spawn_image(
+        &mut self,
+        request: ImageModerationRequest<'a>,
+    ) -> ScopedTask<ModerationResponse> {
+        let client = self.client;
+        self.spawn(client.moderate_image(request))
+    }
+
+    #[cfg(feature = "multipart")]
+    pub fn spawn_image_bytes(
+        &mut self,
+        bytes: Vec<u8>,
+        file_name: &'a str,
+        options: ImageUploadOptions<'a>,
+    ) -> ScopedTask<ModerationResponse> {
+        let client = self.client;
+        self.spawn(client.moderate_image_bytes(bytes, file_name, options))
+    }
+
+    pub fn spawn_prompt_injection(
+        &mut self,
+        content: &'a str,
+    ) -> ScopedTask<PromptInjectionResponse> {
+        let client = self.client;
+        self.spawn(client.detect_prompt_injection(content, None))
+    }
+}
+
+impl SafeCommsClient {
+    /// Runs the calls `spawn` starts concurrently, and finishes once all of
+    /// them have. Their results are then available from the tasks `spawn`
+    /// returned:
+    ///
+    /// ```ignore
+    /// let (text, image) = client
+    ///     .scope(|s| (s.spawn_text(message), s.spawn_image(request)))
+    ///     .await;
+    /// let text = text.into_result()?;
+    /// ```
+    ///
+    /// The calls run on the current task, so dropping the returned future
+    /// cancels every call that has not finished.
+    pub async fn scope<'a, R>(&'a self, spawn: impl FnOnce(&mut Scope<'a>) -> R) -> R {
+        let mut scope = Scope {
+            client: self,
+            tasks: Vec::new(),
+        };
+        let tasks = spawn(&mut scope);
+        join_all(scope.tasks).await;
+        tasks
+    }
+}
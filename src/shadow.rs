@@ -0,0 +1,88 @@
+use crate::{Action, Language, ModerationResponse, Policy, SafeCommsClient};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// What a [`Shadow`] has seen so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShadowStats {
+    pub evaluated: u64,
+    /// Evaluations where the shadow policy disagreed with the enforced one.
+    pub diverged: u64,
+    /// How often the shadow policy would have taken each action.
+    pub would_have: BTreeMap<Action, u64>,
+    /// Moderation calls that failed, and were allowed.
+    pub errors: u64,
+}
+
+/// Runs a policy without enforcing it, for rolling out new profiles or
+/// thresholds safely.
+///
+/// Moderation calls are made as usual, so they are recorded, metered and
+/// traced, but every evaluation returns [`Action::Allow`]. What the shadow
+/// policy would have done is counted, along with how often it disagrees with
+/// the enforced policy, which by default allows everything.
+#[derive(Debug)]
+pub struct Shadow {
+    policy: Policy,
+    enforced: Option<Policy>,
+    stats: Mutex<ShadowStats>,
+}
+
+impl Shadow {
+    pub fn new(policy: Policy) -> Self {
+        Self {
+            policy,
+            enforced: None,
+            stats: Mutex::default(),
+        }
+    }
+
+    /// The policy currently in force, to measure divergence against.
+    pub fn enforced(mut self, policy: Policy) -> Self {
+        self.enforced = Some(policy);
+        self
+    }
+
+    /// Counts what the shadow policy would do with `response`, and allows it.
+    pub fn evaluate(&self, response: &ModerationResponse) -> Action {
+        let would_have = self.policy.evaluate(response);
+        let enforced = self
+            .enforced
+            .as_ref()
+            .map_or(Action::Allow, |policy| policy.evaluate(response));
+
+        let mut stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        stats.evaluated += 1;
+        if would_have != enforced {
+            stats.diverged += 1;
+        }
+        *stats.would_have.entry(would_have).or_default() += 1;
+
+        Action::Allow
+    }
+
+    /// Moderates `content` and evaluates the result. Failures are counted and
+    /// logged, and the content is allowed all the same.
+    pub async fn moderate_text(
+        &self,
+        client: &SafeCommsClient,
+        content: &str,
+        language: Option<&str>,
+    ) -> Action {
+        match client
+            .moderate_text(content, language.map(Language::from), None, None, None, None)
+            .await
+        {
+            Ok(response) => self.evaluate(&response),
+            Err(e) => {
+                log::warn!("Shadow moderation failed: {}", e);
+                self.stats.lock().unwrap_or_else(|e| e.into_inner()).errors += 1;
+                Action::Allow
+            }
+        }
+    }
+
+    pub fn stats(&self) -> ShadowStats {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
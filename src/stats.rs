@@ -0,0 +1,131 @@
+use crate::TransportError;
+use reqwest::{Response, StatusCode};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How many recent latencies the mean and 95th percentile are computed over.
+const LATENCY_WINDOW: usize = 1024;
+
+/// Failed HTTP requests, by the kind of failure, counting every attempt.
+/// A call that succeeded after a retry still counts the failed attempt.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ErrorCounts {
+    /// Requests that got no response at all, e.g. connection failures or
+    /// timeouts.
+    pub transport: u64,
+    /// 4xx responses, including rate limiting.
+    pub client: u64,
+    /// 5xx responses.
+    pub server: u64,
+}
+
+/// A snapshot of a client's activity since it was created. Clients derived
+/// with `with_api_key` or `with_idempotency_key` share their parent's
+/// statistics.
+#[derive(Debug, Clone, Default)]
+pub struct ClientStats {
+    /// HTTP requests sent, including retries and failovers, so one call
+    /// may count several times.
+    pub requests: u64,
+    pub errors: ErrorCounts,
+    /// Of the requests, those that were a retry or a failover to another
+    /// endpoint.
+    pub retries: u64,
+    /// 429 responses, including those that were retried.
+    pub rate_limited: u64,
+    /// Latency of whole calls, from the first attempt to the final response.
+    pub mean_latency: Option<Duration>,
+    pub p95_latency: Option<Duration>,
+    /// Texts a [`Deduper`](crate::Deduper) answered with a near-duplicate's
+    /// verdict instead of calling the API.
+    pub cache_hits: u64,
+    /// Texts a [`Deduper`](crate::Deduper) fingerprinted but had no verdict
+    /// for.
+    pub cache_misses: u64,
+}
+
+impl ClientStats {
+    /// The share of deduplicated texts answered without calling the API, or
+    /// None if no text has been looked up.
+    pub fn cache_hit_ratio(&self) -> Option<f64> {
+        let lookups = self.cache_hits + self.cache_misses;
+        (lookups > 0).then(|| self.cache_hits as f64 / lookups as f64)
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    requests: u64,
+    errors: ErrorCounts,
+    rate_limited: u64,
+    latencies: VecDeque<Duration>,
+    cache_hits: u64,
+    cache_misses: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct StatsRecorder {
+    counters: Mutex<Counters>,
+}
+
+impl StatsRecorder {
+    /// Counts one HTTP request and its outcome.
+    pub(crate) fn record_attempt(&self, response: Result<&Response, &TransportError>) {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        counters.requests += 1;
+
+        match response.map(Response::status) {
+            Err(_) => counters.errors.transport += 1,
+            Ok(status) if status.is_server_error() => counters.errors.server += 1,
+            Ok(status) if status.is_client_error() => {
+                counters.errors.client += 1;
+                if status == StatusCode::TOO_MANY_REQUESTS {
+                    counters.rate_limited += 1;
+                }
+            }
+            Ok(_) => {}
+        }
+    }
+
+    /// Records how long a whole call took.
+    pub(crate) fn record_latency(&self, latency: Duration) {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        if counters.latencies.len() == LATENCY_WINDOW {
+            counters.latencies.pop_front();
+        }
+        counters.latencies.push_back(latency);
+    }
+
+    pub(crate) fn record_cache_lookup(&self, hit: bool) {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        if hit {
+            counters.cache_hits += 1;
+        } else {
+            counters.cache_misses += 1;
+        }
+    }
+
+    pub(crate) fn snapshot(&self, retries: u64) -> ClientStats {
+        let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        let mut latencies: Vec<Duration> = counters.latencies.iter().copied().collect();
+        latencies.sort();
+
+        let mean_latency = (!latencies.is_empty())
+            .then(|| latencies.iter().sum::<Duration>() / latencies.len() as u32);
+        let p95_latency = latencies
+            .get((latencies.len() * 95).div_ceil(100).saturating_sub(1))
+            .copied();
+
+        ClientStats {
+            requests: counters.requests,
+            errors: counters.errors,
+            retries,
+            rate_limited: counters.rate_limited,
+            mean_latency,
+            p95_latency,
+            cache_hits: counters.cache_hits,
+            cache_misses: counters.cache_misses,
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{AttachmentVerdict, MessageVerdict, SafeCommsClient, SafeCommsError};
+use std::future::Future;
+use std::sync::Arc;
+use teloxide::Bot;
+use teloxide::net::Download;
+use teloxide::prelude::Requester;
+use teloxide::types::{Message, PhotoSize};
+
+const MAX_PHOTO_BYTES: u32 = 20 * 1024 * 1024;
+// Telegram re-encodes every photo as JPEG.
+const PHOTO_FILE_NAME: &str = "photo.jpg";
+
+impl SafeCommsClient {
+    /// Moderates a Telegram message's text (or caption) and photo
+    /// concurrently. The largest available size of the photo is downloaded
+    /// through `bot` and uploaded to the API.
+    pub async fn moderate_telegram_message(
+        &self,
+        bot: &Bot,
+        message: &Message,
+    ) -> Result<MessageVerdict, SafeCommsError> {
+        let text = async {
+            match message.text().or(message.caption()) {
+                Some(text) if !text.trim().is_empty() => self
+                    .moderate_text(text, None, None, None, None, None)
+                    .await
+                    .map(Some),
+                _ => Ok(None),
+            }
+        };
+
+        let photo = async {
+            match message.photo().and_then(|sizes| sizes.last()) {
+                Some(photo) => vec![self.moderate_telegram_photo(bot, photo).await],
+                None => Vec::new(),
+            }
+        };
+
+        let (text, attachments) = futures_util::join!(text, photo);
+        Ok(MessageVerdict::new(text?, attachments))
+    }
+
+    async fn moderate_telegram_photo(&self, bot: &Bot, photo: &PhotoSize) -> AttachmentVerdict {
+        let result = async {
+            if photo.file.size > MAX_PHOTO_BYTES {
+                return Err(SafeCommsError::ApiError(format!(
+                    "Photo is larger than {} bytes",
+                    MAX_PHOTO_BYTES
+                )));
+            }
+
+            let download_error =
+                |e: &dyn std::fmt::Display| SafeCommsError::ApiError(format!("Failed to download photo: {}", e));
+            let file = bot
+                .get_file(photo.file.id.clone())
+                .await
+                .map_err(|e| download_error(&e))?;
+            let mut bytes = Vec::new();
+            bot.download_file(&file.path, &mut bytes)
+                .await
+                .map_err(|e| download_error(&e))?;
+
+            self.moderate_image_bytes(bytes, PHOTO_FILE_NAME, Default::default())
+                .await
+        };
+
+        AttachmentVerdict {
+            file_name: PHOTO_FILE_NAME.to_string(),
+            result: result.await,
+        }
+    }
+}
+
+/// Moderates incoming messages and hands each verdict to a policy callback
+/// that decides what to do with it.
+///
+/// The policy receives the outcome of moderation, including API failures,
+/// and returns whether the message should continue to the rest of the
+/// handler chain. This makes it a natural dptree filter:
+///
+/// ```ignore
+/// let moderation = TelegramModeration::new(client, |bot, msg, verdict| async move {
+///     match verdict {
+///         Ok(verdict) if !verdict.is_clean() => {
+///             let _ = bot.delete_message(msg.chat.id, msg.id).await;
+///             false
+///         }
+///         _ => true,
+///     }
+/// });
+///
+/// let handler = Update::filter_message()
+///     .filter_async(move |bot: Bot, msg: Message| {
+///         let moderation = moderation.clone();
+///         async move { moderation.check(&bot, &msg).await }
+///     })
+///     .endpoint(answer);
+/// ```
+pub struct TelegramModeration<P> {
+    client: SafeCommsClient,
+    policy: Arc<P>,
+}
+
+impl<P> Clone for TelegramModeration<P> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            policy: Arc::clone(&self.policy),
+        }
+    }
+}
+
+impl<P, Fut> TelegramModeration<P>
+where
+    P: Fn(Bot, Message, Result<MessageVerdict, SafeCommsError>) -> Fut + Send + Sync,
+    Fut: Future<Output = bool> + Send,
+{
+    pub fn new(client: SafeCommsClient, policy: P) -> Self {
+        Self {
+            client,
+            policy: Arc::new(policy),
+        }
+    }
+
+    /// Moderates `message` and returns the policy's decision on whether it
+    /// should be handled further.
+    pub async fn check(&self, bot: &Bot, message: &Message) -> bool {
+        let verdict = self.client.moderate_telegram_message(bot, message).await;
+        (self.policy)(bot.clone(), message.clone(), verdict).await
+    }
+}
@@ -0,0 +1,39 @@
+//! Spans for outgoing requests. Only the request's shape is recorded, never
+//! the content being moderated or the response body.
+
+use crate::REQUEST_ID_HEADER;
+use reqwest::{Method, Response};
+use std::time::Duration;
+use tracing::Span;
+use tracing::field::Empty;
+
+pub(crate) fn request_span(method: &Method, path: &str) -> Span {
+    tracing::info_span!(
+        "safecomms.request",
+        method = %method,
+        endpoint = path,
+        status = Empty,
+        latency_ms = Empty,
+        attempts = Empty,
+        request_id = Empty,
+    )
+}
+
+/// Called by the endpoint pool before each attempt, so that failovers show up
+/// as a retry count.
+pub(crate) fn record_attempt(attempt: usize) {
+    Span::current().record("attempts", attempt);
+}
+
+pub(crate) fn record_response(response: &Response, latency: Duration) {
+    let span = Span::current();
+    span.record("status", response.status().as_u16());
+    span.record("latency_ms", latency.as_millis() as u64);
+    if let Some(request_id) = response
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        span.record("request_id", request_id);
+    }
+}
@@ -0,0 +1,281 @@
+use crate::keys::ApiKeys;
+use crate::{ClientStats, Language, Metadata, ProfileId, SafeCommsClient, SafeCommsError};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+type Loader = Box<dyn Fn(&str) -> Option<TenantConfig> + Send + Sync>;
+
+/// How a tenant's client is set up: the API keys it uses, and the defaults
+/// applied to its requests when they do not set their own.
+#[derive(Clone)]
+pub struct TenantConfig {
+    api_keys: Vec<String>,
+    moderation_profile_id: Option<ProfileId>,
+    language: Option<Language>,
+    metadata: Metadata,
+}
+
+impl TenantConfig {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            api_keys: vec![api_key],
+            moderation_profile_id: None,
+            language: None,
+            metadata: Metadata::default(),
+        }
+    }
+
+    /// More keys for the tenant to rotate between, as with
+    /// [`api_keys`](crate::SafeCommsClientBuilder::api_keys).
+    pub fn api_keys(mut self, keys: Vec<String>) -> Self {
+        self.api_keys.extend(keys);
+        self
+    }
+
+    pub fn moderation_profile_id(mut self, id: impl Into<ProfileId>) -> Self {
+        self.moderation_profile_id = Some(id.into());
+        self
+    }
+
+    pub fn language(mut self, language: Language) -> Self {
+        self.language = Some(language);
+        self
+    }
+
+    /// Tags every request of the tenant, under the request's own metadata.
+    /// The tenant's ID is added as `tenant` unless set here.
+    pub fn metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+}
+
+/// What each moderation call costs, for estimating what tenants cost.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pricing {
+    pub per_text: f64,
+    pub per_image: f64,
+}
+
+/// A tenant's usage since its client was created.
+#[derive(Debug, Clone, Default)]
+pub struct TenantUsage {
+    /// Text moderations and prompt-injection checks.
+    pub text_moderations: u64,
+    /// Image moderations, including queued image jobs.
+    pub image_moderations: u64,
+    /// Calls whose content was not clean, or was a prompt injection. Queued
+    /// image jobs have no verdict when they are counted, so never count.
+    pub flagged: u64,
+    /// Estimated from the manager's [`Pricing`].
+    pub cost: f64,
+    /// Counters and latencies of all the tenant's requests.
+    pub stats: ClientStats,
+}
+
+#[derive(Default)]
+struct Counters {
+    text: u64,
+    image: u64,
+    flagged: u64,
+}
+
+/// The defaults and usage counters of a tenant's client.
+pub(crate) struct Tenant {
+    pub(crate) moderation_profile_id: Option<ProfileId>,
+    pub(crate) language: Option<Language>,
+    metadata: Metadata,
+    counters: Mutex<Counters>,
+}
+
+impl Tenant {
+    /// The tenant's metadata with the request's own laid over it.
+    pub(crate) fn metadata(&self, request: Option<&Metadata>) -> Metadata {
+        let mut metadata = self.metadata.clone();
+        for (key, value) in request.into_iter().flat_map(Metadata::iter) {
+            metadata = metadata.with(key, value);
+        }
+        metadata
+    }
+
+    pub(crate) fn record(&self, path: &str, flagged: bool) {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        if path.starts_with("/moderation/image") {
+            counters.image += 1;
+        } else {
+            counters.text += 1;
+        }
+        if flagged {
+            counters.flagged += 1;
+        }
+    }
+
+    fn usage(&self, pricing: &Pricing, stats: ClientStats) -> TenantUsage {
+        let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        TenantUsage {
+            text_moderations: counters.text,
+            image_moderations: counters.image,
+            flagged: counters.flagged,
+            cost: counters.text as f64 * pricing.per_text
+                + counters.image as f64 * pricing.per_image,
+            stats,
+        }
+    }
+}
+
+/// Hands out a client per tenant, for services that moderate on behalf of
+/// several customers, each with their own API keys and profile.
+///
+/// Tenant clients are built on first use from a template client, whose
+/// connection pool, endpoints and settings they share; only its API keys are
+/// replaced. Each tenant client keeps its own statistics, and counts its
+/// moderation calls towards the tenant's [`usage`](Self::usage).
+pub struct ClientManager {
+    base: SafeCommsClient,
+    pricing: Pricing,
+    loader: Option<Loader>,
+    configs: Mutex<HashMap<String, TenantConfig>>,
+    clients: Mutex<HashMap<String, (SafeCommsClient, Arc<Tenant>)>>,
+    /// Bumped for a tenant whenever it is registered or removed, with the
+    /// `clients` lock held, so a client built from a config that has since
+    /// changed is never cached.
+    generations: Mutex<HashMap<String, u64>>,
+}
+
+impl ClientManager {
+    pub fn new(base: SafeCommsClient) -> Self {
+        Self {
+            base,
+            pricing: Pricing::default(),
+            loader: None,
+            configs: Mutex::default(),
+            clients: Mutex::default(),
+            generations: Mutex::default(),
+        }
+    }
+
+    pub fn pricing(mut self, pricing: Pricing) -> Self {
+        self.pricing = pricing;
+        self
+    }
+
+    /// Looks up tenants that were not registered, e.g. in a database. The
+    /// loader is called when a tenant's client is first needed, and runs
+    /// without blocking other tenants; callers that race for the same new
+    /// tenant may each call it, and the first client built is kept.
+    pub fn loader(
+        mut self,
+        loader: impl Fn(&str) -> Option<TenantConfig> + Send + Sync + 'static,
+    ) -> Self {
+        self.loader = Some(Box::new(loader));
+        self
+    }
+
+    /// Sets up a tenant, replacing its client and usage if it had one.
+    pub fn register(&self, tenant_id: impl Into<String>, config: TenantConfig) {
+        let tenant_id = tenant_id.into();
+        self.configs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(tenant_id.clone(), config);
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        clients.remove(&tenant_id);
+        self.bump(&tenant_id);
+    }
+
+    /// Forgets a tenant and its usage. Returns whether it was known.
+    pub fn remove(&self, tenant_id: &str) -> bool {
+        let config = self.configs.lock().unwrap_or_else(|e| e.into_inner()).remove(tenant_id);
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        let client = clients.remove(tenant_id);
+        self.bump(tenant_id);
+        config.is_some() || client.is_some()
+    }
+
+    fn generation(&self, tenant_id: &str) -> u64 {
+        let generations = self.generations.lock().unwrap_or_else(|e| e.into_inner());
+        generations.get(tenant_id).copied().unwrap_or_default()
+    }
+
+    fn bump(&self, tenant_id: &str) {
+        let mut generations = self.generations.lock().unwrap_or_else(|e| e.into_inner());
+        *generations.entry(tenant_id.to_string()).or_default() += 1;
+    }
+
+    /// The tenant's client, built on first use.
+    pub fn client(&self, tenant_id: &str) -> Result<SafeCommsClient, SafeCommsError> {
+        loop {
+            if let Some(client) = self.try_client(tenant_id)? {
+                return Ok(client);
+            }
+        }
+    }
+
+    /// The tenant's client, or None if the tenant was registered or removed
+    /// while its client was being built.
+    fn try_client(&self, tenant_id: &str) -> Result<Option<SafeCommsClient>, SafeCommsError> {
+        let generation = {
+            let clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some((client, _)) = clients.get(tenant_id) {
+                return Ok(Some(client.clone()));
+            }
+            self.generation(tenant_id)
+        };
+
+        let registered = self
+            .configs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(tenant_id)
+            .cloned();
+        // The guard above is dropped with its statement, so no lock is held
+        // while the loader runs: it may be slow or call back into the manager.
+        let config = registered
+            .or_else(|| self.loader.as_ref().and_then(|loader| loader(tenant_id)))
+            .ok_or_else(|| SafeCommsError::UnknownTenant(tenant_id.to_string()))?;
+
+        let built = self.build(tenant_id, config);
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        if self.generation(tenant_id) != generation {
+            return Ok(None);
+        }
+        let (client, _) = clients.entry(tenant_id.to_string()).or_insert(built);
+        Ok(Some(client.clone()))
+    }
+
+    /// The tenant's usage, if its client has been built.
+    pub fn usage(&self, tenant_id: &str) -> Option<TenantUsage> {
+        let clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        let (client, tenant) = clients.get(tenant_id)?;
+        Some(tenant.usage(&self.pricing, client.stats()))
+    }
+
+    /// The usage of every tenant whose client has been built.
+    pub fn all_usage(&self) -> BTreeMap<String, TenantUsage> {
+        let clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        clients
+            .iter()
+            .map(|(id, (client, tenant))| (id.clone(), tenant.usage(&self.pricing, client.stats())))
+            .collect()
+    }
+
+    fn build(&self, tenant_id: &str, config: TenantConfig) -> (SafeCommsClient, Arc<Tenant>) {
+        let metadata = match config.metadata.get("tenant") {
+            Some(_) => config.metadata,
+            None => config.metadata.tenant(tenant_id),
+        };
+        let tenant = Arc::new(Tenant {
+            moderation_profile_id: config.moderation_profile_id,
+            language: config.language,
+            metadata,
+            counters: Mutex::default(),
+        });
+        let client = SafeCommsClient {
+            keys: Arc::new(ApiKeys::new(config.api_keys)),
+            stats: Arc::default(),
+            tenant: Some(tenant.clone()),
+            ..self.base.clone()
+        };
+        (client, tenant)
+    }
+}
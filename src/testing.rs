@@ -0,0 +1,219 @@
+//! A fake API for end-to-end tests, answering each request with a canned
+//! response picked by rules on its content.
+
+use crate::{HttpTransport, Severity, TransportError};
+use futures_util::future::BoxFuture;
+use regex::Regex;
+use reqwest::{Request, Response};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "mock-server")]
+mod server;
+
+#[cfg(feature = "mock-server")]
+pub use self::server::{MockServer, ReceivedRequest};
+
+/// The `Retry-After` sent with [`Canned::RateLimited`], in seconds.
+const RATE_LIMIT_RETRY_AFTER: u64 = 1;
+
+/// A response for [`FakeApi`] to give.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Canned {
+    Clean,
+    /// Flagged with the given severity, and not clean.
+    Flagged(Severity),
+    /// Flagged as an attempt to evade moderation.
+    BypassAttempt,
+    /// A 429 problem response with a `Retry-After` header.
+    RateLimited,
+    /// This body, with a 200 status.
+    Json(Value),
+}
+
+impl Canned {
+    /// The status and body for a request to `path`, shaped like that
+    /// endpoint's responses.
+    fn response(&self, path: &str) -> (u16, Value) {
+        let moderation = match self {
+            Canned::Clean => json!({ "isClean": true, "isBypassAttempt": false }),
+            Canned::Flagged(severity) => json!({
+                "isClean": false,
+                "isBypassAttempt": false,
+                "severity": severity,
+                "reason": "Flagged by a test rule",
+            }),
+            Canned::BypassAttempt => json!({
+                "isClean": false,
+                "isBypassAttempt": true,
+                "severity": Severity::High,
+                "reason": "Bypass attempt flagged by a test rule",
+            }),
+            Canned::RateLimited => {
+                return (
+                    429,
+                    json!({
+                        "status": 429,
+                        "title": "Too Many Requests",
+                        "detail": "Rate limited by a test rule",
+                    }),
+                );
+            }
+            Canned::Json(body) => return (200, body.clone()),
+        };
+
+        let body = if path.ends_with("/moderation/prompt-injection") {
+            let is_injection = *self != Canned::Clean;
+            json!({
+                "isInjection": is_injection,
+                "score": if is_injection { 1.0 } else { 0.0 },
+            })
+        } else if path.ends_with("/moderation/image/async") {
+            json!({ "jobId": "fake", "status": "completed", "result": moderation })
+        } else {
+            moderation
+        };
+        (200, body)
+    }
+}
+
+enum Matcher {
+    Pattern(Regex),
+    Hash(String),
+}
+
+/// An [`HttpTransport`] that answers requests from rules instead of the
+/// API, so tests can reach every branch of their policies deterministically
+/// and without a network; set it with
+/// [`SafeCommsClientBuilder::transport`](crate::SafeCommsClientBuilder::transport).
+///
+/// Rules match the `content` of text and prompt injection requests, or the
+/// `image` of image requests, and the first rule that matches gives the
+/// response. Requests no rule matches, including multipart uploads and
+/// bodies that are not JSON, get [`Canned::Clean`] unless
+/// [`otherwise`](Self::otherwise) says different. `/usage` is always
+/// answered with an unlimited plan.
+///
+/// Unlike [`DryRun`](crate::DryRun), the fake sits below the client, so
+/// retries, key rotation, statistics and error handling run as they would
+/// against the API.
+pub struct FakeApi {
+    rules: Vec<(Matcher, Canned)>,
+    otherwise: Canned,
+}
+
+impl FakeApi {
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            otherwise: Canned::Clean,
+        }
+    }
+
+    /// Answers content that `pattern` matches with `canned`.
+    ///
+    /// # Panics
+    ///
+    /// If `pattern` is not a valid regular expression.
+    pub fn when_matches(mut self, pattern: &str, canned: Canned) -> Self {
+        let regex = Regex::new(pattern)
+            .unwrap_or_else(|e| panic!("invalid fake API pattern `{}`: {}", pattern, e));
+        self.rules.push((Matcher::Pattern(regex), canned));
+        self
+    }
+
+    /// Answers content whose [`content_hash`] is `hash` with `canned`, so
+    /// that fixtures need not be spelled out in tests.
+    pub fn when_hash(mut self, hash: &str, canned: Canned) -> Self {
+        self.rules
+            .push((Matcher::Hash(hash.to_ascii_lowercase()), canned));
+        self
+    }
+
+    /// Answers exactly `content` with `canned`.
+    pub fn when_content(self, content: &str, canned: Canned) -> Self {
+        self.when_hash(&content_hash(content), canned)
+    }
+
+    /// The response for content no rule matches. Defaults to
+    /// [`Canned::Clean`].
+    pub fn otherwise(mut self, canned: Canned) -> Self {
+        self.otherwise = canned;
+        self
+    }
+
+    /// The response the rules give for `content`.
+    pub fn canned_for(&self, content: &str) -> &Canned {
+        let mut hash = None;
+        self.rules
+            .iter()
+            .find(|(matcher, _)| match matcher {
+                Matcher::Pattern(regex) => regex.is_match(content),
+                Matcher::Hash(expected) => {
+                    *expected == *hash.get_or_insert_with(|| content_hash(content))
+                }
+            })
+            .map_or(&self.otherwise, |(_, canned)| canned)
+    }
+
+    /// The status and body for a request to `path` with `body`.
+    fn answer(&self, path: &str, body: Option<&[u8]>) -> (u16, Value) {
+        if path.ends_with("/usage") {
+            let usage = json!({
+                "tier": "fake",
+                "rateLimit": 0,
+                "tokensUsed": 0,
+                "remainingTokens": 0,
+            });
+            return (200, usage);
+        }
+
+        let content = body
+            .and_then(|bytes| serde_json::from_slice::<Value>(bytes).ok())
+            .and_then(|body| {
+                let content = body.get("content").or_else(|| body.get("image"))?;
+                content.as_str().map(str::to_string)
+            });
+        match content {
+            Some(content) => self.canned_for(&content).response(path),
+            None => self.otherwise.response(path),
+        }
+    }
+}
+
+/// A JSON response, or a problem with a `Retry-After` header for a 429.
+fn http_response(status: u16, body: &Value) -> http::Response<String> {
+    let mut response = http::Response::builder().status(status);
+    response = match status {
+        200..=299 => response.header("content-type", "application/json"),
+        429 => response
+            .header("content-type", "application/problem+json")
+            .header("retry-after", RATE_LIMIT_RETRY_AFTER.to_string()),
+        _ => response.header("content-type", "application/problem+json"),
+    };
+    response
+        .body(body.to_string())
+        .expect("status and headers are valid")
+}
+
+impl Default for FakeApi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpTransport for FakeApi {
+    fn execute(&self, request: Request) -> BoxFuture<'_, Result<Response, TransportError>> {
+        Box::pin(async move {
+            let body = request.body().and_then(|body| body.as_bytes());
+            let (status, body) = self.answer(request.url().path(), body);
+            Ok(Response::from(http_response(status, &body)))
+        })
+    }
+}
+
+/// The lowercase hex SHA-256 of `content`, as matched by
+/// [`FakeApi::when_hash`].
+pub fn content_hash(content: &str) -> String {
+    hex::encode(Sha256::digest(content.as_bytes()))
+}
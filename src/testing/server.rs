@@ -0,0 +1,262 @@
+use super::{FakeApi, http_response};
+use crate::{REQUEST_ID_HEADER, SafeCommsClient, SafeCommsClientBuilder};
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::header::{AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, Response, Uri};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// The key [`MockServer::client`] sends; the server accepts any key.
+const MOCK_API_KEY: &str = "mock-api-key";
+/// The JSON endpoints and the field each requires.
+const ENDPOINTS: [(&str, &str); 4] = [
+    ("/moderation/text", "content"),
+    ("/moderation/prompt-injection", "content"),
+    ("/moderation/image", "image"),
+    ("/moderation/image/async", "image"),
+];
+
+/// A request the [`MockServer`] received.
+#[derive(Debug, Clone)]
+pub struct ReceivedRequest {
+    pub method: Method,
+    pub path: String,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl ReceivedRequest {
+    /// The body parsed as JSON, if it is JSON.
+    pub fn json(&self) -> Option<Value> {
+        serde_json::from_slice(&self.body).ok()
+    }
+}
+
+struct ServerState {
+    fake: FakeApi,
+    requests: Mutex<Vec<ReceivedRequest>>,
+    jobs: Mutex<HashMap<String, Value>>,
+}
+
+/// A local HTTP server that behaves like the SafeComms API, for integration
+/// tests of code that talks to it over the network, e.g. another process or
+/// a client configured from the environment.
+///
+/// It serves the real paths and answers from [`FakeApi`] rules. Requests
+/// without an API key get a 401 problem, unknown paths a 404, and bodies
+/// missing their `content` or `image` a 400 validation problem, all as RFC
+/// 7807 problem details with an `X-Request-Id` header. Async image jobs
+/// complete immediately and can be fetched or followed as events.
+/// Compressed and MessagePack bodies are not validated.
+///
+/// The server listens on a free port on 127.0.0.1 and stops when dropped.
+pub struct MockServer {
+    address: SocketAddr,
+    state: Arc<ServerState>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Starts a server that finds all content clean.
+    pub async fn start() -> io::Result<Self> {
+        Self::with_rules(FakeApi::new()).await
+    }
+
+    /// Starts a server that answers from `fake`'s rules.
+    pub async fn with_rules(fake: FakeApi) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(ServerState {
+            fake,
+            requests: Mutex::default(),
+            jobs: Mutex::default(),
+        });
+
+        let app = Router::new().fallback(receive).with_state(state.clone());
+        let task = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                log::warn!("mock server stopped: {}", e);
+            }
+        });
+
+        Ok(Self {
+            address,
+            state,
+            task,
+        })
+    }
+
+    /// The base URL to point a client at.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// A client builder pointed at the server, for further configuration.
+    pub fn client(&self) -> SafeCommsClientBuilder {
+        SafeCommsClient::builder(MOCK_API_KEY.to_string()).base_url(self.url())
+    }
+
+    /// The requests received so far, oldest first.
+    pub fn received_requests(&self) -> Vec<ReceivedRequest> {
+        self.state
+            .requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn receive(
+    State(state): State<Arc<ServerState>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response<String> {
+    let path = uri.path().to_string();
+    let mut response = respond(&state, &method, &path, &headers, &body);
+    state
+        .requests
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(ReceivedRequest {
+            method,
+            path,
+            headers,
+            body,
+        });
+
+    let request_id =
+        HeaderValue::from_str(&Uuid::new_v4().to_string()).expect("UUIDs are valid header values");
+    let name = HeaderName::from_bytes(REQUEST_ID_HEADER.as_bytes())
+        .expect("request ID header name is valid");
+    response.headers_mut().insert(name, request_id);
+    response
+}
+
+fn respond(
+    state: &ServerState,
+    method: &Method,
+    path: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Response<String> {
+    let has_key = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|key| !key.trim().is_empty());
+    if !has_key {
+        return problem(401, "Unauthorized", "A valid API key is required.");
+    }
+
+    let jobs = path.strip_prefix("/jobs/");
+    match (method, path) {
+        (&Method::GET, "/usage") | (&Method::POST, "/moderation/image/upload") => {
+            let (status, body) = state.fake.answer(path, None);
+            http_response(status, &body)
+        }
+        (&Method::GET, _) if let Some(job_id) = jobs => match job_id.strip_suffix("/events") {
+            Some(job_id) => job_events(state, job_id),
+            None => job(state, job_id),
+        },
+        (&Method::POST, _) if let Some((_, field)) = ENDPOINTS.iter().find(|(p, _)| *p == path) => {
+            moderate(state, path, field, headers, body)
+        }
+        _ => problem(
+            404,
+            "Not Found",
+            &format!("No endpoint for {} {}.", method, path),
+        ),
+    }
+}
+
+fn moderate(
+    state: &ServerState,
+    path: &str,
+    field: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Response<String> {
+    let is_json = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if is_json && !headers.contains_key(CONTENT_ENCODING) {
+        let Ok(request) = serde_json::from_slice::<Value>(body) else {
+            return problem(400, "Bad Request", "The request body is not valid JSON.");
+        };
+        if request
+            .get(field)
+            .and_then(Value::as_str)
+            .is_none_or(str::is_empty)
+        {
+            let errors = json!({ field: [format!("The {} field is required.", field)] });
+            return validation_problem(errors);
+        }
+    }
+
+    let (status, mut response) = state.fake.answer(path, Some(body));
+    if status == 200 && path.ends_with("/async") {
+        let job_id = Uuid::new_v4().to_string();
+        response["jobId"] = json!(job_id);
+        state
+            .jobs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(job_id, response.clone());
+    }
+    http_response(status, &response)
+}
+
+fn job(state: &ServerState, job_id: &str) -> Response<String> {
+    let jobs = state.jobs.lock().unwrap_or_else(|e| e.into_inner());
+    match jobs.get(job_id) {
+        Some(job) => http_response(200, job),
+        None => problem(404, "Not Found", &format!("Job {} was not found.", job_id)),
+    }
+}
+
+/// The job's events: a single `completed` event, since mock jobs finish
+/// when they are queued.
+fn job_events(state: &ServerState, job_id: &str) -> Response<String> {
+    let jobs = state.jobs.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(job) = jobs.get(job_id) else {
+        return problem(404, "Not Found", &format!("Job {} was not found.", job_id));
+    };
+
+    Response::builder()
+        .status(200)
+        .header(CONTENT_TYPE, "text/event-stream")
+        .body(format!("id: 1\nevent: completed\ndata: {}\n\n", job))
+        .expect("status and headers are valid")
+}
+
+fn problem(status: u16, title: &str, detail: &str) -> Response<String> {
+    let body = json!({ "status": status, "title": title, "detail": detail });
+    http_response(status, &body)
+}
+
+fn validation_problem(errors: Value) -> Response<String> {
+    let body = json!({
+        "type": "https://tools.ietf.org/html/rfc9110#section-15.5.1",
+        "title": "One or more validation errors occurred.",
+        "status": 400,
+        "errors": errors,
+    });
+    http_response(400, &body)
+}
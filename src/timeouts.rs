@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// The kinds of API call that can be given their own timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointClass {
+    /// Text moderation and prompt-injection checks.
+    Text,
+    /// Image moderation, including uploads and queuing asynchronous jobs.
+    /// Polling a queued job is [`Other`](Self::Other).
+    Image,
+    /// Everything else, such as usage and job status. Job event streams
+    /// are never timed out.
+    Other,
+}
+
+impl EndpointClass {
+    fn of(path: &str) -> Self {
+        if path.starts_with("/moderation/image") {
+            EndpointClass::Image
+        } else if path.starts_with("/moderation/") {
+            EndpointClass::Text
+        } else {
+            EndpointClass::Other
+        }
+    }
+}
+
+/// Total timeouts collected by the builder, by endpoint class.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Timeouts {
+    pub(crate) default: Option<Duration>,
+    pub(crate) classes: HashMap<EndpointClass, Duration>,
+}
+
+impl Timeouts {
+    /// The time allowed for one attempt at a request to `path`, from
+    /// sending it to reading the whole response.
+    pub(crate) fn total(&self, path: &str) -> Option<Duration> {
+        self.classes
+            .get(&EndpointClass::of(path))
+            .copied()
+            .or(self.default)
+    }
+}
@@ -0,0 +1,98 @@
+use reqwest::{Certificate, ClientBuilder, Identity};
+
+/// A client certificate and private key to present for mutual TLS; see
+/// [`SafeCommsClientBuilder::client_identity`](crate::SafeCommsClientBuilder::client_identity).
+#[derive(Clone)]
+pub struct ClientIdentity {
+    source: Source,
+}
+
+#[derive(Clone)]
+enum Source {
+    Pem { certificate: Vec<u8>, key: Vec<u8> },
+    #[cfg(feature = "native-tls")]
+    Pkcs12 { der: Vec<u8>, password: String },
+}
+
+impl ClientIdentity {
+    /// A PEM certificate chain, leaf first, and its PEM private key. With
+    /// `native-tls` the key must be PKCS#8 (`BEGIN PRIVATE KEY`).
+    pub fn from_pem(certificate: &[u8], key: &[u8]) -> Self {
+        Self {
+            source: Source::Pem {
+                certificate: certificate.to_vec(),
+                key: key.to_vec(),
+            },
+        }
+    }
+
+    /// A DER-encoded PKCS#12 archive (`.p12` or `.pfx`) and its password.
+    #[cfg(feature = "native-tls")]
+    pub fn from_pkcs12(der: &[u8], password: &str) -> Self {
+        Self {
+            source: Source::Pkcs12 {
+                der: der.to_vec(),
+                password: password.to_string(),
+            },
+        }
+    }
+
+    fn to_reqwest(&self) -> Result<Identity, reqwest::Error> {
+        match &self.source {
+            #[cfg(feature = "rustls")]
+            Source::Pem { certificate, key } => {
+                let mut pem = certificate.clone();
+                pem.push(b'\n');
+                pem.extend_from_slice(key);
+                Identity::from_pem(&pem)
+            }
+            #[cfg(not(feature = "rustls"))]
+            Source::Pem { certificate, key } => Identity::from_pkcs8_pem(certificate, key),
+            #[cfg(feature = "native-tls")]
+            Source::Pkcs12 { der, password } => Identity::from_pkcs12_der(der, password),
+        }
+    }
+}
+
+impl std::fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match &self.source {
+            Source::Pem { .. } => "pem",
+            #[cfg(feature = "native-tls")]
+            Source::Pkcs12 { .. } => "pkcs12",
+        };
+        f.debug_struct("ClientIdentity").field("kind", &kind).finish_non_exhaustive()
+    }
+}
+
+/// TLS settings collected by the builder and applied when the HTTP client is
+/// built, so invalid certificates surface from `build()`.
+pub(crate) struct TlsConfig {
+    pub(crate) identity: Option<ClientIdentity>,
+    pub(crate) ca_bundles: Vec<Vec<u8>>,
+    pub(crate) built_in_roots: bool,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            identity: None,
+            ca_bundles: Vec::new(),
+            built_in_roots: true,
+        }
+    }
+}
+
+impl TlsConfig {
+    pub(crate) fn apply(&self, mut http: ClientBuilder) -> Result<ClientBuilder, reqwest::Error> {
+        if let Some(identity) = &self.identity {
+            http = http.identity(identity.to_reqwest()?);
+        }
+        for bundle in &self.ca_bundles {
+            for certificate in Certificate::from_pem_bundle(bundle)? {
+                http = http.add_root_certificate(certificate);
+            }
+        }
+        Ok(http.tls_built_in_root_certs(self.built_in_roots))
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{ModerationRequest, ModerationResponse, SafeCommsClient, SafeCommsError};
+use futures_util::future::BoxFuture;
+use std::task::{Context, Poll};
+use thiserror::Error;
+use tower_layer::Layer;
+use tower_service::Service;
+
+impl Service<ModerationRequest> for SafeCommsClient {
+    type Response = ModerationResponse;
+    type Error = SafeCommsError;
+    type Future = BoxFuture<'static, Result<ModerationResponse, SafeCommsError>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), SafeCommsError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: ModerationRequest) -> Self::Future {
+        let client = self.clone();
+        Box::pin(async move {
+            client.moderate_request(&request).await
+        })
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ModerationError<E> {
+    #[error("Content was rejected by moderation")]
+    Rejected(ModerationResponse),
+    #[error(transparent)]
+    Api(SafeCommsError),
+    #[error(transparent)]
+    Inner(E),
+}
+
+/// Moderates requests before they reach the wrapped service.
+///
+/// `extract` picks the text to check out of each request; requests for which
+/// it returns `None` pass straight through. Requests whose content is not
+/// clean fail with [`ModerationError::Rejected`].
+#[derive(Clone)]
+pub struct ModerationLayer<F> {
+    client: SafeCommsClient,
+    extract: F,
+}
+
+impl<F> ModerationLayer<F> {
+    pub fn new(client: SafeCommsClient, extract: F) -> Self {
+        Self { client, extract }
+    }
+}
+
+impl<S, F: Clone> Layer<S> for ModerationLayer<F> {
+    type Service = Moderation<S, F>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Moderation {
+            inner,
+            client: self.client.clone(),
+            extract: self.extract.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Moderation<S, F> {
+    inner: S,
+    client: SafeCommsClient,
+    extract: F,
+}
+
+impl<S, F, Req> Service<Req> for Moderation<S, F>
+where
+    S: Service<Req> + Clone + Send + 'static,
+    S::Future: Send,
+    F: Fn(&Req) -> Option<ModerationRequest>,
+    Req: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = ModerationError<S::Error>;
+    type Future = BoxFuture<'static, Result<S::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(ModerationError::Inner)
+    }
+
+    fn call(&mut self, request: Req) -> Self::Future {
+        let moderation = (self.extract)(&request);
+        let mut client = self.client.clone();
+
+        // The clone has not been polled for readiness, so keep the ready
+        // service for this call and leave the clone in its place.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            if let Some(moderation) = moderation {
+                let result = client.call(moderation).await.map_err(ModerationError::Api)?;
+                if !result.is_clean {
+                    return Err(ModerationError::Rejected(result));
+                }
+            }
+
+            inner.call(request).await.map_err(ModerationError::Inner)
+        })
+    }
+}
@@ -0,0 +1,45 @@
+use futures_util::future::BoxFuture;
+use reqwest::{Request, Response};
+use std::error::Error;
+use thiserror::Error;
+
+/// Sends requests built by the client, in place of its `reqwest` client; set
+/// one with [`SafeCommsClientBuilder::transport`](crate::SafeCommsClientBuilder::transport).
+///
+/// Requests arrive with their URL, headers and body final, including any
+/// compression and signature. Multipart uploads have a streamed body, which
+/// implements `http_body::Body` and can be handed to `hyper` as is; a
+/// response built elsewhere converts with `Response::from(http::Response)`.
+///
+/// A transport is called once per endpoint attempted, so failover,
+/// statistics and middleware keep working.
+pub trait HttpTransport: Send + Sync {
+    fn execute(&self, request: Request) -> BoxFuture<'_, Result<Response, TransportError>>;
+}
+
+impl HttpTransport for reqwest::Client {
+    fn execute(&self, request: Request) -> BoxFuture<'_, Result<Response, TransportError>> {
+        Box::pin(async move { Ok(reqwest::Client::execute(self, request).await?) })
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum TransportError {
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    /// The endpoint could not be reached, so the next one is tried.
+    #[error("Failed to connect: {0}")]
+    Connect(Box<dyn Error + Send + Sync>),
+    #[error("Transport failed: {0}")]
+    Other(Box<dyn Error + Send + Sync>),
+}
+
+impl TransportError {
+    pub fn is_connect(&self) -> bool {
+        match self {
+            TransportError::Reqwest(e) => e.is_connect(),
+            TransportError::Connect(_) => true,
+            TransportError::Other(_) => false,
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{ModerationResponse, SafeCommsClient, SafeCommsError, SuggestedAction};
+use futures_util::{Stream, StreamExt, stream};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedReceiver;
+use twitch_irc::message::{PrivmsgMessage, ServerMessage};
+
+const DEFAULT_CONCURRENCY: usize = 8;
+const STRIKE_WINDOW: Duration = Duration::from_secs(60 * 60);
+const BASE_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// An action for the bot to apply to a chat message.
+#[derive(Debug)]
+pub struct TwitchAction {
+    pub channel_login: String,
+    pub user_id: String,
+    pub user_login: String,
+    pub message_id: String,
+    pub action: SuggestedAction,
+    pub result: Result<ModerationResponse, SafeCommsError>,
+}
+
+#[derive(Default)]
+struct UserContext {
+    strikes: Vec<Instant>,
+}
+
+/// Moderates the chat messages arriving on a twitch-irc connection.
+///
+/// Messages are moderated concurrently but actions come out in arrival
+/// order. Each chatter's recent offences are remembered per channel, and
+/// repeat offenders are suggested progressively longer timeouts.
+pub struct TwitchModerator {
+    client: SafeCommsClient,
+    concurrency: usize,
+    users: Arc<Mutex<HashMap<(String, String), UserContext>>>,
+}
+
+impl TwitchModerator {
+    pub fn new(client: SafeCommsClient) -> Self {
+        Self {
+            client,
+            concurrency: DEFAULT_CONCURRENCY,
+            users: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// How many messages may be awaiting a verdict at once.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Consumes the receiver returned by `TwitchIRCClient::new` and yields an
+    /// action for every message that was not clean. Moderation failures are
+    /// reported with [`SuggestedAction::Allow`] so the bot can decide how to
+    /// treat them.
+    pub fn moderate(
+        self,
+        incoming: UnboundedReceiver<ServerMessage>,
+    ) -> impl Stream<Item = TwitchAction> {
+        let client = self.client;
+        let users = self.users;
+
+        let messages = stream::unfold(incoming, |mut incoming| async move {
+            incoming.recv().await.map(|message| (message, incoming))
+        })
+        .filter_map(|message| async move {
+            match message {
+                ServerMessage::Privmsg(message) => Some(message),
+                _ => None,
+            }
+        });
+
+        messages
+            .map(move |message| {
+                let client = client.clone();
+                async move {
+                    let result = client
+                        .moderate_text(&message.message_text, None, None, None, None, None)
+                        .await;
+                    (message, result)
+                }
+            })
+            .buffered(self.concurrency)
+            .filter_map(move |(message, result)| {
+                let action = match &result {
+                    Ok(response) => escalate(&users, &message, SuggestedAction::for_response(response)),
+                    Err(_) => SuggestedAction::Allow,
+                };
+
+                let keep = action != SuggestedAction::Allow || result.is_err();
+                async move {
+                    keep.then(|| TwitchAction {
+                        channel_login: message.channel_login,
+                        user_id: message.sender.id,
+                        user_login: message.sender.login,
+                        message_id: message.message_id,
+                        action,
+                        result,
+                    })
+                }
+            })
+    }
+}
+
+/// Records a strike for a non-clean message and raises the action to a
+/// timeout that doubles with every further strike inside the window.
+fn escalate(
+    users: &Mutex<HashMap<(String, String), UserContext>>,
+    message: &PrivmsgMessage,
+    action: SuggestedAction,
+) -> SuggestedAction {
+    if action == SuggestedAction::Allow {
+        return action;
+    }
+
+    let mut users = users.lock().unwrap_or_else(|e| e.into_inner());
+    let now = Instant::now();
+    users.retain(|_, user| {
+        user.strikes.retain(|&at| now.duration_since(at) < STRIKE_WINDOW);
+        !user.strikes.is_empty()
+    });
+
+    let key = (message.channel_login.clone(), message.sender.id.clone());
+    let user = users.entry(key).or_default();
+    user.strikes.push(now);
+
+    let prior = user.strikes.len() as u32 - 1;
+    if prior == 0 {
+        return action;
+    }
+
+    let timeout = BASE_TIMEOUT
+        .saturating_mul(2u32.saturating_pow(prior - 1))
+        .min(MAX_TIMEOUT);
+    action.max(SuggestedAction::Timeout(timeout))
+}
@@ -0,0 +1,108 @@
+use crate::SafeCommsError;
+
+/// Generous enough for any plan; lower it to match yours.
+const DEFAULT_MAX_TEXT_CHARS: usize = 100_000;
+const DEFAULT_MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+
+/// Limits checked before a request is sent, so input the API would reject
+/// fails without a round trip.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct InputLimits {
+    pub(crate) max_text_chars: usize,
+    pub(crate) max_image_bytes: usize,
+}
+
+impl Default for InputLimits {
+    fn default() -> Self {
+        Self {
+            max_text_chars: DEFAULT_MAX_TEXT_CHARS,
+            max_image_bytes: DEFAULT_MAX_IMAGE_BYTES,
+        }
+    }
+}
+
+impl InputLimits {
+    pub(crate) fn text(&self, field: &'static str, text: &str) -> Result<(), SafeCommsError> {
+        if text.trim().is_empty() {
+            return Err(invalid(field, "must not be empty".to_string()));
+        }
+        let chars = text.chars().count();
+        if chars > self.max_text_chars {
+            return Err(invalid(
+                field,
+                format!("is {} characters, over the limit of {}", chars, self.max_text_chars),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks an image given as a URL or as base64, optionally in a `data:`
+    /// URI.
+    #[cfg(feature = "image-endpoints")]
+    pub(crate) fn image(&self, image: &str) -> Result<(), SafeCommsError> {
+        let image = image.trim();
+        if image.is_empty() {
+            return Err(invalid("image", "must not be empty".to_string()));
+        }
+        if image.starts_with("http://") || image.starts_with("https://") {
+            return Ok(());
+        }
+
+        let encoded = match image.strip_prefix("data:") {
+            Some(uri) => match uri.split_once(";base64,") {
+                Some((_, encoded)) => encoded,
+                None => return Err(invalid("image", "is a data URI without base64".to_string())),
+            },
+            None => image,
+        };
+        let size = base64_decoded_size(encoded).ok_or_else(|| {
+            invalid("image", "is neither an http(s) URL nor valid base64".to_string())
+        })?;
+        self.image_size(size)
+    }
+
+    #[cfg(feature = "multipart")]
+    pub(crate) fn image_bytes(&self, bytes: &[u8]) -> Result<(), SafeCommsError> {
+        if bytes.is_empty() {
+            return Err(invalid("image", "must not be empty".to_string()));
+        }
+        self.image_size(bytes.len())
+    }
+
+    #[cfg(feature = "image-endpoints")]
+    fn image_size(&self, size: usize) -> Result<(), SafeCommsError> {
+        if size > self.max_image_bytes {
+            return Err(invalid(
+                "image",
+                format!("is {} bytes, over the limit of {}", size, self.max_image_bytes),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The size of the data `encoded` decodes to, if it is standard or URL-safe
+/// base64, padded or not.
+#[cfg(feature = "image-endpoints")]
+fn base64_decoded_size(encoded: &str) -> Option<usize> {
+    let encoded: Vec<u8> = encoded
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+    let data = encoded.strip_suffix(b"==").or_else(|| encoded.strip_suffix(b"="));
+    let padded = data.is_some();
+    let data = data.unwrap_or(&encoded);
+
+    let alphabet = |byte: &u8| byte.is_ascii_alphanumeric() || b"+/-_".contains(byte);
+    if data.is_empty() || !data.iter().all(alphabet) {
+        return None;
+    }
+    if data.len() % 4 == 1 || (padded && !encoded.len().is_multiple_of(4)) {
+        return None;
+    }
+    Some(data.len() * 3 / 4)
+}
+
+fn invalid(field: &'static str, reason: String) -> SafeCommsError {
+    SafeCommsError::InvalidInput { field, reason }
+}
@@ -0,0 +1,107 @@
+use crate::{ModerationResponse, SafeCommsError};
+use std::time::Duration;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// What a chat integration should do about a message, from least to most
+/// severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SuggestedAction {
+    Allow,
+    Warn,
+    Delete,
+    /// Delete the message and time the author out.
+    Timeout(Duration),
+}
+
+impl SuggestedAction {
+    pub fn for_response(response: &ModerationResponse) -> Self {
+        if response.is_clean {
+            return SuggestedAction::Allow;
+        }
+        if response.is_bypass_attempt {
+            return SuggestedAction::Delete;
+        }
+
+        match response.severity.as_deref().map(str::to_ascii_lowercase).as_deref() {
+            Some("low") => SuggestedAction::Warn,
+            Some("high") | Some("critical") => SuggestedAction::Timeout(DEFAULT_TIMEOUT),
+            _ => SuggestedAction::Delete,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AttachmentVerdict {
+    pub file_name: String,
+    pub result: Result<ModerationResponse, SafeCommsError>,
+}
+
+/// The outcome of moderating the text of an embed, such as a link preview.
+#[derive(Debug)]
+pub struct EmbedVerdict {
+    pub text: String,
+    pub result: Result<ModerationResponse, SafeCommsError>,
+}
+
+/// The combined outcome of moderating every part of a chat message.
+///
+/// `action` is the most severe action suggested by any part. Attachments and
+/// embeds that could not be moderated count as [`SuggestedAction::Delete`],
+/// so a message is never allowed on the strength of the parts that were
+/// checked alone; inspect their `result` to retry or to treat them otherwise.
+#[derive(Debug)]
+pub struct MessageVerdict {
+    pub text: Option<ModerationResponse>,
+    pub attachments: Vec<AttachmentVerdict>,
+    pub embeds: Vec<EmbedVerdict>,
+    /// How many attachments and embeds could not be moderated.
+    pub failed_parts: usize,
+    pub action: SuggestedAction,
+}
+
+impl MessageVerdict {
+    pub fn new(text: Option<ModerationResponse>, attachments: Vec<AttachmentVerdict>) -> Self {
+        let mut verdict = Self {
+            text,
+            attachments,
+            embeds: Vec::new(),
+            failed_parts: 0,
+            action: SuggestedAction::Allow,
+        };
+        verdict.update();
+        verdict
+    }
+
+    /// Adds the verdicts on the message's embeds.
+    pub fn with_embeds(mut self, embeds: Vec<EmbedVerdict>) -> Self {
+        self.embeds = embeds;
+        self.update();
+        self
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.action == SuggestedAction::Allow
+    }
+
+    fn update(&mut self) {
+        let attachments = self.attachments.iter().filter(|a| a.result.is_err()).count();
+        let embeds = self.embeds.iter().filter(|e| e.result.is_err()).count();
+        self.failed_parts = attachments + embeds;
+        self.action = self.worst_action();
+    }
+
+    fn worst_action(&self) -> SuggestedAction {
+        let failed = (self.failed_parts > 0).then_some(SuggestedAction::Delete);
+        let attachments = self.attachments.iter().filter_map(|a| a.result.as_ref().ok());
+        let embeds = self.embeds.iter().filter_map(|e| e.result.as_ref().ok());
+        self.text
+            .iter()
+            .chain(attachments)
+            .chain(embeds)
+            .map(SuggestedAction::for_response)
+            .chain(failed)
+            .max()
+            .unwrap_or(SuggestedAction::Allow)
+    }
+}
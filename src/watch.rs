@@ -0,0 +1,228 @@
+use crate::files::{FileKind, moderate_file};
+use crate::{Action, Policy, SafeCommsClient, SafeCommsError};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(2);
+/// How long a file must go unmodified before it is considered complete.
+const DEFAULT_SETTLE: Duration = Duration::from_secs(1);
+
+/// The outcome for one file, as written to the results log.
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchResult {
+    pub file: PathBuf,
+    /// Where the file was moved to; `None` if moderating it failed for now
+    /// and it was left in place to be retried.
+    pub moved_to: Option<PathBuf>,
+    pub accepted: bool,
+    pub severity: Option<String>,
+    pub reason: Option<String>,
+    pub error: Option<String>,
+    /// Seconds since the Unix epoch.
+    pub at: u64,
+}
+
+/// Watches a directory for new image and text files, moderates each one
+/// and moves it to an accepted or rejected directory.
+///
+/// Images (`.jpg`, `.png`, `.gif`, `.webp`, `.bmp`) are uploaded and text
+/// files (`.txt`, `.md`) are moderated as text; other files are ignored.
+/// Files are picked up once they have not been modified for a second, so
+/// partially written files are not moderated. Files that fail to moderate
+/// because the API is unreachable, rate limited or failing are left in place
+/// and retried on the next scan; files that can never be moderated, such as
+/// empty or oversized ones, are moved to a failed directory. A file is given
+/// a numbered name if one by its name was already moved to the same place.
+pub struct FolderWatcher {
+    client: SafeCommsClient,
+    input: PathBuf,
+    accepted: PathBuf,
+    rejected: PathBuf,
+    failed: PathBuf,
+    results_log: Option<PathBuf>,
+    policy: Option<Policy>,
+    interval: Duration,
+    settle: Duration,
+}
+
+impl FolderWatcher {
+    /// Watches `input`, moving files to its `accepted`, `rejected` and
+    /// `failed` subdirectories unless configured otherwise.
+    pub fn new(client: SafeCommsClient, input: impl Into<PathBuf>) -> Self {
+        let input = input.into();
+        Self {
+            client,
+            accepted: input.join("accepted"),
+            rejected: input.join("rejected"),
+            failed: input.join("failed"),
+            input,
+            results_log: None,
+            policy: None,
+            interval: DEFAULT_INTERVAL,
+            settle: DEFAULT_SETTLE,
+        }
+    }
+
+    pub fn accepted_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.accepted = dir.into();
+        self
+    }
+
+    pub fn rejected_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.rejected = dir.into();
+        self
+    }
+
+    /// Where files that can never be moderated are moved.
+    pub fn failed_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.failed = dir.into();
+        self
+    }
+
+    /// Appends a JSON line per file to `path`.
+    pub fn results_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.results_log = Some(path.into());
+        self
+    }
+
+    /// Accepts files the policy allows or only flags, instead of only clean
+    /// ones.
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// How often the directory is scanned.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Scans the directory until an error prevents it from being read.
+    pub async fn run(&self) -> Result<(), SafeCommsError> {
+        loop {
+            self.scan_once().await?;
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+
+    /// Moderates every complete file currently in the directory.
+    pub async fn scan_once(&self) -> Result<Vec<WatchResult>, SafeCommsError> {
+        for dir in [&self.accepted, &self.rejected, &self.failed] {
+            tokio::fs::create_dir_all(dir).await.map_err(io_error)?;
+        }
+
+        let mut entries = tokio::fs::read_dir(&self.input).await.map_err(io_error)?;
+        let mut results = Vec::new();
+        while let Some(entry) = entries.next_entry().await.map_err(io_error)? {
+            let path = entry.path();
+            let Some(kind) = FileKind::of(&path) else {
+                continue;
+            };
+            let Ok(metadata) = entry.metadata().await else {
+                continue;
+            };
+            let settled = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age >= self.settle);
+            if !metadata.is_file() || !settled {
+                continue;
+            }
+
+            let result = self.process(&path, kind).await;
+            self.log(&result);
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    async fn process(&self, path: &Path, kind: FileKind) -> WatchResult {
+        let mut result = WatchResult {
+            file: path.to_path_buf(),
+            moved_to: None,
+            accepted: false,
+            severity: None,
+            reason: None,
+            error: None,
+            at: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+        };
+
+        let response = match moderate_file(&self.client, path, kind).await {
+            Ok(response) => response,
+            Err(e) => {
+                result.error = Some(e.to_string());
+                if !e.is_transient() {
+                    move_file(path, &self.failed, &mut result).await;
+                }
+                return result;
+            }
+        };
+
+        result.accepted = match &self.policy {
+            Some(policy) => policy.evaluate(&response) <= Action::Flag,
+            None => response.is_clean,
+        };
+        result.severity = response.severity;
+        result.reason = response.reason;
+
+        let dir = if result.accepted { &self.accepted } else { &self.rejected };
+        move_file(path, dir, &mut result).await;
+        result
+    }
+
+    fn log(&self, result: &WatchResult) {
+        let Some(path) = &self.results_log else {
+            return;
+        };
+
+        let written = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut log| {
+                let line = serde_json::to_string(result).map_err(std::io::Error::other)?;
+                writeln!(log, "{}", line)
+            });
+        if let Err(e) = written {
+            log::warn!("Failed to write to the results log: {}", e);
+        }
+    }
+}
+
+async fn move_file(path: &Path, dir: &Path, result: &mut WatchResult) {
+    match move_into(path, dir).await {
+        Ok(destination) => result.moved_to = Some(destination),
+        Err(e) => result.error = Some(format!("Failed to move file: {}", e)),
+    }
+}
+
+/// Moves `path` into `dir`, numbering its name (`report-1.txt`,
+/// `report-2.txt`, ...) if a file by that name is already there.
+async fn move_into(path: &Path, dir: &Path) -> std::io::Result<PathBuf> {
+    let name = Path::new(path.file_name().unwrap_or_default());
+    let mut destination = dir.join(name);
+    let mut number = 0;
+    while tokio::fs::try_exists(&destination).await? {
+        number += 1;
+        let mut numbered = name.file_stem().unwrap_or_default().to_os_string();
+        numbered.push(format!("-{}", number));
+        if let Some(extension) = name.extension() {
+            numbered.push(".");
+            numbered.push(extension);
+        }
+        destination = dir.join(numbered);
+    }
+
+    tokio::fs::rename(path, &destination).await?;
+    Ok(destination)
+}
+
+fn io_error(e: std::io::Error) -> SafeCommsError {
+    SafeCommsError::ApiError(format!("Failed to read watched directory: {}", e))
+}
@@ -0,0 +1,127 @@
+//! Verification and parsing of webhook callbacks sent by SafeComms.
+//!
+//! Every callback carries a `SafeComms-Signature` header of the form
+//! `t=<unix timestamp>,v1=<hex HMAC-SHA256>`, where the HMAC is computed over
+//! `"<timestamp>.<raw body>"` using the webhook secret.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+#[cfg(feature = "axum")]
+mod axum;
+mod dispatch;
+mod event;
+mod replay;
+
+#[cfg(feature = "axum")]
+pub use self::axum::safecomms_webhook_router;
+pub use dispatch::{EventDispatcher, EventHandler};
+pub use event::{JobCompleted, ProfileUpdated, UsageThreshold, WebhookEvent};
+pub use replay::{InMemoryNonceStore, NonceStore, ReplayGuard};
+
+pub const SIGNATURE_HEADER: &str = "SafeComms-Signature";
+pub const DEFAULT_TOLERANCE: Duration = Duration::from_secs(300);
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum WebhookError {
+    #[error("Signature header has no timestamp")]
+    MissingTimestamp,
+    #[error("Signature header has no signature")]
+    MissingSignature,
+    #[error("Timestamp is outside the tolerance window")]
+    TimestampOutsideTolerance,
+    #[error("Signature does not match")]
+    InvalidSignature,
+    #[error("Webhook has already been received")]
+    Replayed,
+    #[error("Invalid webhook payload: {0}")]
+    InvalidPayload(String),
+}
+
+/// A signature that matched the body, as extracted from a signature header.
+struct VerifiedSignature {
+    timestamp: u64,
+    signature: Vec<u8>,
+}
+
+/// Verifies a webhook callback using the default five minute timestamp
+/// tolerance.
+pub fn verify(signature_header: &str, body: &[u8], secret: &str) -> Result<(), WebhookError> {
+    verify_with_tolerance(signature_header, body, secret, DEFAULT_TOLERANCE)
+}
+
+pub fn verify_with_tolerance(
+    signature_header: &str,
+    body: &[u8],
+    secret: &str,
+    tolerance: Duration,
+) -> Result<(), WebhookError> {
+    verify_signature(signature_header, body, secret, tolerance).map(|_| ())
+}
+
+fn verify_signature(
+    signature_header: &str,
+    body: &[u8],
+    secret: &str,
+    tolerance: Duration,
+) -> Result<VerifiedSignature, WebhookError> {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+
+    for part in signature_header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<u64>().ok(),
+            Some(("v1", value)) => signatures.push(value),
+            _ => {}
+        }
+    }
+
+    let timestamp = timestamp.ok_or(WebhookError::MissingTimestamp)?;
+    if signatures.is_empty() {
+        return Err(WebhookError::MissingSignature);
+    }
+
+    if unix_now().abs_diff(timestamp) > tolerance.as_secs() {
+        return Err(WebhookError::TimestampOutsideTolerance);
+    }
+
+    let mac = signed_payload_mac(timestamp, body, secret);
+
+    // `verify_slice` compares in constant time.
+    signatures
+        .iter()
+        .filter_map(|signature| hex::decode(signature).ok())
+        .find(|signature| mac.clone().verify_slice(signature).is_ok())
+        .map(|signature| VerifiedSignature {
+            timestamp,
+            signature,
+        })
+        .ok_or(WebhookError::InvalidSignature)
+}
+
+/// Builds a signature header for `body`, as SafeComms would. Useful for
+/// testing webhook handlers.
+pub fn sign(body: &[u8], secret: &str, timestamp: u64) -> String {
+    let signature = signed_payload_mac(timestamp, body, secret).finalize().into_bytes();
+    format!("t={},v1={}", timestamp, hex::encode(signature))
+}
+
+fn signed_payload_mac(timestamp: u64, body: &[u8], secret: &str) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -0,0 +1,68 @@
+use super::{ReplayGuard, SIGNATURE_HEADER, WebhookEvent};
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use std::future::Future;
+use std::sync::Arc;
+
+struct WebhookState<F> {
+    secret: String,
+    guard: ReplayGuard,
+    handler: F,
+}
+
+/// A router that accepts SafeComms webhooks on `POST /`.
+///
+/// Deliveries are verified and checked for replays before being parsed and
+/// passed to `handler`. Nest it wherever the webhook URL points:
+///
+/// ```ignore
+/// let app = Router::new().nest(
+///     "/webhooks/safecomms",
+///     safecomms_webhook_router(secret, |event| async move { println!("{:?}", event) }),
+/// );
+/// ```
+pub fn safecomms_webhook_router<F, Fut>(secret: String, handler: F) -> Router
+where
+    F: Fn(WebhookEvent) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let state = Arc::new(WebhookState {
+        secret,
+        guard: ReplayGuard::new(),
+        handler,
+    });
+
+    Router::new().route("/", post(receive::<F, Fut>)).with_state(state)
+}
+
+async fn receive<F, Fut>(
+    State(state): State<Arc<WebhookState<F>>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode
+where
+    F: Fn(WebhookEvent) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let Some(signature) = headers
+        .get(SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return StatusCode::UNAUTHORIZED;
+    };
+
+    if state.guard.verify(signature, &body, &state.secret).is_err() {
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    match WebhookEvent::parse(&body) {
+        Ok(event) => {
+            (state.handler)(event).await;
+            StatusCode::OK
+        }
+        Err(_) => StatusCode::BAD_REQUEST,
+    }
+}
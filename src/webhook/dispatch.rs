@@ -0,0 +1,63 @@
+use super::{
+    JobCompleted, ProfileUpdated, ReplayGuard, UsageThreshold, WebhookError, WebhookEvent,
+};
+use serde_json::Value;
+use std::future::Future;
+
+/// Callbacks for each kind of webhook event. Every method defaults to doing
+/// nothing, so implementations only override the events they care about.
+pub trait EventHandler: Send + Sync {
+    fn on_job_completed(&self, _event: JobCompleted) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    fn on_usage_alert(&self, _event: UsageThreshold) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    fn on_profile_updated(&self, _event: ProfileUpdated) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called for event types this version of the SDK does not recognise.
+    fn on_unknown(&self, _payload: Value) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+}
+
+/// Verifies, parses, and routes webhook deliveries to an [`EventHandler`].
+pub struct EventDispatcher<H> {
+    handler: H,
+    secret: String,
+    guard: ReplayGuard,
+}
+
+impl<H: EventHandler> EventDispatcher<H> {
+    pub fn new(secret: String, handler: H) -> Self {
+        Self {
+            handler,
+            secret,
+            guard: ReplayGuard::new(),
+        }
+    }
+
+    /// Handles a raw delivery: checks its signature and freshness, then passes
+    /// the parsed event to the handler.
+    pub async fn receive(&self, signature_header: &str, body: &[u8]) -> Result<(), WebhookError> {
+        self.guard.verify(signature_header, body, &self.secret)?;
+        let event = WebhookEvent::parse(body)
+            .map_err(|e| WebhookError::InvalidPayload(e.to_string()))?;
+        self.dispatch(event).await;
+        Ok(())
+    }
+
+    /// Routes an already verified event to the matching handler method.
+    pub async fn dispatch(&self, event: WebhookEvent) {
+        match event {
+            WebhookEvent::JobCompleted(event) => self.handler.on_job_completed(event).await,
+            WebhookEvent::UsageThreshold(event) => self.handler.on_usage_alert(event).await,
+            WebhookEvent::ProfileUpdated(event) => self.handler.on_profile_updated(event).await,
+            WebhookEvent::Unknown(payload) => self.handler.on_unknown(payload).await,
+        }
+    }
+}
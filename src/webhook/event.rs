@@ -0,0 +1,59 @@
+use crate::{JobId, ModerationResponse, ProfileId};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// A parsed webhook payload.
+///
+/// Payloads have the shape `{"type": "...", "data": {...}}`. Event types this
+/// version of the SDK does not know about are kept as [`WebhookEvent::Unknown`]
+/// so handlers keep working when new events are introduced.
+#[derive(Debug)]
+pub enum WebhookEvent {
+    JobCompleted(JobCompleted),
+    UsageThreshold(UsageThreshold),
+    ProfileUpdated(ProfileUpdated),
+    Unknown(Value),
+}
+
+#[derive(Deserialize, Debug)]
+pub struct JobCompleted {
+    #[serde(rename = "jobId")]
+    pub job_id: JobId,
+    pub status: String,
+    pub result: Option<Box<ModerationResponse>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UsageThreshold {
+    #[serde(rename = "thresholdPercent")]
+    pub threshold_percent: u32,
+    #[serde(rename = "tokensUsed")]
+    pub tokens_used: i32,
+    #[serde(rename = "tokenLimit")]
+    pub token_limit: Option<i32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ProfileUpdated {
+    #[serde(rename = "profileId")]
+    pub profile_id: ProfileId,
+    pub name: Option<String>,
+}
+
+impl WebhookEvent {
+    pub fn parse(body: &[u8]) -> Result<Self, serde_json::Error> {
+        let payload: Value = serde_json::from_slice(body)?;
+
+        match payload.get("type").and_then(Value::as_str) {
+            Some("job.completed") => Ok(Self::JobCompleted(data(&payload)?)),
+            Some("usage.threshold") => Ok(Self::UsageThreshold(data(&payload)?)),
+            Some("profile.updated") => Ok(Self::ProfileUpdated(data(&payload)?)),
+            _ => Ok(Self::Unknown(payload)),
+        }
+    }
+}
+
+fn data<T: DeserializeOwned>(payload: &Value) -> Result<T, serde_json::Error> {
+    T::deserialize(payload.get("data").unwrap_or(&Value::Null))
+}
@@ -0,0 +1,92 @@
+use super::{DEFAULT_TOLERANCE, WebhookError, unix_now, verify_signature};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Remembers which webhook deliveries have already been accepted.
+///
+/// Implement this on top of a shared store such as Redis when several
+/// instances receive webhooks behind a load balancer.
+pub trait NonceStore: Send + Sync {
+    /// Records `nonce`, returning `false` if it had already been recorded.
+    /// Entries only need to be kept for `window`, after which the timestamp
+    /// check rejects the delivery anyway.
+    fn insert(&self, nonce: &str, timestamp: u64, window: Duration) -> bool;
+}
+
+/// A process-local [`NonceStore`].
+#[derive(Default)]
+pub struct InMemoryNonceStore {
+    seen: Mutex<HashMap<String, u64>>,
+}
+
+impl InMemoryNonceStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl NonceStore for InMemoryNonceStore {
+    fn insert(&self, nonce: &str, timestamp: u64, window: Duration) -> bool {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+
+        let oldest = unix_now().saturating_sub(window.as_secs());
+        seen.retain(|_, &mut seen_at| seen_at >= oldest);
+
+        if seen.contains_key(nonce) {
+            return false;
+        }
+        seen.insert(nonce.to_string(), timestamp);
+        true
+    }
+}
+
+/// Verifies webhook signatures and rejects deliveries that are older than the
+/// window or have been seen before.
+pub struct ReplayGuard<S = InMemoryNonceStore> {
+    store: S,
+    window: Duration,
+}
+
+impl ReplayGuard {
+    pub fn new() -> Self {
+        Self::with_store(InMemoryNonceStore::new())
+    }
+}
+
+impl Default for ReplayGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: NonceStore> ReplayGuard<S> {
+    pub fn with_store(store: S) -> Self {
+        Self {
+            store,
+            window: DEFAULT_TOLERANCE,
+        }
+    }
+
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn verify(
+        &self,
+        signature_header: &str,
+        body: &[u8],
+        secret: &str,
+    ) -> Result<(), WebhookError> {
+        let verified = verify_signature(signature_header, body, secret, self.window)?;
+
+        // The signature is unique per delivery, so it doubles as the nonce.
+        let nonce = hex::encode(&verified.signature);
+        if self.store.insert(&nonce, verified.timestamp, self.window) {
+            Ok(())
+        } else {
+            Err(WebhookError::Replayed)
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::SafeCommsError;
+use bytes::Bytes;
+use reqwest::RequestBuilder;
+use reqwest::header::{CONTENT_TYPE, HeaderValue};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+use std::cell::RefCell;
+
+const JSON: &str = "application/json";
+#[cfg(feature = "msgpack")]
+const MESSAGE_PACK: &str = "application/msgpack";
+#[cfg(feature = "cbor")]
+const CBOR: &str = "application/cbor";
+/// Buffers that have grown past this are not kept for reuse.
+const MAX_KEPT_BUFFER: usize = 64 * 1024;
+
+thread_local! {
+    /// Reused to serialize JSON bodies, so that encoding grows no buffer
+    /// once a thread has encoded a body of the size.
+    static JSON_BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// How request and response bodies are encoded; see
+/// [`SafeCommsClientBuilder::wire_format`](crate::SafeCommsClientBuilder::wire_format).
+///
+/// Binary formats are negotiated: responses are requested in the binary
+/// format with JSON as a fallback, and parsed according to their
+/// `Content-Type`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WireFormat {
+    #[default]
+    Json,
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+impl WireFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            WireFormat::Json => JSON,
+            #[cfg(feature = "msgpack")]
+            WireFormat::MessagePack => MESSAGE_PACK,
+            #[cfg(feature = "cbor")]
+            WireFormat::Cbor => CBOR,
+        }
+    }
+
+    /// The `Accept` header to send, if responses should be negotiated.
+    pub(crate) fn accept(self) -> Option<HeaderValue> {
+        match self {
+            WireFormat::Json => None,
+            #[cfg(feature = "msgpack")]
+            WireFormat::MessagePack => Some(HeaderValue::from_static(
+                "application/msgpack, application/json;q=0.9",
+            )),
+            #[cfg(feature = "cbor")]
+            WireFormat::Cbor => Some(HeaderValue::from_static(
+                "application/cbor, application/json;q=0.9",
+            )),
+        }
+    }
+
+    pub(crate) fn encode<S: Serialize>(self, value: &S) -> Result<Payload, SafeCommsError> {
+        let bytes = match self {
+            WireFormat::Json => encode_json(value)?,
+            #[cfg(feature = "msgpack")]
+            WireFormat::MessagePack => rmp_serde::to_vec_named(value).map_err(wire_error)?.into(),
+            #[cfg(feature = "cbor")]
+            WireFormat::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes).map_err(wire_error)?;
+                bytes.into()
+            }
+        };
+        Ok(Payload {
+            bytes,
+            content_type: self.content_type(),
+        })
+    }
+}
+
+/// Serializes `value` into this thread's buffer and copies out a body of
+/// exactly its size.
+fn encode_json<S: Serialize>(value: &S) -> Result<Bytes, SafeCommsError> {
+    JSON_BUFFER.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        buffer.clear();
+        let result = serde_json::to_writer(&mut *buffer, value);
+        let bytes = result.map(|()| Bytes::copy_from_slice(&buffer));
+        if buffer.capacity() > MAX_KEPT_BUFFER {
+            *buffer = Vec::new();
+        }
+        Ok(bytes?)
+    })
+}
+
+/// An encoded request body, attached for every endpoint attempted without
+/// being copied.
+pub(crate) struct Payload {
+    bytes: Bytes,
+    content_type: &'static str,
+}
+
+impl Payload {
+    pub(crate) fn attach(&self, builder: RequestBuilder) -> RequestBuilder {
+        builder
+            .header(CONTENT_TYPE, HeaderValue::from_static(self.content_type))
+            .body(self.bytes.clone())
+    }
+
+    /// The body as JSON: the body itself when it is JSON, or `value`
+    /// serialized again when the wire format is binary.
+    pub(crate) fn json<S: Serialize>(&self, value: &S) -> Result<Cow<'_, [u8]>, SafeCommsError> {
+        if self.content_type == JSON {
+            Ok(Cow::Borrowed(&self.bytes))
+        } else {
+            Ok(Cow::Owned(serde_json::to_vec(value)?))
+        }
+    }
+}
+
+/// Parses a response body according to its `Content-Type`, as JSON unless it
+/// names a binary format that is enabled.
+pub(crate) fn decode<T: DeserializeOwned>(
+    content_type: Option<&str>,
+    body: Vec<u8>,
+) -> Result<T, SafeCommsError> {
+    let media_type = content_type
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase());
+    match media_type.as_deref() {
+        #[cfg(feature = "msgpack")]
+        Some(MESSAGE_PACK | "application/x-msgpack") => {
+            rmp_serde::from_slice(&body).map_err(wire_error)
+        }
+        #[cfg(feature = "cbor")]
+        Some(CBOR) => ciborium::from_reader(body.as_slice()).map_err(wire_error),
+        _ => parse_json(body),
+    }
+}
+
+#[cfg(not(feature = "simd-json"))]
+fn parse_json<T: DeserializeOwned>(body: Vec<u8>) -> Result<T, SafeCommsError> {
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// simd-json parses in place, so the body is consumed.
+#[cfg(feature = "simd-json")]
+fn parse_json<T: DeserializeOwned>(mut body: Vec<u8>) -> Result<T, SafeCommsError> {
+    Ok(simd_json::serde::from_slice(&mut body)?)
+}
+
+#[cfg(any(feature = "msgpack", feature = "cbor"))]
+fn wire_error(e: impl std::fmt::Display) -> SafeCommsError {
+    SafeCommsError::WireFormatError(e.to_string())
+}